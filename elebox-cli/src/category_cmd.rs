@@ -5,6 +5,10 @@ use std::io::Write;
 use clap::{Args, Subcommand};
 use elebox_core::Category;
 use elebox_core::Handler;
+use elebox_core::ParamTemplate;
+
+use crate::delete_policy;
+use crate::field_cmd::FieldType;
 
 #[derive(Debug, Args)]
 pub struct CategoryCommand {
@@ -26,6 +30,9 @@ enum CategorySubCommand {
     /// Update info of an existing category
     Update(UpdateCategoryArgs),
 
+    /// Set or remove a field the parts of a category are expected to have
+    Param(ParamArgs),
+
     /// Export data
     Export(ExportCategoryArgs),
 }
@@ -58,6 +65,31 @@ struct NewCategoryArgs {
     alias: Option<String>,
 }
 
+#[derive(Debug, Args)]
+struct ParamArgs {
+    /// Category name
+    name: String,
+
+    /// Field name, e.g. Capacitance
+    field: String,
+
+    /// Unit of a parameter, e.g. F
+    #[arg(short = 'u', long = "unit", default_value = "")]
+    unit: String,
+
+    /// Type of the field
+    #[arg(short = 't', long = "type", value_enum, default_value = "parameter")]
+    field_type: FieldType,
+
+    /// A choice of an enum, in order
+    #[arg(short = 'c', long = "choice")]
+    choices: Vec<String>,
+
+    /// Remove the field instead
+    #[arg(short = 'r', long = "remove")]
+    remove: bool,
+}
+
 #[derive(Debug, Args)]
 struct NameCategoryArgs {
     name: String,
@@ -70,6 +102,19 @@ struct DeleteCategoryArgs {
     /// Skip confirm, delete directly
     #[arg(short = 'Y', long = "yes")]
     yes: bool,
+
+    /// Move the parts and sub-categories that use it to another category
+    #[arg(
+        short = 'r',
+        long = "reassign",
+        value_name = "CATEGORY",
+        conflicts_with = "cascade"
+    )]
+    reassign: Option<String>,
+
+    /// Also delete the parts and sub-categories that use it
+    #[arg(short = 'c', long = "cascade")]
+    cascade: bool,
 }
 
 #[derive(Debug, Args)]
@@ -96,7 +141,11 @@ pub fn category_cmd(handler: elebox_core::CategoryHandler, cmd: &CategoryCommand
                     cat.name,
                     unwrap_none(&cat.alias),
                     unwrap_none(&cat.parent),
-                )
+                );
+                // Including the fields of the parents
+                for param in handler.template(&cat.name).unwrap_or_default() {
+                    println!("  {}  {}  {}", param.name, param.field_type, param.unit);
+                }
             }
             Err(err) => println!("Error: {err}"),
         },
@@ -117,7 +166,8 @@ pub fn category_cmd(handler: elebox_core::CategoryHandler, cmd: &CategoryCommand
             }
 
             println!("Deleting '{}'...", args.name);
-            if let Err(err) = handler.delete(&args.name) {
+            let policy = delete_policy(&args.reassign, args.cascade);
+            if let Err(err) = handler.delete_with(&args.name, &policy) {
                 println!("Error: {err}");
             };
         }
@@ -133,14 +183,42 @@ pub fn category_cmd(handler: elebox_core::CategoryHandler, cmd: &CategoryCommand
                 .as_deref()
                 .or_else(|| ori_cat.alias.as_deref());
 
-            let new_item = Category::new(
+            let mut new_item = Category::new(
                 args.new_name.as_deref().unwrap_or(&ori_cat.name),
                 parent.filter(|&s| !s.is_empty()),
                 alias.filter(|&s| !s.is_empty()),
             );
+            new_item.parameters = ori_cat.parameters.clone();
 
             let _ = handler.update(&args.ori_name, &new_item);
         }
+        Some(CategorySubCommand::Param(args)) => {
+            let mut cat = match handler.get(&args.name) {
+                Ok(cat) => cat,
+                Err(err) => {
+                    println!("Error: {err}");
+                    return;
+                }
+            };
+
+            cat.parameters
+                .retain(|p| !p.name.eq_ignore_ascii_case(&args.field));
+            if !args.remove {
+                cat.parameters.push(ParamTemplate {
+                    name: args.field.clone(),
+                    field_type: args.field_type.with(&args.unit, &args.choices),
+                    // Numbers keep the unit in the type
+                    unit: match args.field_type {
+                        FieldType::Parameter => args.unit.clone(),
+                        _ => String::new(),
+                    },
+                });
+            }
+
+            if let Err(err) = handler.update(&args.name, &cat) {
+                println!("Error: {err}");
+            }
+        }
         Some(CategorySubCommand::Export(_args)) => {
             todo!();
         }
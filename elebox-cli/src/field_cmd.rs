@@ -0,0 +1,163 @@
+use clap::{Args, Subcommand, ValueEnum};
+
+use elebox_core::{CustomFieldType, FieldDef, Handler};
+use std::io::stdin;
+use std::io::stdout;
+use std::io::Write;
+
+#[derive(Debug, Args)]
+pub struct FieldCommand {
+    #[clap(subcommand)]
+    command: Option<FieldSubCommand>,
+}
+
+#[derive(Debug, Subcommand)]
+enum FieldSubCommand {
+    /// Define a custom field for all parts
+    New(NewArgs),
+
+    /// Get info about a specific field
+    Get(NameArgs),
+
+    /// Remove a field definition, the values of the parts are kept
+    Delete(DeleteArgs),
+
+    /// Rename a field in all parts and category templates
+    Rename(RenameArgs),
+}
+
+#[derive(Debug, Args)]
+struct NewArgs {
+    /// Field name
+    name: String,
+
+    /// Type of the values
+    #[arg(short = 't', long = "type", value_enum, default_value = "normal")]
+    field_type: FieldType,
+
+    /// Unit of a number, e.g. mm
+    #[arg(short = 'u', long = "unit", default_value = "")]
+    unit: String,
+
+    /// A choice of an enum, in order
+    #[arg(short = 'c', long = "choice")]
+    choices: Vec<String>,
+
+    /// What the field is for
+    #[arg(short = 'd', long = "description")]
+    description: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct NameArgs {
+    name: String,
+}
+
+#[derive(Debug, Args)]
+struct DeleteArgs {
+    name: String,
+
+    /// Skip confirm, delete directly
+    #[arg(short = 'Y', long = "yes")]
+    yes: bool,
+}
+
+#[derive(Debug, Args)]
+struct RenameArgs {
+    name: String,
+    new_name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub(crate) enum FieldType {
+    Normal,
+    Link,
+    Parameter,
+    Number,
+    Integer,
+    Boolean,
+    Date,
+    Enum,
+    Markdown,
+}
+
+impl FieldType {
+    /// The type with the unit of a number and the choices of an enum.
+    pub(crate) fn with(self, unit: &str, choices: &[String]) -> CustomFieldType {
+        match self {
+            FieldType::Normal => CustomFieldType::Normal,
+            FieldType::Link => CustomFieldType::Link,
+            FieldType::Parameter => CustomFieldType::Parameter,
+            FieldType::Number => {
+                CustomFieldType::Number(Some(unit.to_string()).filter(|u| !u.is_empty()))
+            }
+            FieldType::Integer => CustomFieldType::Integer,
+            FieldType::Boolean => CustomFieldType::Boolean,
+            FieldType::Date => CustomFieldType::Date,
+            FieldType::Enum => CustomFieldType::Enum(choices.to_vec()),
+            FieldType::Markdown => CustomFieldType::Markdown,
+        }
+    }
+}
+
+pub fn field_cmd(handler: elebox_core::FieldHandler, cmd: &FieldCommand) {
+    match &cmd.command {
+        Some(FieldSubCommand::New(args)) => {
+            let mut def =
+                FieldDef::new(&args.name, args.field_type.with(&args.unit, &args.choices));
+            def.description = args.description.clone();
+            if let Err(err) = handler.add(&def) {
+                println!("Error: {err}");
+            };
+        }
+        Some(FieldSubCommand::Get(args)) => match handler.get(&args.name) {
+            Ok(def) => {
+                println!(
+                    "Name: {}, Type: {}, Description: {}",
+                    def.name,
+                    def.field_type,
+                    def.description.as_deref().unwrap_or("-none-"),
+                )
+            }
+            Err(err) => println!("Error: {err}"),
+        },
+        Some(FieldSubCommand::Delete(args)) => {
+            // Confirm delete message
+            if !args.yes {
+                println!("Are you sure you want to delete '{}' ?", args.name);
+                print!("This action cannot be undone. [y/N]: ");
+
+                let mut input = String::new();
+                let _ = stdout().flush();
+                stdin().read_line(&mut input).expect("Failed to read input");
+
+                if input.trim_end().to_lowercase() != "y" {
+                    println!("Deletion canceled");
+                    return;
+                }
+            }
+
+            println!("Deleting '{}'...", args.name);
+            if let Err(err) = handler.delete(&args.name) {
+                println!("Error: {err}");
+            };
+        }
+        Some(FieldSubCommand::Rename(args)) => match handler.rename(&args.name, &args.new_name) {
+            Ok(count) => println!("Renamed {} in {} parts", args.name, count),
+            Err(err) => println!("Error: {err}"),
+        },
+        None => match handler.list() {
+            Ok(defs) => {
+                for def in defs {
+                    println!(
+                        "{}  {}  {}",
+                        def.name,
+                        def.field_type,
+                        def.description.as_deref().unwrap_or("-none-"),
+                    );
+                }
+            }
+            Err(err) => println!("Error: {err}"),
+        },
+    }
+}
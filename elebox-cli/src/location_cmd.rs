@@ -0,0 +1,205 @@
+use std::io::stdin;
+use std::io::stdout;
+use std::io::Write;
+
+use clap::{Args, Subcommand};
+use elebox_core::Handler;
+use elebox_core::Location;
+use elebox_core::TreeNode;
+
+use crate::delete_policy;
+
+#[derive(Debug, Args)]
+pub struct LocationCommand {
+    #[clap(subcommand)]
+    command: Option<LocationSubCommand>,
+}
+
+#[derive(Debug, Subcommand)]
+enum LocationSubCommand {
+    /// Add a new location, e.g. a drawer in a cabinet
+    New(NewLocationArgs),
+
+    /// Get info about a specific location
+    Get(NameLocationArgs),
+
+    /// Remove a location from the database
+    Delete(DeleteLocationArgs),
+
+    /// Update info of an existing location
+    Update(UpdateLocationArgs),
+
+    /// List the parts stored in a location and the locations inside it
+    Parts(NameLocationArgs),
+
+    /// Show all locations as a tree
+    Tree,
+}
+
+#[derive(Debug, Args)]
+struct UpdateLocationArgs {
+    ori_name: String,
+
+    #[arg(short = 'n', long = "name")]
+    new_name: Option<String>,
+
+    #[arg(short = 'p', long = "parent")]
+    new_parent: Option<String>,
+
+    #[arg(short = 'a', long = "alias")]
+    new_alias: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct NewLocationArgs {
+    /// Location name
+    name: String,
+
+    /// The location it is in
+    #[arg(short = 'p', long = "parent")]
+    parent: Option<String>,
+
+    /// Alternative name
+    #[arg(short = 'a', long = "alias")]
+    alias: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct NameLocationArgs {
+    name: String,
+}
+
+#[derive(Debug, Args)]
+struct DeleteLocationArgs {
+    name: String,
+
+    /// Skip confirm, delete directly
+    #[arg(short = 'Y', long = "yes")]
+    yes: bool,
+
+    /// Move the parts and locations inside it to another location
+    #[arg(
+        short = 'r',
+        long = "reassign",
+        value_name = "LOCATION",
+        conflicts_with = "cascade"
+    )]
+    reassign: Option<String>,
+
+    /// Also delete the locations inside it, their parts are kept without a location
+    #[arg(short = 'c', long = "cascade")]
+    cascade: bool,
+}
+
+pub fn location_cmd(handler: elebox_core::LocationHandler, cmd: &LocationCommand) {
+    match &cmd.command {
+        Some(LocationSubCommand::New(args)) => {
+            if let Err(err) = handler.add(&Location::new(
+                &args.name,
+                args.parent.as_deref(),
+                args.alias.as_deref(),
+            )) {
+                println!("Error: {err}");
+            };
+        }
+        Some(LocationSubCommand::Get(args)) => match handler.get(&args.name) {
+            Ok(loc) => {
+                println!(
+                    "Name: {}, Alias: {}, Parent: {}",
+                    loc.name,
+                    unwrap_none(&loc.alias),
+                    unwrap_none(&loc.parent),
+                )
+            }
+            Err(err) => println!("Error: {err}"),
+        },
+        Some(LocationSubCommand::Delete(args)) => {
+            // Confirm delete message
+            if !args.yes {
+                println!("Are you sure you want to delete '{}' ?", args.name);
+                print!("This action cannot be undone. [y/N]: ");
+
+                let mut input = String::new();
+                let _ = stdout().flush();
+                stdin().read_line(&mut input).expect("Failed to read input");
+
+                if input.trim_end().to_lowercase() != "y" {
+                    println!("Deletion canceled");
+                    return;
+                }
+            }
+
+            println!("Deleting '{}'...", args.name);
+            let policy = delete_policy(&args.reassign, args.cascade);
+            if let Err(err) = handler.delete_with(&args.name, &policy) {
+                println!("Error: {err}");
+            };
+        }
+        Some(LocationSubCommand::Update(args)) => {
+            let ori_loc = match handler.get(&args.ori_name) {
+                Ok(loc) => loc,
+                Err(err) => {
+                    println!("Error: {err}");
+                    return;
+                }
+            };
+
+            let parent = args.new_parent.as_deref().or(ori_loc.parent.as_deref());
+            let alias = args.new_alias.as_deref().or(ori_loc.alias.as_deref());
+
+            let new_item = Location::new(
+                args.new_name.as_deref().unwrap_or(&ori_loc.name),
+                parent.filter(|&s| !s.is_empty()),
+                alias.filter(|&s| !s.is_empty()),
+            );
+
+            if let Err(err) = handler.update(&args.ori_name, &new_item) {
+                println!("Error: {err}");
+            }
+        }
+        Some(LocationSubCommand::Parts(args)) => match handler.parts_in(&args.name) {
+            Ok(parts) => {
+                for part in parts {
+                    println!(
+                        "{}  {}  {}",
+                        part.name,
+                        part.quantity,
+                        unwrap_none(&part.location),
+                    );
+                }
+            }
+            Err(err) => println!("Error: {err}"),
+        },
+        Some(LocationSubCommand::Tree) => match handler.get_tree() {
+            Ok(tree) => print_tree(&tree, 0),
+            Err(err) => println!("Error: {err}"),
+        },
+        None => match handler.list() {
+            Ok(locations) => {
+                for loc in locations {
+                    println!(
+                        "{}  {}  {}",
+                        loc.name,
+                        unwrap_none(&loc.alias),
+                        unwrap_none(&loc.parent),
+                    );
+                }
+            }
+            Err(err) => println!("Error: {err}"),
+        },
+    }
+}
+
+fn print_tree(nodes: &[TreeNode], depth: usize) {
+    for node in nodes {
+        println!("{}{}", "  ".repeat(depth), node.name);
+        print_tree(&node.children, depth + 1);
+    }
+}
+
+fn unwrap_none(val: &Option<String>) -> String {
+    match val {
+        Some(v) => String::from(v),
+        None => String::from("-none-"),
+    }
+}
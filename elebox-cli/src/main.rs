@@ -1,17 +1,32 @@
 use std::path::PathBuf;
 
-use clap::{Args, Parser, Subcommand};
-use elebox_core::{self, JammDatabase};
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use elebox_core::{
+    self, BulkAddFormat, DeletePolicy, EleboxError, KicadMapping, LedgerEntry, Manager, Marking,
+    PassiveKind, SiValue, ValueQuery,
+};
+use rust_decimal::Decimal;
 
 mod category_cmd;
+mod field_cmd;
+mod location_cmd;
 mod manufacturer_cmd;
+mod order_cmd;
 mod package_cmd;
 mod part_cmd;
+mod project_cmd;
+mod supplier_cmd;
 
 pub use category_cmd::*;
+pub use field_cmd::*;
+pub use location_cmd::*;
 pub use manufacturer_cmd::*;
+pub use order_cmd::*;
 pub use package_cmd::*;
 pub use part_cmd::*;
+pub use project_cmd::*;
+pub use supplier_cmd::*;
 
 #[derive(Parser)]
 #[clap(author, version, about)]
@@ -43,40 +58,511 @@ enum EntityType {
     /// Edit or query categories
     Category(CategoryCommand),
 
+    /// Edit the custom fields shared by all parts
+    Field(FieldCommand),
+
     /// Edit or query packages
     Package(PackageCommand),
 
     /// Edit or query manufacturers
     Mfr(ManufacturerCommand),
 
+    /// Edit or query storage locations
+    Location(LocationCommand),
+
+    /// Edit or query suppliers
+    Supplier(SupplierCommand),
+
+    /// Edit projects and build them from the stock
+    Project(ProjectCommand),
+
+    /// Edit purchase orders and receive them into the stock
+    Order(OrderCommand),
+
     /// Export all data
     Export(PathArgs),
 
     /// Import all data
     Import(PathArgs),
+
+    /// Copy all data into a new SQLite database
+    Migrate(MigrateArgs),
+
+    /// Check the database for broken references and other problems
+    Doctor(DoctorArgs),
+
+    /// List the stock movements
+    Ledger(LedgerArgs),
+
+    /// Export the parts as a KiCad database library
+    KicadDbl(KicadDblArgs),
+
+    /// List the parts at or below their minimum stock by supplier
+    Reorder(ReorderArgs),
+
+    /// Show the value of the stock on hand by category
+    Value(ValueArgs),
+
+    /// Decode the marking of a resistor, capacitor or inductor and find the parts with its value
+    Decode(DecodeArgs),
+
+    /// Find the stocked values nearest to a target, alone or in series and parallel
+    Nearest(NearestArgs),
+}
+
+#[derive(Debug, Args)]
+struct NearestArgs {
+    /// Value like `13.7k`, `4.7uF` or `10uH`
+    #[arg(value_parser = parse_si_value)]
+    target: SiValue,
+
+    /// Kind of component
+    #[arg(short = 't', long = "type", value_enum, default_value = "resistor")]
+    kind: Component,
+
+    /// Largest deviation from the target in percent
+    #[arg(long = "tolerance", default_value = "5")]
+    tolerance: Decimal,
+
+    /// Package name or alias, or the size alone like `0805`
+    #[arg(short = 'p', long = "package")]
+    package: Option<String>,
+
+    /// Number of circuits to build from the stock
+    #[arg(short = 'q', long = "quantity", default_value = "1")]
+    quantity: u16,
+
+    /// Most parts in a combination, 1 for single parts only
+    #[arg(
+        short = 'n',
+        long = "parts",
+        default_value = "3",
+        value_parser = clap::value_parser!(u8).range(1..=3)
+    )]
+    max_parts: u8,
+
+    /// Most values and combinations shown
+    #[arg(long = "limit", default_value = "5")]
+    limit: usize,
+}
+
+fn parse_si_value(value: &str) -> Result<SiValue, String> {
+    SiValue::parse(value).ok_or_else(|| format!("expected a value like 13.7k, got {value}"))
+}
+
+#[derive(Debug, Args)]
+struct DecodeArgs {
+    /// Code like `103`, `4R7`, `1002`, `01C` or `104K`, or colour bands like
+    /// `brown black orange gold`
+    #[arg(required = true)]
+    code: Vec<String>,
+
+    /// Kind of component
+    #[arg(short = 't', long = "type", value_enum, default_value = "resistor")]
+    kind: Component,
+
+    /// Package name or alias, or the size alone like `0805`
+    #[arg(short = 'p', long = "package")]
+    package: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Component {
+    Resistor,
+    Capacitor,
+    Inductor,
+}
+
+impl From<Component> for PassiveKind {
+    fn from(kind: Component) -> Self {
+        match kind {
+            Component::Resistor => PassiveKind::Resistor,
+            Component::Capacitor => PassiveKind::Capacitor,
+            Component::Inductor => PassiveKind::Inductor,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+struct ValueArgs {
+    /// Write the value of each part to a CSV file instead
+    #[arg(short = 'o', long = "output")]
+    output: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct ReorderArgs {
+    /// Only the parts of this supplier, ignoring case
+    #[arg(short = 's', long = "supplier")]
+    supplier: Option<String>,
+
+    /// Write a CSV file for the bulk add of the supplier instead
+    #[arg(short = 'o', long = "output", requires = "supplier")]
+    output: Option<String>,
+
+    /// Column layout of the CSV file
+    #[arg(short = 'f', long = "format", value_enum, default_value = "digikey")]
+    format: CsvFormat,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum CsvFormat {
+    Digikey,
+    Mouser,
+    Lcsc,
+}
+
+impl From<CsvFormat> for BulkAddFormat {
+    fn from(format: CsvFormat) -> Self {
+        match format {
+            CsvFormat::Digikey => BulkAddFormat::DigiKey,
+            CsvFormat::Mouser => BulkAddFormat::Mouser,
+            CsvFormat::Lcsc => BulkAddFormat::Lcsc,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+struct KicadDblArgs {
+    /// Path to the `.kicad_dbl` file, the table is written next to it
+    #[arg(default_value = "elebox.kicad_dbl")]
+    path: String,
+
+    /// YAML file with the KiCad symbol and footprint of each package
+    #[arg(short = 'm', long = "mapping")]
+    mapping: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct LedgerArgs {
+    /// Only the movements of this part
+    part: Option<String>,
+
+    /// From this date, e.g. 2024-01-31
+    #[arg(short = 's', long = "since")]
+    since: Option<NaiveDate>,
+
+    /// Until before this date
+    #[arg(short = 'u', long = "until")]
+    until: Option<NaiveDate>,
+
+    /// Check the quantity of the part against its ledger
+    #[arg(long = "verify", requires = "part")]
+    verify: bool,
+}
+
+#[derive(Debug, Args)]
+struct DoctorArgs {
+    /// Fix the problems that can be fixed without losing data
+    #[arg(long = "repair")]
+    repair: bool,
+}
+
+#[derive(Debug, Args)]
+struct MigrateArgs {
+    /// Path to the SQLite database file
+    #[arg(default_value = "elebox.sqlite")]
+    path: String,
 }
 
 fn main() {
     let cli = Cli::parse();
     let db_path = cli.db_path;
 
-    let part_db = Box::new(JammDatabase::new(&db_path));
-    let pkg_db = Box::new(JammDatabase::new(&db_path));
-    let cat_db = Box::new(JammDatabase::new(&db_path));
-    let mfr_db = Box::new(JammDatabase::new(&db_path));
-    let manager = elebox_core::Manager::new(part_db, pkg_db, cat_db, mfr_db);
+    let manager = match Manager::open(&db_path) {
+        Ok(mgr) => mgr,
+        Err(err) => {
+            println!("Error: {err}");
+            return;
+        }
+    };
 
     let _ = match &cli.entity_type {
         EntityType::Init => manager.init(),
         EntityType::Part(cmd) => Ok(part_cmd(manager.part(), cmd)),
         EntityType::Category(cmd) => Ok(category_cmd(manager.category(), cmd)),
+        EntityType::Field(cmd) => Ok(field_cmd(manager.field(), cmd)),
         EntityType::Mfr(cmd) => Ok(manufacturer_cmd(manager.manufacturer(), cmd)),
         EntityType::Package(cmd) => Ok(package_cmd(manager.package(), cmd)),
+        EntityType::Location(cmd) => Ok(location_cmd(manager.location(), cmd)),
+        EntityType::Supplier(cmd) => Ok(supplier_cmd(manager.supplier(), cmd)),
+        EntityType::Project(cmd) => Ok(project_cmd(manager.project(), cmd)),
+        EntityType::Order(cmd) => Ok(order_cmd(manager.order(), cmd)),
         EntityType::Export(args) => {
             manager.export(&PathBuf::from(args.path.clone()), &args.filetype)
         }
         EntityType::Import(args) => {
             manager.import(&PathBuf::from(args.path.clone()), &args.filetype)
         }
+        EntityType::Migrate(args) => {
+            let res = Manager::open(&args.path)
+                .map_err(EleboxError::from)
+                .and_then(|dst| manager.copy_into(&dst));
+            match &res {
+                Ok(()) => println!("Migrated {} to {}", db_path, args.path),
+                Err(err) => println!("Error: {err}"),
+            }
+            res
+        }
+        EntityType::Doctor(args) => {
+            let res = manager.fsck(args.repair);
+            match &res {
+                Ok(report) => {
+                    for issue in &report.issues {
+                        let status = if issue.repaired { "fixed" } else { "found" };
+                        println!("[{status}] {}", issue.problem);
+                    }
+                    println!(
+                        "{} problems, {} unresolved",
+                        report.issues.len(),
+                        report.unresolved().count()
+                    );
+                }
+                Err(err) => println!("Error: {err}"),
+            }
+            res.map(|_| ())
+        }
+        EntityType::KicadDbl(args) => {
+            let res = match &args.mapping {
+                Some(path) => elebox_core::read_kicad_mapping(&PathBuf::from(path)),
+                None => Ok(KicadMapping::new()),
+            }
+            .and_then(|mapping| manager.export_kicad_dbl(&PathBuf::from(&args.path), &mapping));
+            match &res {
+                Ok(()) => println!("Exported {}", args.path),
+                Err(err) => println!("Error: {err}"),
+            }
+            res
+        }
+        EntityType::Ledger(args) => {
+            let res = ledger_cmd(&manager, args);
+            if let Err(err) = &res {
+                println!("Error: {err}");
+            }
+            res
+        }
+        EntityType::Reorder(args) => {
+            let res = reorder_cmd(&manager, args);
+            if let Err(err) = &res {
+                println!("Error: {err}");
+            }
+            res
+        }
+        EntityType::Value(args) => {
+            let res = value_cmd(&manager, args);
+            if let Err(err) = &res {
+                println!("Error: {err}");
+            }
+            res
+        }
+        EntityType::Decode(args) => {
+            let res = decode_cmd(&manager, args);
+            if let Err(err) = &res {
+                println!("Error: {err}");
+            }
+            res
+        }
+        EntityType::Nearest(args) => {
+            let res = nearest_cmd(&manager, args);
+            if let Err(err) = &res {
+                println!("Error: {err}");
+            }
+            res
+        }
+    };
+}
+
+fn reorder_cmd(manager: &Manager, args: &ReorderArgs) -> Result<(), EleboxError> {
+    let groups: Vec<_> = manager
+        .reorder()?
+        .into_iter()
+        .filter(|group| match (&args.supplier, &group.supplier) {
+            (None, _) => true,
+            (Some(name), Some(supplier)) => supplier.eq_ignore_ascii_case(name),
+            (Some(_), None) => false,
+        })
+        .collect();
+
+    if let Some(path) = &args.output {
+        let items: Vec<_> = groups.into_iter().flat_map(|group| group.items).collect();
+        elebox_core::write_bulk_add(&PathBuf::from(path), &items, args.format.into())?;
+        println!("Exported {} parts to {}", items.len(), path);
+        return Ok(());
+    }
+
+    for group in groups {
+        println!("{}", group.supplier.as_deref().unwrap_or("-none-"));
+        for item in group.items {
+            let price = match item.price {
+                Some(price) => format!("{} {}", price, item.currency.unwrap_or_default()),
+                None => String::new(),
+            };
+            let on_order = match item.on_order {
+                0 => String::new(),
+                n => format!(" (+{n} on order)"),
+            };
+            println!(
+                "  {}   {}   {}/{}{}   order {}   {}",
+                item.part,
+                item.sku.or(item.mfr_no).unwrap_or_default(),
+                item.quantity,
+                item.min_stock,
+                on_order,
+                item.order_quantity,
+                price.trim_end(),
+            );
+        }
+    }
+    Ok(())
+}
+
+fn value_cmd(manager: &Manager, args: &ValueArgs) -> Result<(), EleboxError> {
+    let valuation = manager.valuation()?;
+
+    if let Some(path) = &args.output {
+        elebox_core::write_valuation(&PathBuf::from(path), &valuation)?;
+        println!("Exported {} parts to {}", valuation.parts.len(), path);
+        return Ok(());
+    }
+
+    for category in &valuation.categories {
+        let unpriced = match category.unpriced.len() {
+            0 => String::new(),
+            n => format!("   ({n} without a price)"),
+        };
+        println!(
+            "{}   {}{}",
+            category.category,
+            amounts(&category.totals),
+            unpriced
+        );
+    }
+    println!("Total: {}", amounts(&valuation.totals));
+    Ok(())
+}
+
+fn decode_cmd(manager: &Manager, args: &DecodeArgs) -> Result<(), EleboxError> {
+    let marking = Marking::decode(&args.code.join(" "), args.kind.into())?;
+    match &marking.tolerance {
+        Some(tolerance) => println!("{} ±{}", marking.value, tolerance),
+        None => println!("{}", marking.value),
+    }
+
+    let parts = manager
+        .part()
+        .find_by_marking(&marking, args.package.as_deref())?;
+    if parts.is_empty() {
+        println!(
+            "No parts with {} {}",
+            marking.kind.parameter(),
+            marking.value
+        );
+    }
+    for part in parts {
+        println!(
+            "{}   {}   {}   {}",
+            part.name,
+            part.quantity,
+            part.package.as_deref().unwrap_or("-none-"),
+            part.location.as_deref().unwrap_or("-none-"),
+        );
+    }
+    Ok(())
+}
+
+fn nearest_cmd(manager: &Manager, args: &NearestArgs) -> Result<(), EleboxError> {
+    let query = ValueQuery {
+        tolerance: args.tolerance,
+        package: args.package.clone(),
+        quantity: args.quantity,
+        max_parts: args.max_parts.into(),
+        limit: args.limit,
+        ..ValueQuery::new(args.kind.into(), args.target.clone())
+    };
+    let matches = manager.part().nearest_values(&query)?;
+    if matches.nearest.is_empty() && matches.combinations.is_empty() {
+        println!("Nothing within {}% of {}", args.tolerance, args.target);
+        return Ok(());
+    }
+
+    for found in matches.nearest.iter().chain(&matches.combinations) {
+        let parts: Vec<String> = found
+            .parts
+            .iter()
+            .map(|p| format!("{} x{}", p.part, p.quantity))
+            .collect();
+        let value = match found.parts.len() {
+            1 => found.value.to_string(),
+            _ => format!("{} = {}", found, found.value),
+        };
+        let sign = if found.deviation > Decimal::ZERO {
+            "+"
+        } else {
+            ""
+        };
+        println!(
+            "{}   ({}{}%)   {}",
+            value,
+            sign,
+            found.deviation,
+            parts.join(", ")
+        );
+    }
+    Ok(())
+}
+
+fn ledger_cmd(manager: &Manager, args: &LedgerArgs) -> Result<(), EleboxError> {
+    let handler = manager.ledger();
+    let entries: Vec<LedgerEntry> = match &args.part {
+        Some(name) => handler.list_part(name)?,
+        None => handler.list()?,
     };
+
+    let from = args.since.map_or(i64::MIN, local_timestamp);
+    let to = args.until.map_or(i64::MAX, local_timestamp);
+    for entry in entries {
+        if !(from..to).contains(&entry.timestamp) {
+            continue;
+        }
+        let time = DateTime::from_timestamp(entry.timestamp, 0)
+            .map(|time| {
+                time.with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_default();
+        println!(
+            "{}   {}   {:+}   {}   {}   {}",
+            time,
+            entry.part,
+            entry.delta,
+            entry.reason,
+            entry.project.unwrap_or_default(),
+            entry.note.unwrap_or_default(),
+        );
+    }
+
+    if let (Some(name), true) = (&args.part, args.verify) {
+        handler.verify(name)?;
+        println!("Quantity of {} matches its ledger", name);
+    }
+    Ok(())
+}
+
+/// Unix time of the start of the day in local time.
+fn local_timestamp(date: NaiveDate) -> i64 {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map_or(midnight.and_utc().timestamp(), |time| time.timestamp())
+}
+
+/// Delete policy of the `--reassign` and `--cascade` flags, restrict if none given.
+fn delete_policy(reassign: &Option<String>, cascade: bool) -> DeletePolicy {
+    match (reassign, cascade) {
+        (Some(name), _) => DeletePolicy::Reassign(name.to_string()),
+        (None, true) => DeletePolicy::Cascade,
+        (None, false) => DeletePolicy::Restrict,
+    }
 }
@@ -0,0 +1,193 @@
+use clap::{Args, Subcommand};
+
+use elebox_core::{Handler, OrderLine, PurchaseOrder};
+use std::io::stdin;
+use std::io::stdout;
+use std::io::Write;
+
+#[derive(Debug, Args)]
+pub struct OrderCommand {
+    #[clap(subcommand)]
+    command: Option<OrderSubCommand>,
+}
+
+#[derive(Debug, Subcommand)]
+enum OrderSubCommand {
+    /// Add a new draft purchase order to the database
+    New(NewArgs),
+
+    /// Get info and the lines of a specific order
+    Get(NameArgs),
+
+    /// Remove an order from the database
+    Delete(DeleteArgs),
+
+    /// Set the ordered quantity of a part, 0 removes it
+    Line(LineArgs),
+
+    /// Mark a draft order as placed at the supplier
+    Send(NameArgs),
+
+    /// Receive the parts of a sent order into the stock
+    Receive(ReceiveArgs),
+}
+
+#[derive(Debug, Args)]
+struct NewArgs {
+    /// Order number
+    name: String,
+
+    /// Supplier the parts are ordered from
+    supplier: String,
+
+    /// Additional details about this order
+    #[arg(short = 'n', long = "note")]
+    note: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct NameArgs {
+    name: String,
+}
+
+#[derive(Debug, Args)]
+struct DeleteArgs {
+    name: String,
+
+    /// Skip confirm, delete directly
+    #[arg(short = 'Y', long = "yes")]
+    yes: bool,
+}
+
+#[derive(Debug, Args)]
+struct LineArgs {
+    name: String,
+    part: String,
+    quantity: u16,
+}
+
+#[derive(Debug, Args)]
+struct ReceiveArgs {
+    name: String,
+
+    /// Only this part, everything outstanding if not given
+    #[arg(requires = "quantity")]
+    part: Option<String>,
+
+    /// Quantity of the part received
+    quantity: Option<u16>,
+
+    /// Storage location, the part's location if not given
+    #[arg(short = 'l', long = "location")]
+    location: Option<String>,
+}
+
+pub fn order_cmd(handler: elebox_core::OrderHandler, cmd: &OrderCommand) {
+    match &cmd.command {
+        Some(OrderSubCommand::New(args)) => {
+            let mut order = PurchaseOrder::new(&args.name, &args.supplier, vec![]);
+            order.note = args.note.clone();
+            if let Err(err) = handler.add(&order) {
+                println!("Error: {err}");
+            };
+        }
+        Some(OrderSubCommand::Get(args)) => match handler.get(&args.name) {
+            Ok(order) => {
+                println!(
+                    "Name: {}, Supplier: {}, Status: {}, Note: {}",
+                    order.name,
+                    order.supplier,
+                    order.status,
+                    order.note.as_deref().unwrap_or("-none-"),
+                );
+                for line in order.lines {
+                    println!("  {}  {}/{}", line.part, line.received, line.quantity);
+                }
+            }
+            Err(err) => println!("Error: {err}"),
+        },
+        Some(OrderSubCommand::Delete(args)) => {
+            // Confirm delete message
+            if !args.yes {
+                println!("Are you sure you want to delete '{}' ?", args.name);
+                print!("This action cannot be undone. [y/N]: ");
+
+                let mut input = String::new();
+                let _ = stdout().flush();
+                stdin().read_line(&mut input).expect("Failed to read input");
+
+                if input.trim_end().to_lowercase() != "y" {
+                    println!("Deletion canceled");
+                    return;
+                }
+            }
+
+            println!("Deleting '{}'...", args.name);
+            if let Err(err) = handler.delete(&args.name) {
+                println!("Error: {err}");
+            };
+        }
+        Some(OrderSubCommand::Line(args)) => {
+            let mut order = match handler.get(&args.name) {
+                Ok(order) => order,
+                Err(err) => {
+                    println!("Error: {err}");
+                    return;
+                }
+            };
+
+            let received = order
+                .lines
+                .iter()
+                .find(|line| line.part == args.part)
+                .map_or(0, |line| line.received);
+            order.lines.retain(|line| line.part != args.part);
+            if args.quantity > 0 {
+                let mut line = OrderLine::new(&args.part, args.quantity);
+                line.received = received;
+                order.lines.push(line);
+            }
+
+            if let Err(err) = handler.update(&args.name, &order) {
+                println!("Error: {err}");
+            };
+        }
+        Some(OrderSubCommand::Send(args)) => match handler.send(&args.name) {
+            Ok(()) => println!("Sent {}", args.name),
+            Err(err) => println!("Error: {err}"),
+        },
+        Some(OrderSubCommand::Receive(args)) => {
+            let location = args.location.as_deref();
+            let res = match (&args.part, args.quantity) {
+                (Some(part), Some(quantity)) => {
+                    handler.receive(&args.name, part, quantity, location)
+                }
+                _ => handler.receive_all(&args.name, location),
+            };
+            match res {
+                Ok(()) => println!("Received {}", args.name),
+                Err(err) => println!("Error: {err}"),
+            }
+        }
+        None => match handler.list() {
+            Ok(orders) => {
+                for order in orders {
+                    let outstanding: u32 = order
+                        .lines
+                        .iter()
+                        .map(|line| line.outstanding() as u32)
+                        .sum();
+                    println!(
+                        "{}  {}  {}  {} lines, {} outstanding",
+                        order.name,
+                        order.supplier,
+                        order.status,
+                        order.lines.len(),
+                        outstanding,
+                    );
+                }
+            }
+            Err(err) => println!("Error: {err}"),
+        },
+    }
+}
@@ -8,6 +8,8 @@ use std::io::stdin;
 use std::io::stdout;
 use std::io::Write;
 
+use crate::delete_policy;
+
 #[derive(Debug, Args)]
 pub struct PackageCommand {
     #[clap(subcommand)]
@@ -71,6 +73,19 @@ struct DeleteArgs {
     /// Skip confirm, delete directly
     #[arg(short = 'Y', long = "yes")]
     yes: bool,
+
+    /// Move the parts that use it to another package
+    #[arg(
+        short = 'r',
+        long = "reassign",
+        value_name = "PACKAGE",
+        conflicts_with = "cascade"
+    )]
+    reassign: Option<String>,
+
+    /// Also delete the parts that use it
+    #[arg(short = 'c', long = "cascade")]
+    cascade: bool,
 }
 
 #[derive(Debug, Args)]
@@ -118,7 +133,8 @@ pub fn package_cmd(handler: elebox_core::PackageHandler, cmd: &PackageCommand) {
             }
 
             println!("Deleting '{}'...", args.name);
-            if let Err(err) = handler.delete(&args.name) {
+            let policy = delete_policy(&args.reassign, args.cascade);
+            if let Err(err) = handler.delete_with(&args.name, &policy) {
                 println!("Error: {err}");
             };
         }
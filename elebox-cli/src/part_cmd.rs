@@ -1,7 +1,11 @@
 use std::path::PathBuf;
 
 use clap::{Args, Subcommand};
-use elebox_core::{Handler, Part, Transferable};
+use elebox_core::{
+    sort_by_field, CustomField, CustomFieldType, FieldFilter, Handler, ParamRange, Part, PartQuery,
+    PartSupplier, PriceBreak, StockReason, Transferable,
+};
+use rust_decimal::Decimal;
 use std::io::stdin;
 use std::io::stdout;
 use std::io::Write;
@@ -32,6 +36,18 @@ enum PartSubCommand {
     /// Record the consumption or use of a part, reducing inventory
     Use(UsePartArgs),
 
+    /// Move stock of a part from one location to another
+    Transfer(TransferPartArgs),
+
+    /// Set or remove the offer of a supplier for a part
+    Offer(OfferArgs),
+
+    /// Set or remove a parameter with a unit, e.g. `Capacitance 100nF`
+    Param(ParamArgs),
+
+    /// Find parts by category, package, parameter ranges and custom fields
+    Find(FindArgs),
+
     /// Export data
     Export(BackupArgs),
 
@@ -70,7 +86,7 @@ struct NewPartArgs {
     #[arg(short = 'a', long = "alias")]
     alias: Option<String>,
 
-    /// Storage location
+    /// Storage location, added with `location new`
     #[arg(short = 'l', long = "location")]
     location: Option<String>,
 
@@ -93,7 +109,20 @@ struct NewPartArgs {
     /// Marked with a star
     #[arg(short = 's', long = "starred")]
     starred: bool,
-    // TODO custom field and suppliers
+
+    /// Reorder the part at or below this quantity
+    #[arg(long = "min-stock")]
+    min_stock: Option<u16>,
+
+    /// Quantity to order, the minimum stock by default
+    #[arg(long = "reorder-qty")]
+    reorder_quantity: Option<u16>,
+
+    /// Custom field, e.g. `Capacitance=100nF`. The fields of the category
+    /// template that are not given are asked for
+    #[arg(short = 'f', long = "field", value_name = "NAME=VALUE", value_parser = parse_field)]
+    fields: Vec<(String, String)>,
+    // TODO suppliers
 }
 
 #[derive(Debug, Args)]
@@ -141,6 +170,12 @@ struct UpdatePartArgs {
 
     #[arg(short = 's', long = "starred")]
     starred: Option<bool>,
+
+    #[arg(long = "min-stock")]
+    min_stock: Option<u16>,
+
+    #[arg(long = "reorder-qty")]
+    reorder_quantity: Option<u16>,
     // TODO custom field and suppliers
 }
 
@@ -148,12 +183,130 @@ struct UpdatePartArgs {
 struct AddPartArgs {
     name: String,
     quantity: u16,
+
+    /// Note kept in the stock ledger
+    #[arg(short = 'n', long = "note")]
+    note: Option<String>,
+
+    /// Location to put the parts in, the part's location by default
+    #[arg(short = 'l', long = "location")]
+    location: Option<String>,
 }
 
 #[derive(Debug, Args)]
 struct UsePartArgs {
     name: String,
     quantity: u16,
+
+    /// Note kept in the stock ledger
+    #[arg(short = 'n', long = "note")]
+    note: Option<String>,
+
+    /// Project the parts are used for
+    #[arg(short = 'p', long = "project")]
+    project: Option<String>,
+
+    /// Location to take the parts from, the part's location first by default
+    #[arg(short = 'l', long = "location")]
+    location: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct TransferPartArgs {
+    name: String,
+    quantity: u16,
+
+    /// Location to take the parts from, the part's location by default
+    #[arg(short = 'f', long = "from")]
+    from: Option<String>,
+
+    /// Location to put the parts in, the part's location by default
+    #[arg(short = 't', long = "to")]
+    to: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct OfferArgs {
+    name: String,
+
+    /// Supplier, added with `supplier new`
+    supplier: String,
+
+    /// Order number at the supplier
+    #[arg(short = 'k', long = "sku")]
+    sku: Option<String>,
+
+    /// URL of the part at the supplier
+    #[arg(short = 'l', long = "link")]
+    link: Option<String>,
+
+    /// Minimum order quantity
+    #[arg(short = 'm', long = "min-order")]
+    min_order: Option<u32>,
+
+    /// Sold in multiples of it, e.g. a reel
+    #[arg(short = 'p', long = "pack-size")]
+    pack_size: Option<u32>,
+
+    /// Currency code of the prices, e.g. USD
+    #[arg(short = 'c', long = "currency")]
+    currency: Option<String>,
+
+    /// Unit price from a quantity on, e.g. `100:0.042`, replaces all breaks
+    #[arg(short = 'b', long = "price-break", value_name = "QTY:PRICE", value_parser = parse_price_break)]
+    price_breaks: Vec<PriceBreak>,
+
+    #[arg(short = 'n', long = "note")]
+    note: Option<String>,
+
+    /// Remove the offer instead
+    #[arg(short = 'r', long = "remove")]
+    remove: bool,
+}
+
+#[derive(Debug, Args)]
+struct ParamArgs {
+    name: String,
+
+    /// Parameter name, e.g. Capacitance
+    param: String,
+
+    /// Value with a unit, e.g. `4k7`, `0.1uF` or `16V`
+    #[arg(required_unless_present = "remove")]
+    value: Option<String>,
+
+    /// Remove the parameter instead
+    #[arg(short = 'r', long = "remove")]
+    remove: bool,
+}
+
+#[derive(Debug, Args)]
+struct FindArgs {
+    /// The category or any of its subcategories
+    #[arg(short = 'c', long = "category")]
+    category: Option<String>,
+
+    /// Package name or alias
+    #[arg(short = 'p', long = "package")]
+    package: Option<String>,
+
+    /// Conditions on parameters, e.g. `Capacitance>=10uF`, `Voltage<=50V` or
+    /// `Resistance=1k..10k`
+    #[arg(value_name = "CONDITION", value_parser = parse_param_range)]
+    parameters: Vec<ParamRange>,
+
+    /// Conditions on custom fields, e.g. `Pins>=8`, `Tested=yes`,
+    /// `Status!=Obsolete` or `Notes~smd`
+    #[arg(short = 'f', long = "field", value_parser = parse_field_filter)]
+    fields: Vec<FieldFilter>,
+
+    /// Sort the parts by a custom field
+    #[arg(long = "sort", value_name = "FIELD")]
+    sort: Option<String>,
+
+    /// Sort in descending order
+    #[arg(long = "desc", requires = "sort")]
+    descending: bool,
 }
 
 #[derive(Debug, Args)]
@@ -180,6 +333,37 @@ pub fn part_cmd(handler: elebox_core::PartHandler, cmd: &PartCommand) {
     match &cmd.command {
         Some(sub_cmd) => match sub_cmd {
             PartSubCommand::New(args) => {
+                let mut custom_fields: Vec<CustomField> = args
+                    .fields
+                    .iter()
+                    .map(|(name, value)| CustomField {
+                        field_type: CustomFieldType::Normal,
+                        name: name.clone(),
+                        value: value.clone(),
+                    })
+                    .collect();
+                for param in handler.template(&args.category).unwrap_or_default() {
+                    if custom_fields
+                        .iter()
+                        .any(|f| f.name.eq_ignore_ascii_case(&param.name))
+                    {
+                        continue;
+                    }
+
+                    match param.unit.as_str() {
+                        "" => print!("{}: ", param.name),
+                        unit => print!("{} ({}): ", param.name, unit),
+                    }
+                    let mut input = String::new();
+                    let _ = stdout().flush();
+                    stdin().read_line(&mut input).expect("Failed to read input");
+                    custom_fields.push(CustomField {
+                        field_type: param.field_type,
+                        name: param.name,
+                        value: input.trim().to_string(),
+                    });
+                }
+
                 let part = Part {
                     name: args.name.clone(),
                     quantity: args.quantity,
@@ -195,8 +379,11 @@ pub fn part_cmd(handler: elebox_core::PartHandler, cmd: &PartCommand) {
                     product_link: args.product.clone(),
                     image_link: args.image.clone(),
                     starred: args.starred,
-                    custom_fields: vec![], // TODO
-                    suppliers: vec![],     // TODO
+                    custom_fields,
+                    suppliers: vec![], // TODO
+                    stock: vec![],
+                    min_stock: args.min_stock,
+                    reorder_quantity: args.reorder_quantity,
                 };
 
                 let res = handler.add(&part);
@@ -216,6 +403,12 @@ pub fn part_cmd(handler: elebox_core::PartHandler, cmd: &PartCommand) {
                         Alias: {}\n\
                         Description: {}\n\
                         Location: {}\n\
+                        Stock: {}\n\
+                        Suppliers: {}\n\
+                        Parameters: {}\n\
+                        Fields: {}\n\
+                        Min Stock: {}\n\
+                        Reorder Quantity: {}\n\
                         Manufacturer: {}\n\
                         Manufacturer Number: {}\n\
                         Datasheet Link: {}\n\
@@ -230,6 +423,12 @@ pub fn part_cmd(handler: elebox_core::PartHandler, cmd: &PartCommand) {
                         unwrap_none(&part.alias),
                         unwrap_none(&part.description),
                         unwrap_none(&part.location),
+                        stock_lines(&part),
+                        supplier_lines(&part),
+                        parameter_lines(&part),
+                        field_lines(&part),
+                        unwrap_none(&part.min_stock.map(|q| q.to_string())),
+                        unwrap_none(&part.reorder_quantity.map(|q| q.to_string())),
                         unwrap_none(&part.mfr),
                         unwrap_none(&part.mfr_no),
                         unwrap_none(&part.datasheet_link),
@@ -363,21 +562,138 @@ pub fn part_cmd(handler: elebox_core::PartHandler, cmd: &PartCommand) {
                     starred,
                     custom_fields: ori_part.custom_fields.clone(), // TODO
                     suppliers: ori_part.suppliers.clone(),         // TODO
+                    stock: ori_part.stock.clone(),
+                    min_stock: args.min_stock.or(ori_part.min_stock),
+                    reorder_quantity: args.reorder_quantity.or(ori_part.reorder_quantity),
                 };
 
-                let _ = handler.update(&args.ori_name, &new_item);
+                if let Err(err) = handler.update(&args.ori_name, &new_item) {
+                    println!("ERR: {err}");
+                }
             }
             PartSubCommand::Restock(args) => {
-                if let Err(err) = handler.update_part_quantity(&args.name, args.quantity as i16) {
+                if let Err(err) = handler.move_stock(
+                    &args.name,
+                    args.quantity as i16,
+                    args.location.as_deref(),
+                    StockReason::Restock,
+                    args.note.as_deref(),
+                    None,
+                ) {
                     println!("ERR: {err}");
                 }
             }
             PartSubCommand::Use(args) => {
                 let q = args.quantity as i16 * -1;
-                if let Err(err) = handler.update_part_quantity(&args.name, q) {
+                if let Err(err) = handler.move_stock(
+                    &args.name,
+                    q,
+                    args.location.as_deref(),
+                    StockReason::Use,
+                    args.note.as_deref(),
+                    args.project.as_deref(),
+                ) {
+                    println!("ERR: {err}");
+                }
+            }
+            PartSubCommand::Transfer(args) => {
+                if let Err(err) = handler.transfer(
+                    &args.name,
+                    args.quantity,
+                    args.from.as_deref(),
+                    args.to.as_deref(),
+                ) {
                     println!("ERR: {err}");
                 }
             }
+            PartSubCommand::Offer(args) => {
+                let mut part = match handler.get(&args.name) {
+                    Ok(part) => part,
+                    Err(err) => {
+                        println!("Error: {err}");
+                        return;
+                    }
+                };
+
+                let same = |s: &PartSupplier| s.supplier.eq_ignore_ascii_case(&args.supplier);
+                let index = match part.suppliers.iter().position(same) {
+                    Some(index) => index,
+                    None => {
+                        part.suppliers.push(PartSupplier::new(&args.supplier));
+                        part.suppliers.len() - 1
+                    }
+                };
+
+                if args.remove {
+                    part.suppliers.remove(index);
+                } else {
+                    let offer = &mut part.suppliers[index];
+                    offer.sku = args.sku.clone().or(offer.sku.take());
+                    offer.link = args.link.clone().or(offer.link.take());
+                    offer.min_order = args.min_order.or(offer.min_order);
+                    offer.pack_size = args.pack_size.or(offer.pack_size);
+                    offer.currency = args.currency.clone().or(offer.currency.take());
+                    offer.note = args.note.clone().or(offer.note.take());
+                    if !args.price_breaks.is_empty() {
+                        offer.price_breaks = args.price_breaks.clone();
+                    }
+                }
+
+                if let Err(err) = handler.update(&args.name, &part) {
+                    println!("Error: {err}");
+                }
+            }
+            PartSubCommand::Param(args) => {
+                let mut part = match handler.get(&args.name) {
+                    Ok(part) => part,
+                    Err(err) => {
+                        println!("Error: {err}");
+                        return;
+                    }
+                };
+
+                let same = |f: &CustomField| {
+                    f.field_type == CustomFieldType::Parameter
+                        && f.name.eq_ignore_ascii_case(&args.param)
+                };
+                part.custom_fields.retain(|f| !same(f));
+                if let (Some(value), false) = (&args.value, args.remove) {
+                    part.custom_fields.push(CustomField {
+                        field_type: CustomFieldType::Parameter,
+                        name: args.param.clone(),
+                        value: value.clone(),
+                    });
+                }
+
+                if let Err(err) = handler.update(&args.name, &part) {
+                    println!("Error: {err}");
+                }
+            }
+            PartSubCommand::Find(args) => {
+                let query = PartQuery {
+                    category: args.category.clone(),
+                    package: args.package.clone(),
+                    parameters: args.parameters.clone(),
+                    fields: args.fields.clone(),
+                };
+                match handler.find(&query) {
+                    Ok(mut parts) => {
+                        if let Some(field) = &args.sort {
+                            sort_by_field(&mut parts, field, args.descending);
+                        }
+                        for part in parts {
+                            println!(
+                                "{}   {}   {}   {}",
+                                part.name,
+                                part.quantity,
+                                unwrap_none(&part.package),
+                                parameter_lines(&part),
+                            );
+                        }
+                    }
+                    Err(err) => println!("Error: {err}"),
+                }
+            }
             PartSubCommand::Export(args) => {
                 todo!();
                 // match handler.export(&PathBuf::from(&args.path)) {
@@ -409,3 +725,103 @@ fn unwrap_none(val: &Option<String>) -> String {
         None => String::from("-none-"),
     }
 }
+
+/// The stock by location, e.g. `Storeroom x2000, Bench x35`.
+fn stock_lines(part: &Part) -> String {
+    let lines: Vec<String> = part
+        .stock
+        .iter()
+        .map(|line| format!("{} x{}", unwrap_none(&line.location), line.quantity))
+        .collect();
+    if lines.is_empty() {
+        String::from("-none-")
+    } else {
+        lines.join(", ")
+    }
+}
+
+/// The parameters, e.g. `Capacitance 100nF, Voltage 16V`.
+fn parameter_lines(part: &Part) -> String {
+    let lines: Vec<String> = part
+        .custom_fields
+        .iter()
+        .filter(|f| f.field_type == CustomFieldType::Parameter)
+        .map(|f| format!("{} {}", f.name, f.value))
+        .collect();
+    if lines.is_empty() {
+        String::from("-none-")
+    } else {
+        lines.join(", ")
+    }
+}
+
+/// The other custom fields, e.g. `Pins 48, Status NRND`.
+fn field_lines(part: &Part) -> String {
+    let lines: Vec<String> = part
+        .custom_fields
+        .iter()
+        .filter(|f| f.field_type != CustomFieldType::Parameter)
+        .map(|f| format!("{} {}", f.name, f.value))
+        .collect();
+    if lines.is_empty() {
+        String::from("-none-")
+    } else {
+        lines.join(", ")
+    }
+}
+
+/// The offers of the suppliers, e.g. `Mouser 358-SC09147 (1+ 0.80 USD)`.
+fn supplier_lines(part: &Part) -> String {
+    let lines: Vec<String> = part
+        .suppliers
+        .iter()
+        .map(|offer| {
+            let currency = offer.currency.as_deref().unwrap_or("");
+            let prices: Vec<String> = offer
+                .price_breaks
+                .iter()
+                .map(|b| format!("{}+ {} {}", b.quantity, b.price, currency))
+                .collect();
+            format!(
+                "{} {} ({})",
+                offer.supplier,
+                unwrap_none(&offer.sku),
+                prices.join(", ").trim_end()
+            )
+        })
+        .collect();
+    if lines.is_empty() {
+        String::from("-none-")
+    } else {
+        lines.join(", ")
+    }
+}
+
+/// A price break given as `QTY:PRICE`.
+fn parse_price_break(value: &str) -> Result<PriceBreak, String> {
+    let (quantity, price) = value
+        .split_once(':')
+        .ok_or_else(|| format!("expected QTY:PRICE, got {value}"))?;
+    Ok(PriceBreak {
+        quantity: quantity.trim().parse().map_err(|err| format!("{err}"))?,
+        price: price
+            .trim()
+            .parse::<Decimal>()
+            .map_err(|err| format!("{err}"))?,
+    })
+}
+
+fn parse_field(value: &str) -> Result<(String, String), String> {
+    let (name, value) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=VALUE, got {value}"))?;
+    Ok((name.trim().to_string(), value.trim().to_string()))
+}
+
+fn parse_field_filter(value: &str) -> Result<FieldFilter, String> {
+    FieldFilter::parse(value).map_err(|err| err.to_string())
+}
+
+fn parse_param_range(value: &str) -> Result<ParamRange, String> {
+    ParamRange::parse(value).map_err(|err| err.to_string())
+}
@@ -0,0 +1,300 @@
+use clap::{Args, Subcommand};
+
+use elebox_core::{Amount, BomItem, BomMatch, Handler, Project};
+use std::io::stdin;
+use std::io::stdout;
+use std::io::Write;
+use std::path::PathBuf;
+
+#[derive(Debug, Args)]
+pub struct ProjectCommand {
+    #[clap(subcommand)]
+    command: Option<ProjectSubCommand>,
+}
+
+#[derive(Debug, Subcommand)]
+enum ProjectSubCommand {
+    /// Add a new project to the database
+    New(NewArgs),
+
+    /// Get info and the BOM of a specific project
+    Get(NameArgs),
+
+    /// Remove a project from the database
+    Delete(DeleteArgs),
+
+    /// Set the quantity per board of a part in the BOM, 0 removes it
+    Bom(BomArgs),
+
+    /// Show how many boards can be built and the parts that are short
+    Check(CheckArgs),
+
+    /// Take the parts of the boards from the stock
+    Build(BuildArgs),
+
+    /// Show the cost of the parts per board and for a build
+    Cost(CostArgs),
+
+    /// Match a KiCad BOM CSV or XML netlist against the parts
+    ImportKicad(ImportKicadArgs),
+}
+
+#[derive(Debug, Args)]
+struct NewArgs {
+    /// Project name
+    name: String,
+
+    /// Additional details about this project
+    #[arg(short = 'd', long = "description")]
+    description: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct NameArgs {
+    name: String,
+}
+
+#[derive(Debug, Args)]
+struct DeleteArgs {
+    name: String,
+
+    /// Skip confirm, delete directly
+    #[arg(short = 'Y', long = "yes")]
+    yes: bool,
+}
+
+#[derive(Debug, Args)]
+struct BomArgs {
+    name: String,
+    part: String,
+
+    /// Quantity per board
+    quantity: u16,
+}
+
+#[derive(Debug, Args)]
+struct CheckArgs {
+    name: String,
+
+    /// Number of boards to check the shortages for
+    #[arg(default_value_t = 1)]
+    boards: u16,
+}
+
+#[derive(Debug, Args)]
+struct BuildArgs {
+    name: String,
+
+    /// Number of boards
+    boards: u16,
+}
+
+#[derive(Debug, Args)]
+struct CostArgs {
+    name: String,
+
+    /// Number of boards, the price breaks of the whole build apply
+    #[arg(default_value_t = 1)]
+    boards: u16,
+
+    /// Write the cost of each part to a CSV file instead
+    #[arg(short = 'o', long = "output")]
+    output: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct ImportKicadArgs {
+    /// Path to the BOM CSV or XML netlist
+    path: String,
+
+    /// Save the matched lines as the BOM of a new project
+    #[arg(short = 's', long = "save", value_name = "PROJECT")]
+    save: Option<String>,
+}
+
+pub fn project_cmd(handler: elebox_core::ProjectHandler, cmd: &ProjectCommand) {
+    match &cmd.command {
+        Some(ProjectSubCommand::New(args)) => {
+            let project = Project::new(&args.name, args.description.as_deref(), vec![]);
+            if let Err(err) = handler.add(&project) {
+                println!("Error: {err}");
+            };
+        }
+        Some(ProjectSubCommand::Get(args)) => match handler.get(&args.name) {
+            Ok(project) => {
+                println!(
+                    "Name: {}, Description: {}",
+                    project.name,
+                    project.description.as_deref().unwrap_or("-none-"),
+                );
+                for item in project.bom {
+                    println!("  {}  x{}", item.part, item.quantity);
+                }
+            }
+            Err(err) => println!("Error: {err}"),
+        },
+        Some(ProjectSubCommand::Delete(args)) => {
+            // Confirm delete message
+            if !args.yes {
+                println!("Are you sure you want to delete '{}' ?", args.name);
+                print!("This action cannot be undone. [y/N]: ");
+
+                let mut input = String::new();
+                let _ = stdout().flush();
+                stdin().read_line(&mut input).expect("Failed to read input");
+
+                if input.trim_end().to_lowercase() != "y" {
+                    println!("Deletion canceled");
+                    return;
+                }
+            }
+
+            println!("Deleting '{}'...", args.name);
+            if let Err(err) = handler.delete(&args.name) {
+                println!("Error: {err}");
+            };
+        }
+        Some(ProjectSubCommand::Bom(args)) => {
+            let mut project = match handler.get(&args.name) {
+                Ok(project) => project,
+                Err(err) => {
+                    println!("Error: {err}");
+                    return;
+                }
+            };
+
+            project.bom.retain(|item| item.part != args.part);
+            if args.quantity > 0 {
+                project.bom.push(BomItem::new(&args.part, args.quantity));
+            }
+
+            if let Err(err) = handler.update(&args.name, &project) {
+                println!("Error: {err}");
+            };
+        }
+        Some(ProjectSubCommand::Check(args)) => {
+            let res = handler.buildable(&args.name).and_then(|buildable| {
+                let shortages = handler.shortages(&args.name, args.boards)?;
+                Ok((buildable, shortages))
+            });
+            match res {
+                Ok((buildable, shortages)) => {
+                    println!("Can build {} boards of {}", buildable, args.name);
+                    for shortage in shortages {
+                        println!(
+                            "Short of {}: {} required, {} available",
+                            shortage.part, shortage.required, shortage.available
+                        );
+                    }
+                }
+                Err(err) => println!("Error: {err}"),
+            }
+        }
+        Some(ProjectSubCommand::Build(args)) => match handler.build(&args.name, args.boards) {
+            Ok(()) => println!("Built {} x{}", args.name, args.boards),
+            Err(err) => println!("Error: {err}"),
+        },
+        Some(ProjectSubCommand::Cost(args)) => {
+            let cost = match handler.cost(&args.name, args.boards) {
+                Ok(cost) => cost,
+                Err(err) => {
+                    println!("Error: {err}");
+                    return;
+                }
+            };
+
+            if let Some(path) = &args.output {
+                match elebox_core::write_bom_cost(&PathBuf::from(path), &cost) {
+                    Ok(()) => println!("Exported {} parts to {}", cost.lines.len(), path),
+                    Err(err) => println!("Error: {err}"),
+                }
+                return;
+            }
+
+            for line in &cost.lines {
+                let price = match (&line.unit_price, &line.board_cost) {
+                    (Some(price), Some(board_cost)) => format!(
+                        "{} x {} = {} {}",
+                        line.per_board,
+                        price,
+                        board_cost,
+                        line.currency.as_deref().unwrap_or_default()
+                    ),
+                    _ => String::from("-no price-"),
+                };
+                println!(
+                    "  {}   {}   {}   {}",
+                    line.part,
+                    line.supplier.as_deref().unwrap_or("-none-"),
+                    line.required,
+                    price.trim_end()
+                );
+            }
+            println!("Per board: {}", amounts(&cost.per_board));
+            println!("{} boards: {}", cost.boards, amounts(&cost.total));
+            if !cost.unpriced.is_empty() {
+                println!("Without a price: {}", cost.unpriced.join(", "));
+            }
+        }
+        Some(ProjectSubCommand::ImportKicad(args)) => {
+            let report = match handler.import_kicad(&PathBuf::from(&args.path)) {
+                Ok(report) => report,
+                Err(err) => {
+                    println!("Error: {err}");
+                    return;
+                }
+            };
+
+            for line in &report.lines {
+                let status = match &line.status {
+                    BomMatch::Matched(part) => format!("matched {part}"),
+                    BomMatch::Ambiguous(parts) => format!("ambiguous {}", parts.join(", ")),
+                    BomMatch::Missing => String::from("missing"),
+                };
+                println!(
+                    "{}  {}  x{}  {}",
+                    line.line.references.join(","),
+                    line.line.value,
+                    line.line.quantity,
+                    status
+                );
+            }
+            println!(
+                "{} matched, {} ambiguous, {} missing",
+                report.matched().count(),
+                report.ambiguous().count(),
+                report.missing().count()
+            );
+
+            if let Some(name) = &args.save {
+                if let Err(err) = handler.add(&report.to_project(name, None)) {
+                    println!("Error: {err}");
+                }
+            }
+        }
+        None => match handler.list() {
+            Ok(projects) => {
+                for project in projects {
+                    println!(
+                        "{}  {}",
+                        project.name,
+                        project.description.as_deref().unwrap_or("-none-"),
+                    );
+                }
+            }
+            Err(err) => println!("Error: {err}"),
+        },
+    }
+}
+
+/// Amounts in different currencies, e.g. `1.20 USD, 3.00 EUR`.
+pub(crate) fn amounts(amounts: &[Amount]) -> String {
+    if amounts.is_empty() {
+        return String::from("0");
+    }
+    amounts
+        .iter()
+        .map(|amount| amount.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
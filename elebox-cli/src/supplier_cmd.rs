@@ -0,0 +1,165 @@
+use clap::{Args, Subcommand};
+
+use elebox_core::Handler;
+
+use elebox_core::Supplier;
+use std::io::stdin;
+use std::io::stdout;
+use std::io::Write;
+
+use crate::delete_policy;
+
+#[derive(Debug, Args)]
+pub struct SupplierCommand {
+    #[clap(subcommand)]
+    command: Option<SupplierSubCommand>,
+}
+
+#[derive(Debug, Subcommand)]
+enum SupplierSubCommand {
+    /// Add a new supplier to the database
+    New(NewArgs),
+
+    /// Get info about a specific supplier
+    Get(NameArgs),
+
+    /// Remove a supplier from the database
+    Delete(DeleteArgs),
+
+    /// Update info of an existing supplier
+    Update(UpdateArgs),
+}
+
+#[derive(Debug, Args)]
+struct UpdateArgs {
+    ori_name: String,
+
+    #[arg(short = 'n', long = "name")]
+    new_name: Option<String>,
+
+    #[arg(short = 'a', long = "alias")]
+    new_alias: Option<String>,
+
+    #[arg(short = 'l', long = "link")]
+    new_link: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct NewArgs {
+    /// Supplier name
+    name: String,
+
+    /// Alternative name
+    #[arg(short = 'a', long = "alias")]
+    alias: Option<String>,
+
+    /// URL of the supplier
+    #[arg(short = 'l', long = "link")]
+    link: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct NameArgs {
+    name: String,
+}
+
+#[derive(Debug, Args)]
+struct DeleteArgs {
+    name: String,
+
+    /// Skip confirm, delete directly
+    #[arg(short = 'Y', long = "yes")]
+    yes: bool,
+
+    /// Move its offers of parts and its orders to another supplier
+    #[arg(
+        short = 'r',
+        long = "reassign",
+        value_name = "SUPPLIER",
+        conflicts_with = "cascade"
+    )]
+    reassign: Option<String>,
+
+    /// Also remove its offers from the parts and delete its received orders
+    #[arg(short = 'c', long = "cascade")]
+    cascade: bool,
+}
+
+pub fn supplier_cmd(handler: elebox_core::SupplierHandler, cmd: &SupplierCommand) {
+    match &cmd.command {
+        Some(SupplierSubCommand::New(args)) => {
+            if let Err(err) = handler.add(&elebox_core::Supplier::new(
+                &args.name,
+                args.alias.as_deref(),
+                args.link.as_deref(),
+            )) {
+                println!("Error: {err}");
+            };
+        }
+        Some(SupplierSubCommand::Get(args)) => match handler.get(&args.name) {
+            Ok(sup) => {
+                println!(
+                    "Name: {}, Alias: {}, Link: {}",
+                    sup.name,
+                    unwrap_none(&sup.alias),
+                    unwrap_none(&sup.url),
+                )
+            }
+            Err(err) => println!("Error: {err}"),
+        },
+        Some(SupplierSubCommand::Delete(args)) => {
+            // Confirm delete message
+            if !args.yes {
+                println!("Are you sure you want to delete '{}' ?", args.name);
+                print!("This action cannot be undone. [y/N]: ");
+
+                let mut input = String::new();
+                let _ = stdout().flush();
+                stdin().read_line(&mut input).expect("Failed to read input");
+
+                if input.trim_end().to_lowercase() != "y" {
+                    println!("Deletion canceled");
+                    return;
+                }
+            }
+
+            println!("Deleting '{}'...", args.name);
+            let policy = delete_policy(&args.reassign, args.cascade);
+            if let Err(err) = handler.delete_with(&args.name, &policy) {
+                println!("Error: {err}");
+            };
+        }
+        Some(SupplierSubCommand::Update(args)) => {
+            let ori_sup = handler.get(&args.ori_name).expect("Supplier not found");
+
+            let link = args.new_link.as_deref().or(ori_sup.url.as_deref());
+            let alias = args.new_alias.as_deref().or(ori_sup.alias.as_deref());
+
+            let new_item = Supplier::new(
+                args.new_name.as_deref().unwrap_or(&ori_sup.name),
+                alias.filter(|&s| !s.is_empty()),
+                link.filter(|&s| !s.is_empty()),
+            );
+
+            let _ = handler.update(&args.ori_name, &new_item);
+        }
+        None => {
+            let sups = handler.list().unwrap();
+            for sup in sups {
+                println!(
+                    "{}  {}  {}",
+                    sup.name,
+                    unwrap_none(&sup.alias),
+                    unwrap_none(&sup.url),
+                );
+            }
+        }
+    }
+}
+
+fn unwrap_none(val: &Option<String>) -> String {
+    match val {
+        Some(v) => String::from(v),
+        None => String::from("-none-"),
+    }
+}
@@ -1,14 +1,23 @@
-use crate::{comm::*, errors::EleboxError, jamm_db::*, json::*, yaml::*};
+use crate::{
+    comm::*,
+    errors::{DbError, EleboxError},
+    jamm_db::*,
+    part::{parts_using, PartHandler},
+};
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, fmt::Debug, path::PathBuf};
 
-const ROOT_CATEGORY: &str = "__ROOT__";
+pub(crate) const ROOT_CATEGORY: &str = "__ROOT__";
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Category {
     pub name: String,
     pub parent: Option<String>,
     pub alias: Option<String>,
+    /// Fields the parts are expected to have, in addition to the ones of
+    /// the parent categories.
+    #[serde(default)]
+    pub parameters: Vec<ParamTemplate>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -23,12 +32,43 @@ impl Category {
             name: name.to_string(),
             parent: parent.map(|p| p.to_string()),
             alias: alias.map(|a| a.to_string()),
+            parameters: vec![],
         }
     }
 }
 
 pub struct CategoryHandler<'a> {
     pub(crate) db: &'a dyn Database<DbCategory>,
+    pub(crate) part: PartHandler<'a>,
+}
+
+/// Nest the `(name, parent)` pairs of a category or location hierarchy.
+pub(crate) fn build_tree(items: impl Iterator<Item = (String, Option<String>)>) -> Vec<TreeNode> {
+    let mut map: HashMap<String, Vec<String>> = HashMap::new();
+    let mut root: Vec<String> = Vec::new();
+
+    for (name, parent) in items {
+        if let Some(parent) = parent {
+            map.entry(parent).or_default().push(name);
+        } else {
+            map.entry(name.clone()).or_default();
+            root.push(name);
+        }
+    }
+
+    root.into_iter().map(|r| to_node(r, &map)).collect()
+}
+
+fn to_node(name: String, map: &HashMap<String, Vec<String>>) -> TreeNode {
+    let mut children = vec![];
+
+    if let Some(ch_names) = map.get(&name) {
+        for ch_name in ch_names {
+            children.push(to_node(ch_name.to_string(), map));
+        }
+    }
+
+    TreeNode { name, children }
 }
 
 impl CategoryHandler<'_> {
@@ -45,6 +85,7 @@ impl CategoryHandler<'_> {
                 "" => None,
                 other => Some(other.to_string()),
             },
+            parameters: db_category.parameters,
         }
     }
 
@@ -62,6 +103,7 @@ impl CategoryHandler<'_> {
                 Some(s) => s.to_string(),
                 None => "".to_string(),
             },
+            parameters: item.parameters.clone(),
         };
         Ok(db_category)
     }
@@ -69,56 +111,143 @@ impl CategoryHandler<'_> {
     fn add_recursion(&self, category: &Category, cats: &[Category]) -> Result<(), EleboxError> {
         if let Some(parent_name) = &category.parent {
             if let Some(parent_cat) = cats.iter().find(|c| c.name == *parent_name) {
-                self.add_recursion(parent_cat, cats)?;
+                // The parent may have been added already by an earlier item
+                match self.add_recursion(parent_cat, cats) {
+                    Ok(()) | Err(EleboxError::AlreadyExists(_, _)) => {}
+                    Err(err) => return Err(err),
+                }
             }
         }
 
         self.add(category)
     }
 
-    fn to_node(&self, name: String, map: &HashMap<String, Vec<String>>) -> TreeNode {
-        let mut children = vec![];
-
-        if let Some(ch_names) = map.get(&name) {
-            for ch_name in ch_names {
-                let ch_node = self.to_node(ch_name.to_string(), map);
-                children.push(ch_node);
+    /// IDs of the category and all its descendants.
+    pub(crate) fn subtree_ids(&self, id: &str) -> Result<Vec<Id>, EleboxError> {
+        let cats = self.db.list_with_id()?;
+        let mut ids = vec![id.to_string()];
+        let mut i = 0;
+        while i < ids.len() {
+            for (child_id, cat) in &cats {
+                if cat.parent_id == ids[i] && !ids.contains(child_id) {
+                    ids.push(child_id.clone());
+                }
             }
+            i += 1;
         }
-
-        TreeNode { name, children }
+        Ok(ids)
     }
 
-    pub fn get_tree(&self) -> Result<Vec<TreeNode>, EleboxError> {
-        let cats = self.list()?;
+    /// Fields the parts of the category are expected to have, including the
+    /// inherited ones. A category overrides a field of the same name of its
+    /// parents.
+    pub fn template(&self, name: &str) -> Result<Vec<ParamTemplate>, EleboxError> {
+        let id = self.db.get_id(name)?;
+        self.template_of(&id)
+    }
 
-        let mut cat_map: HashMap<String, Vec<String>> = HashMap::new();
-        let mut root: Vec<String> = Vec::new();
+    /// Same as `template`, but by ID, empty for a missing category.
+    pub(crate) fn template_of(&self, id: &str) -> Result<Vec<ParamTemplate>, EleboxError> {
+        // Ancestors first, a broken hierarchy ends at a repeated category
+        let mut chain: Vec<(Id, DbCategory)> = vec![];
+        let mut id = id.to_string();
+        while id != ROOT_CATEGORY && !chain.iter().any(|(i, _)| *i == id) {
+            let cat = match self.db.get(&id) {
+                Ok(cat) => cat,
+                Err(DbError::NotExists(_)) => break,
+                Err(err) => return Err(EleboxError::DatabaseError(err)),
+            };
+            let parent_id = cat.parent_id.clone();
+            chain.push((id, cat));
+            id = parent_id;
+        }
 
-        for c in cats {
-            if let Some(parent) = c.parent {
-                cat_map.entry(parent).or_default().push(c.name);
-            } else {
-                cat_map.entry(c.name.clone()).or_insert_with(Vec::new);
-                root.push(c.name);
+        let mut template: Vec<ParamTemplate> = vec![];
+        for (_, cat) in chain.into_iter().rev() {
+            for param in cat.parameters {
+                template.retain(|p| !p.name.eq_ignore_ascii_case(&param.name));
+                template.push(param);
             }
         }
+        Ok(template)
+    }
 
-        let mut tree_nodes: Vec<TreeNode> = Vec::new();
-        for r in root {
-            let node = self.to_node(r.to_string(), &cat_map);
-            tree_nodes.push(node);
+    /// Delete the category, the parts and child categories that still use
+    /// it are handled by `policy`.
+    pub fn delete_with(&self, name: &str, policy: &DeletePolicy) -> Result<(), EleboxError> {
+        let id = self.db.get_id(name)?;
+        let part_db = self.part.db;
+        let parts = parts_using(part_db, &id, |part| &mut part.category_id)?;
+        let children: Vec<(Id, DbCategory)> = self
+            .db
+            .list_with_id()?
+            .into_iter()
+            .filter(|(_, cat)| cat.parent_id == id)
+            .collect();
+
+        if parts.is_empty() && children.is_empty() {
+            self.db.delete(&id)?;
+            return Ok(());
         }
 
-        Ok(tree_nodes)
+        match policy {
+            DeletePolicy::Restrict => {
+                let part_names = parts.into_iter().map(|(_, part)| part.name);
+                let cat_names = children.into_iter().map(|(_, cat)| cat.name);
+                Err(EleboxError::StillReferenced(
+                    String::from(ITEM_CAT),
+                    name.to_string(),
+                    part_names.chain(cat_names).collect(),
+                ))
+            }
+            DeletePolicy::Reassign(new_name) => {
+                let new_id = self.db.get_id(new_name)?;
+                if self.subtree_ids(&id)?.contains(&new_id) {
+                    return Err(EleboxError::CircularCategory(new_name.to_string()));
+                }
+
+                in_transaction(self.db, part_db, || {
+                    for (part_id, mut part) in parts {
+                        part.category_id = new_id.clone();
+                        part_db.update(&part_id, &part)?;
+                    }
+                    for (cat_id, mut cat) in children {
+                        cat.parent_id = new_id.clone();
+                        self.db.update(&cat_id, &cat)?;
+                    }
+                    self.db.delete(&id)?;
+                    Ok(())
+                })
+            }
+            DeletePolicy::Cascade => {
+                let ids = self.subtree_ids(&id)?;
+                in_transaction(self.db, part_db, || {
+                    let part_ids: Vec<Id> = part_db
+                        .list_with_id()?
+                        .into_iter()
+                        .filter(|(_, part)| ids.contains(&part.category_id))
+                        .map(|(part_id, _)| part_id)
+                        .collect();
+                    self.part.delete_ids(&part_ids)?;
+                    for cat_id in &ids {
+                        self.db.delete(cat_id)?;
+                    }
+                    Ok(())
+                })
+            }
+        }
+    }
+
+    pub fn get_tree(&self) -> Result<Vec<TreeNode>, EleboxError> {
+        let cats = self.list()?;
+        Ok(build_tree(cats.into_iter().map(|c| (c.name, c.parent))))
     }
 }
 
 impl<'a> Handler<Category> for CategoryHandler<'_> {
+    /// Delete the category, refused if any part or child category still uses it.
     fn delete(&self, name: &str) -> Result<(), EleboxError> {
-        let id = self.db.get_id(name)?;
-        let _ = self.db.delete(&id)?;
-        Ok(())
+        self.delete_with(name, &DeletePolicy::Restrict)
     }
 
     fn add(&self, item: &Category) -> Result<(), EleboxError> {
@@ -133,6 +262,7 @@ impl<'a> Handler<Category> for CategoryHandler<'_> {
         let cat = Category {
             name: item.name.clone(),
             alias: item.alias.clone(),
+            parameters: item.parameters.clone(),
             parent: match &item.parent {
                 Some(p) => match p.as_str() {
                     "" => None,
@@ -160,6 +290,7 @@ impl<'a> Handler<Category> for CategoryHandler<'_> {
         let cat = Category {
             name: new_item.name.clone(),
             alias: new_item.alias.clone(),
+            parameters: new_item.parameters.clone(),
             parent: match &new_item.parent {
                 Some(p) => match p.as_str() {
                     "" => None,
@@ -192,35 +323,16 @@ impl<'a> Handler<Category> for CategoryHandler<'_> {
 
 impl Transferable for CategoryHandler<'_> {
     fn export(&self, filename: &PathBuf) -> Result<(), EleboxError> {
-        let items = self.list()?;
-
-        if YamlFile::check_extension(filename) {
-            let _ = YamlFile::write(filename, items);
-        } else if JsonFile::check_extension(filename) {
-            let _ = JsonFile::write(filename, items);
-        } else {
-            todo!()
-        }
-
-        Ok(())
+        write_items(filename, self.list()?)
     }
 
     fn import(&self, filename: &PathBuf) -> Result<(), EleboxError> {
-        let res_items: Result<Vec<Category>, ()> = if YamlFile::check_extension(filename) {
-            YamlFile::read(filename)
-        } else if JsonFile::check_extension(filename) {
-            JsonFile::read(filename)
-        } else {
-            todo!()
-        };
-
-        if res_items.is_err() {
-            todo!()
-        }
-
-        let items: Vec<Category> = res_items.unwrap();
+        let items: Vec<Category> = read_items(filename)?;
         for item in &items {
-            let _ = self.add_recursion(item, &items);
+            match self.add_recursion(item, &items) {
+                Ok(()) | Err(EleboxError::AlreadyExists(_, _)) => {}
+                Err(err) => return Err(err),
+            }
         }
 
         Ok(())
@@ -1,23 +1,187 @@
-use std::path::PathBuf;
+use std::{
+    path::{Path, PathBuf},
+    sync::{Condvar, Mutex, MutexGuard},
+    thread::{self, ThreadId},
+};
 
 use serde::{Deserialize, Serialize};
 
-use crate::{DbError, EleboxError};
+use crate::{json::JsonFile, yaml::YamlFile, DbError, EleboxError, Id};
 
 pub const ITEM_PART: &str = "part";
 pub const ITEM_CAT: &str = "category";
 pub const ITEM_PKG: &str = "package";
 pub const ITEM_MFR: &str = "manufacturer";
+pub const ITEM_PROJECT: &str = "project";
+pub const ITEM_LOCATION: &str = "location";
+pub const ITEM_SUPPLIER: &str = "supplier";
+pub const ITEM_ORDER: &str = "order";
+pub const ITEM_FIELD: &str = "field";
+pub const ITEM_LEDGER: &str = "ledger entry";
+
+/// Items listed by `Database::scan()`, with the decoding result of each.
+pub type Scanned<DI> = Vec<(Id, Result<DI, DbError>)>;
 
 pub trait Database<DI>: Send + Sync {
     fn init(&self) -> Result<(), DbError>;
     fn get_id(&self, name: &str) -> Result<String, DbError>;
     fn get(&self, id: &str) -> Result<DI, DbError>;
     fn list(&self) -> Result<Vec<DI>, DbError>;
+    fn list_with_id(&self) -> Result<Vec<(Id, DI)>, DbError>;
+    /// Like `list_with_id()`, but keeps going past items that cannot be decoded.
+    fn scan(&self) -> Result<Scanned<DI>, DbError>;
     fn add(&self, item: &DI) -> Result<(), DbError>;
     fn update(&self, ori_id: &str, new_item: &DI) -> Result<(), DbError>;
     fn delete(&self, id: &str) -> Result<(), DbError>;
     fn check(&self) -> Result<(), DbError>;
+
+    /// Schema version of the stored items, `0` for databases created before
+    /// versioning was added.
+    fn get_version(&self) -> Result<u32, DbError>;
+    fn set_version(&self, version: u32) -> Result<(), DbError>;
+
+    /// Start a transaction, changes are kept until `commit()` or dropped by
+    /// `rollback()`. Transactions can be nested and are shared by clones of
+    /// the same database, only the outermost `commit()` writes the changes.
+    ///
+    /// A transaction belongs to the thread that began it, other threads do
+    /// not see or join its changes and wait in `begin()` until it ends.
+    fn begin(&self) -> Result<(), DbError>;
+    fn commit(&self) -> Result<(), DbError>;
+    fn rollback(&self) -> Result<(), DbError>;
+}
+
+/// Transaction of a database, shared by its clones but owned by the thread
+/// that began it. Other threads wait in `begin()` until the outermost
+/// transaction ends, so their changes are never part of it.
+#[derive(Default)]
+pub(crate) struct TxLock {
+    state: Mutex<TxState>,
+    ended: Condvar,
+}
+
+#[derive(Default)]
+struct TxState {
+    owner: Option<ThreadId>,
+    depth: usize,
+    failed: bool,
+}
+
+impl TxState {
+    fn is_held_by_other(&self) -> bool {
+        self.owner
+            .is_some_and(|owner| owner != thread::current().id())
+    }
+}
+
+impl TxLock {
+    fn state(&self) -> MutexGuard<'_, TxState> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Wait until no other thread has an active transaction.
+    fn wait(&self) -> MutexGuard<'_, TxState> {
+        let mut state = self.state();
+        while state.is_held_by_other() {
+            state = self
+                .ended
+                .wait(state)
+                .unwrap_or_else(|err| err.into_inner());
+        }
+        state
+    }
+
+    /// Returns `true` if this starts the outermost transaction.
+    pub(crate) fn begin(&self) -> bool {
+        let mut state = self.wait();
+        state.owner = Some(thread::current().id());
+        state.depth += 1;
+        if state.depth == 1 {
+            state.failed = false;
+        }
+        state.depth == 1
+    }
+
+    /// End a level of the transaction. When the outermost one ends, `finish`
+    /// is called with whether to commit before other threads can begin.
+    ///
+    /// Returns `Some(true)` if the outermost transaction ended and was
+    /// committed, `Some(false)` if any level of it was rolled back.
+    pub(crate) fn end(
+        &self,
+        commit: bool,
+        finish: impl FnOnce(bool) -> Result<(), DbError>,
+    ) -> Result<Option<bool>, DbError> {
+        let mut state = self.state();
+        if state.depth == 0 || state.is_held_by_other() {
+            return Err(DbError::Transaction("no active transaction".to_string()));
+        }
+
+        state.depth -= 1;
+        state.failed |= !commit;
+        if state.depth > 0 {
+            return Ok(None);
+        }
+
+        // Still owned by this thread while finishing
+        let commit = !state.failed;
+        drop(state);
+        let result = finish(commit);
+
+        self.state().owner = None;
+        self.ended.notify_all();
+        result.map(|_| Some(commit))
+    }
+
+    /// Whether the current thread has an active transaction.
+    pub(crate) fn is_owner(&self) -> bool {
+        self.state().owner == Some(thread::current().id())
+    }
+
+    /// Run `f` once no other thread has an active transaction, for backends
+    /// that cannot keep changes outside of it apart.
+    pub(crate) fn outside<T>(&self, f: impl FnOnce() -> T) -> T {
+        let _state = self.wait();
+        f()
+    }
+}
+
+/// What to do with the parts, and child categories, that still refer to a
+/// deleted category, package or manufacturer.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub enum DeletePolicy {
+    /// Refuse to delete while still referenced.
+    Restrict,
+    /// Point the references to another item of the same type, by name.
+    Reassign(String),
+    /// Delete the referencing parts and child categories too.
+    Cascade,
+}
+
+/// Run `f` in a transaction of both databases, e.g. to delete an item
+/// together with the parts that refer to it.
+pub(crate) fn in_transaction<A, B, T>(
+    db: &dyn Database<A>,
+    other_db: &dyn Database<B>,
+    f: impl FnOnce() -> Result<T, EleboxError>,
+) -> Result<T, EleboxError> {
+    db.begin()?;
+    if let Err(err) = other_db.begin() {
+        let _ = db.rollback();
+        return Err(err.into());
+    }
+
+    let result = f();
+    let commit = result.is_ok();
+    let ends = if commit {
+        [db.commit(), other_db.commit()]
+    } else {
+        [db.rollback(), other_db.rollback()]
+    };
+    for end in ends {
+        end?;
+    }
+    result
 }
 
 pub trait Handler<T> {
@@ -38,3 +202,36 @@ pub trait HumanReadable {
     fn read<T: for<'de> Deserialize<'de>>(filename: &PathBuf) -> Result<Vec<T>, ()>;
     fn check_extension(filename: &PathBuf) -> bool;
 }
+
+fn invalid_file(filename: &Path, reason: &str) -> EleboxError {
+    EleboxError::InvalidFile(filename.to_string_lossy().into_owned(), reason.to_string())
+}
+
+/// Write `items` as YAML or JSON, picked by the file extension.
+pub(crate) fn write_items<T: Serialize>(
+    filename: &PathBuf,
+    items: Vec<T>,
+) -> Result<(), EleboxError> {
+    let res = if YamlFile::check_extension(filename) {
+        YamlFile::write(filename, items)
+    } else if JsonFile::check_extension(filename) {
+        JsonFile::write(filename, items)
+    } else {
+        return Err(invalid_file(filename, "unsupported extension"));
+    };
+    res.map_err(|_| invalid_file(filename, "cannot be written"))
+}
+
+/// Read items from a YAML or JSON file, picked by the file extension.
+pub(crate) fn read_items<T: for<'de> Deserialize<'de>>(
+    filename: &PathBuf,
+) -> Result<Vec<T>, EleboxError> {
+    let res = if YamlFile::check_extension(filename) {
+        YamlFile::read(filename)
+    } else if JsonFile::check_extension(filename) {
+        JsonFile::read(filename)
+    } else {
+        return Err(invalid_file(filename, "unsupported extension"));
+    };
+    res.map_err(|_| invalid_file(filename, "invalid format"))
+}
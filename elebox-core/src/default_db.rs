@@ -1,14 +1,25 @@
-use crate::{category::*, jamm_db::*, manufacturer::*, package::*, Handler, Manager, Part};
+use crate::{
+    category::*, jamm_db::*, location::*, manufacturer::*, package::*, supplier::*, Handler,
+    Manager, Part,
+};
+use rust_decimal::Decimal;
 use std::path::Path;
 
+fn param(name: &str, field_type: CustomFieldType, unit: &str) -> ParamTemplate {
+    ParamTemplate {
+        name: name.to_string(),
+        field_type,
+        unit: unit.to_string(),
+    }
+}
+
 pub fn create_default_db(path: &str) {
     let exists = Path::new(&path).exists();
 
-    let part_db = Box::new(JammDatabase::new(&path));
-    let pkg_db = Box::new(JammDatabase::new(&path));
-    let cat_db = Box::new(JammDatabase::new(&path));
-    let mfr_db = Box::new(JammDatabase::new(&path));
-    let manager = Manager::new(part_db, pkg_db, cat_db, mfr_db);
+    let manager = match Manager::open(path) {
+        Ok(mgr) => mgr,
+        Err(_) => return,
+    };
 
     let _ = manager.init();
 
@@ -21,121 +32,157 @@ pub fn create_default_db(path: &str) {
             name: "Resistors".to_string(),
             parent: None,
             alias: None,
+            parameters: vec![
+                param("Resistance", CustomFieldType::Parameter, "Ω"),
+                param("Tolerance", CustomFieldType::Parameter, "%"),
+            ],
         },
         Category {
             name: "Variable Resistors".to_string(),
             parent: Some("Resistors".to_string()),
             alias: None,
+            parameters: vec![],
         },
         Category {
             name: "Capacitors".to_string(),
             parent: None,
             alias: None,
+            parameters: vec![
+                param("Capacitance", CustomFieldType::Parameter, "F"),
+                param("Voltage", CustomFieldType::Parameter, "V"),
+            ],
         },
         Category {
             name: "Electrolytic Caps".to_string(),
             parent: Some("Capacitors".to_string()),
             alias: None,
+            parameters: vec![],
         },
         Category {
             name: "Tantalum Caps".to_string(),
             parent: Some("Capacitors".to_string()),
             alias: None,
+            parameters: vec![],
         },
         Category {
             name: "Ceramic Caps".to_string(),
             parent: Some("Capacitors".to_string()),
             alias: None,
+            parameters: vec![
+                param("Dielectric", CustomFieldType::Normal, ""),
+                param("Tolerance", CustomFieldType::Parameter, "%"),
+            ],
         },
         Category {
             name: "Inductors".to_string(),
             parent: None,
             alias: None,
+            parameters: vec![
+                param("Inductance", CustomFieldType::Parameter, "H"),
+                param("Current", CustomFieldType::Parameter, "A"),
+            ],
         },
         Category {
             name: "Crystals".to_string(),
             parent: None,
             alias: None,
+            parameters: vec![param("Frequency", CustomFieldType::Parameter, "Hz")],
         },
         Category {
             name: "Oscillators".to_string(),
             parent: None,
             alias: None,
+            parameters: vec![],
         },
         Category {
             name: "Diodes".to_string(),
             parent: None,
             alias: None,
+            parameters: vec![],
         },
         Category {
             name: "LED".to_string(),
             parent: Some("Diodes".to_string()),
             alias: None,
+            parameters: vec![],
         },
         Category {
             name: "TVS".to_string(),
             parent: Some("Diodes".to_string()),
             alias: Some("Transient-voltage-suppression diode".to_string()),
+            parameters: vec![],
         },
         Category {
             name: "MCU".to_string(),
             parent: None,
             alias: Some("Microcontroller".to_string()),
+            parameters: vec![],
         },
         Category {
             name: "PMIC".to_string(),
             parent: None,
             alias: Some("Power Management IC".to_string()),
+            parameters: vec![],
         },
         Category {
             name: "LDO".to_string(),
             parent: Some("PMIC".to_string()),
             alias: Some("Linear, Low Drop Out Regulators".to_string()),
+            parameters: vec![],
         },
         Category {
             name: "DC-DC Regulators".to_string(),
             parent: Some("PMIC".to_string()),
             alias: None,
+            parameters: vec![],
         },
         Category {
             name: "Buck Converters".to_string(),
             parent: Some("DC-DC Regulators".to_string()),
             alias: None,
+            parameters: vec![],
         },
         Category {
             name: "Boost Converters".to_string(),
             parent: Some("DC-DC Regulators".to_string()),
             alias: None,
+            parameters: vec![],
         },
         Category {
             name: "Connectors".to_string(),
             parent: None,
             alias: None,
+            parameters: vec![],
         },
         Category {
             name: "Buttons".to_string(),
             parent: None,
             alias: None,
+            parameters: vec![],
         },
         Category {
             name: "Transistors".to_string(),
             parent: None,
             alias: None,
+            parameters: vec![],
         },
         Category {
             name: "N-MOSFETs".to_string(),
             parent: Some("Transistors".to_string()),
             alias: None,
+            parameters: vec![],
         },
         Category {
             name: "P-MOSFETs".to_string(),
             parent: Some("Transistors".to_string()),
             alias: None,
+            parameters: vec![],
         },
         Category {
             name: "Modules".to_string(),
             parent: None,
             alias: None,
+            parameters: vec![],
         },
     ];
 
@@ -300,6 +347,18 @@ pub fn create_default_db(path: &str) {
         let _ = mfr_handler.add(&m);
     }
 
+    let _ = manager.location().add(&Location::new("Box #1", None, None));
+
+    let sups: Vec<Supplier> = vec![
+        Supplier::new("Mouser", None, Some("https://www.mouser.com")),
+        Supplier::new("DigiKey", Some("Digi-Key"), Some("https://www.digikey.com")),
+    ];
+
+    let sup_handler = manager.supplier();
+    for s in sups {
+        let _ = sup_handler.add(&s);
+    }
+
     let rp2040 = Part {
         name: "RP2040".to_string(),
         quantity: 15,
@@ -322,6 +381,8 @@ pub fn create_default_db(path: &str) {
         description: Some("Dual ARM Cortex-M0+ 133MHz, 264KB SRAM".to_string()),
         location: Some("Box #1".to_string()),
         starred: false,
+        min_stock: None,
+        reorder_quantity: None,
         custom_fields: vec![
             CustomField {
                 field_type: CustomFieldType::Normal,
@@ -335,19 +396,34 @@ pub fn create_default_db(path: &str) {
             },
         ],
         suppliers: vec![
-            Supplier{
-               name:"Mouser".to_string(),
-               link:"https://www.mouser.com/ProductDetail/Raspberry-Pi/SC09147?qs=T%252BzbugeAwjhSpdbCB4ve%252Bg%3D%3D".to_string(),
-               price:Some(0.8),
-               note:"7' reel".to_string(),
+            PartSupplier {
+                supplier: "Mouser".to_string(),
+                sku: Some("358-SC09147".to_string()),
+                link: Some("https://www.mouser.com/ProductDetail/Raspberry-Pi/SC09147?qs=T%252BzbugeAwjhSpdbCB4ve%252Bg%3D%3D".to_string()),
+                min_order: None,
+                pack_size: None,
+                currency: Some("USD".to_string()),
+                price_breaks: vec![PriceBreak {
+                    quantity: 1,
+                    price: Decimal::new(80, 2),
+                }],
+                note: Some("7' reel".to_string()),
             },
-            Supplier{
-               name:"DigiKey".to_string(),
-               link:"https://www.digikey.com/en/products/detail/raspberry-pi/SC0914-7/14306009?s=N4IgTCBcDa4GwBYAcBaAygYQAwE4CMCAFAOwCUGAKigHIAiIAugL5A".to_string(),
-               price:Some(0.8),
-               note:"7' reel".to_string(),
+            PartSupplier {
+                supplier: "DigiKey".to_string(),
+                sku: Some("2648-SC0914(7)CT-ND".to_string()),
+                link: Some("https://www.digikey.com/en/products/detail/raspberry-pi/SC0914-7/14306009?s=N4IgTCBcDa4GwBYAcBaAygYQAwE4CMCAFAOwCUGAKigHIAiIAugL5A".to_string()),
+                min_order: None,
+                pack_size: None,
+                currency: Some("USD".to_string()),
+                price_breaks: vec![PriceBreak {
+                    quantity: 1,
+                    price: Decimal::new(80, 2),
+                }],
+                note: Some("7' reel".to_string()),
             },
         ],
+        stock: vec![],
     };
 
     let rt9183 = Part{
@@ -365,6 +441,8 @@ pub fn create_default_db(path: &str) {
         product_link: Some("https://www.richtek.com/Products/Linear%20Regulator/Single%20Output%20Linear%20Regulator/RT9183".to_string()),
         image_link: Some("https://www.richtek.com/~/media/Richtek/Products/ProductSpecs/RT9183/en/Version1/40038ommuf.jpg".to_string()),
         starred: false,
+        min_stock: None,
+        reorder_quantity: None,
         custom_fields: vec![
             CustomField {
                 field_type: CustomFieldType::Normal,
@@ -403,6 +481,7 @@ pub fn create_default_db(path: &str) {
             },
         ],
         suppliers: vec![],
+        stock: vec![],
     };
 
     let _ = manager.part().add(&rp2040);
@@ -6,10 +6,48 @@ pub enum EleboxError {
     AlreadyExists(String, String),
     NotExists(String, String),
     InventoryShortage(String),
+    /// The stock of the part would exceed the largest quantity, 65535.
+    StockOverflow(String),
+    /// The item cannot be deleted, with the names of the parts and child
+    /// categories that still refer to it.
+    StillReferenced(String, String, Vec<String>),
+    /// The category would become its own ancestor.
+    CircularCategory(String),
+    /// The location would be inside itself.
+    CircularLocation(String),
+    /// The quantity of the part does not match the sum of its ledger, with
+    /// the quantity and the sum.
+    LedgerMismatch(String, u16, i64),
+    /// The file cannot be read or parsed, with the reason.
+    InvalidFile(String, String),
+    /// The order cannot be changed this way in its current status, with
+    /// the status.
+    InvalidOrderStatus(String, String),
+    /// The value of a parameter, or a condition on it, is not a number with
+    /// a unit, with the parameter and the value.
+    InvalidParameter(String, String),
+    /// The value does not fit the type of the custom field, with the field,
+    /// the value and what was expected.
+    InvalidField(String, String, String),
+    /// The code read off a component cannot be decoded, with the reason.
+    InvalidMarking(String, String),
+    /// The part lacks fields required by the template of its category, with
+    /// the names of the fields.
+    MissingParameters(String, Vec<String>),
+    /// More received than still outstanding on the order, with the order,
+    /// the part and the outstanding quantity.
+    OverReceipt(String, String, u16),
     DatabaseError(DbError),
 }
 
-impl Error for EleboxError {}
+impl Error for EleboxError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EleboxError::DatabaseError(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
 
 impl fmt::Display for EleboxError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -23,6 +61,57 @@ impl fmt::Display for EleboxError {
             EleboxError::InventoryShortage(ref name) => {
                 write!(f, "Part {} not enough stock", name)
             }
+            EleboxError::StockOverflow(ref name) => {
+                write!(f, "Part {} cannot hold more than {}", name, u16::MAX)
+            }
+            EleboxError::StillReferenced(ref item, ref name, ref users) => {
+                write!(f, "{} {} is still used by {}", item, name, users.join(", "))
+            }
+            EleboxError::CircularCategory(ref name) => {
+                write!(f, "Category {} cannot be under itself", name)
+            }
+            EleboxError::CircularLocation(ref name) => {
+                write!(f, "Location {} cannot be inside itself", name)
+            }
+            EleboxError::LedgerMismatch(ref name, quantity, balance) => {
+                write!(
+                    f,
+                    "Part {} has {} in stock but its ledger sums to {}",
+                    name, quantity, balance
+                )
+            }
+            EleboxError::InvalidFile(ref filename, ref reason) => {
+                write!(f, "Cannot read {}, {}", filename, reason)
+            }
+            EleboxError::InvalidOrderStatus(ref name, ref status) => {
+                write!(f, "Order {} cannot be changed while {}", name, status)
+            }
+            EleboxError::InvalidParameter(ref name, ref value) if value.is_empty() => {
+                write!(f, "Invalid parameter {}", name)
+            }
+            EleboxError::InvalidParameter(ref name, ref value) => {
+                write!(f, "Invalid value '{}' of parameter {}", value, name)
+            }
+            EleboxError::InvalidField(ref name, ref value, ref expected) => {
+                write!(
+                    f,
+                    "Invalid value '{}' of field {}, expected {}",
+                    value, name, expected
+                )
+            }
+            EleboxError::InvalidMarking(ref code, ref reason) => {
+                write!(f, "Cannot decode marking {}, {}", code, reason)
+            }
+            EleboxError::MissingParameters(ref name, ref params) => {
+                write!(f, "Part {} is missing {}", name, params.join(", "))
+            }
+            EleboxError::OverReceipt(ref name, ref part, outstanding) => {
+                write!(
+                    f,
+                    "Order {} has only {} of part {} outstanding",
+                    name, outstanding, part
+                )
+            }
             EleboxError::DatabaseError(ref error) => {
                 write!(f, "database error {}", error)
             }
@@ -36,9 +125,33 @@ pub enum DbError {
     CannotOpenBucket(String),
     AccessFailed(String),
     NotExists(String),
+    /// The file is damaged or is not a database, with the reason.
+    Corrupted(String),
+    /// The bucket or table of an item type does not exist, the database
+    /// may not be initialized.
+    BucketMissing(String),
+    /// An item of the bucket cannot be encoded or decoded, with the reason.
+    Serialization(String, String),
+    /// The database file is locked by another process.
+    Locked(String),
+    Io(std::io::Error),
+    /// A transaction cannot be committed, with the reason.
+    Transaction(String),
+    /// The bucket was written by a newer release, with its schema version.
+    UnsupportedVersion(String, u32),
+    /// The bucket was written by an older release and is not upgraded yet,
+    /// with its schema version.
+    OutdatedVersion(String, u32),
 }
 
-impl Error for DbError {}
+impl Error for DbError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DbError::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
 
 impl fmt::Display for DbError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -55,10 +168,52 @@ impl fmt::Display for DbError {
             DbError::NotExists(ref name) => {
                 write!(f, "{} does not exists", name)
             }
+            DbError::Corrupted(ref reason) => {
+                write!(f, "database is corrupted: {}", reason)
+            }
+            DbError::BucketMissing(ref name) => {
+                write!(
+                    f,
+                    "bucket {} does not exist, is the database initialized?",
+                    name
+                )
+            }
+            DbError::Serialization(ref name, ref reason) => {
+                write!(f, "cannot encode or decode {}: {}", name, reason)
+            }
+            DbError::Locked(ref name) => {
+                write!(f, "database {} is locked by another process", name)
+            }
+            DbError::Io(ref err) => {
+                write!(f, "I/O error: {}", err)
+            }
+            DbError::Transaction(ref reason) => {
+                write!(f, "transaction failed: {}", reason)
+            }
+            DbError::UnsupportedVersion(ref name, version) => {
+                write!(
+                    f,
+                    "{} has schema version {}, which requires a newer elebox",
+                    name, version
+                )
+            }
+            DbError::OutdatedVersion(ref name, version) => {
+                write!(
+                    f,
+                    "{} has schema version {}, upgrade the database with `init` first",
+                    name, version
+                )
+            }
         }
     }
 }
 
+impl From<std::io::Error> for DbError {
+    fn from(err: std::io::Error) -> DbError {
+        DbError::Io(err)
+    }
+}
+
 impl From<DbError> for EleboxError {
     fn from(err: DbError) -> EleboxError {
         match err {
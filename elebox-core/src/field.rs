@@ -0,0 +1,675 @@
+use crate::{comm::*, errors::*, jamm_db::*, param::SiValue, Part};
+use core::fmt;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, path::PathBuf, str::FromStr};
+
+/// Definition of a custom field shared by all parts, the fields of the
+/// parts with the same name get its spelling and type.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct FieldDef {
+    pub name: String,
+    pub field_type: CustomFieldType,
+    pub description: Option<String>,
+}
+
+impl FieldDef {
+    pub fn new(name: &str, field_type: CustomFieldType) -> Self {
+        Self {
+            name: name.to_string(),
+            field_type,
+            description: None,
+        }
+    }
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Parse a `YYYY-MM-DD` date, single digit months and days are accepted.
+fn parse_date(value: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = value.split('-').map(|p| p.parse::<u32>().ok());
+    let (year, month, day) = (parts.next()??, parts.next()??, parts.next()??);
+    if parts.next().is_some() || !(1..=12).contains(&month) {
+        return None;
+    }
+    (1..=days_in_month(year, month))
+        .contains(&day)
+        .then_some((year, month, day))
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "y" | "1" => Some(true),
+        "false" | "no" | "n" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+impl CustomFieldType {
+    /// The value written the usual way, e.g. `yes` as `true` or `0.1uF` as
+    /// `100nF`. `None` if it is not valid for the type.
+    pub fn normalize(&self, value: &str) -> Option<String> {
+        let value = value.trim();
+        match self {
+            CustomFieldType::Normal | CustomFieldType::Link | CustomFieldType::Markdown => {
+                Some(value.to_string())
+            }
+            CustomFieldType::Parameter => SiValue::parse(value).map(|v| v.to_string()),
+            CustomFieldType::Number(unit) => {
+                let number = match unit.as_deref() {
+                    Some(unit) => value.strip_suffix(unit).unwrap_or(value).trim(),
+                    None => value,
+                };
+                let number = Decimal::from_str(number).ok()?;
+                Some(number.normalize().to_string())
+            }
+            CustomFieldType::Integer => value.parse::<i64>().ok().map(|i| i.to_string()),
+            CustomFieldType::Boolean => parse_bool(value).map(|b| b.to_string()),
+            CustomFieldType::Date => {
+                parse_date(value).map(|(y, m, d)| format!("{:04}-{:02}-{:02}", y, m, d))
+            }
+            CustomFieldType::Enum(choices) => choices
+                .iter()
+                .find(|c| c.trim().eq_ignore_ascii_case(value))
+                .cloned(),
+        }
+    }
+
+    /// Number to sort and compare the values by, `None` for text.
+    fn sort_key(&self, value: &str) -> Option<Decimal> {
+        let value = self.normalize(value)?;
+        match self {
+            CustomFieldType::Parameter => SiValue::parse(&value).map(|v| v.value),
+            CustomFieldType::Number(_) | CustomFieldType::Integer => Decimal::from_str(&value).ok(),
+            CustomFieldType::Boolean => Some(Decimal::from(u8::from(value == "true"))),
+            CustomFieldType::Date => Decimal::from_str(&value.replace('-', "")).ok(),
+            CustomFieldType::Enum(choices) => {
+                choices.iter().position(|c| *c == value).map(Decimal::from)
+            }
+            _ => None,
+        }
+    }
+
+    /// Compare two values of this type, e.g. numbers by their value, enums
+    /// by the order of the choices and text ignoring case. Invalid values
+    /// come after the valid ones.
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        match (self.sort_key(a), self.sort_key(b)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => a.to_lowercase().cmp(&b.to_lowercase()),
+        }
+    }
+
+    /// What a valid value looks like, for error messages.
+    fn expected(&self) -> String {
+        match self {
+            CustomFieldType::Number(_) => "a number".to_string(),
+            CustomFieldType::Integer => "an integer".to_string(),
+            CustomFieldType::Boolean => "true or false".to_string(),
+            CustomFieldType::Date => "a date like 2024-01-31".to_string(),
+            CustomFieldType::Enum(choices) => format!("one of {}", choices.join(", ")),
+            _ => "text".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for CustomFieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CustomFieldType::Normal => write!(f, "Normal"),
+            CustomFieldType::Link => write!(f, "Link"),
+            CustomFieldType::Parameter => write!(f, "Parameter"),
+            CustomFieldType::Number(Some(unit)) => write!(f, "Number ({})", unit),
+            CustomFieldType::Number(None) => write!(f, "Number"),
+            CustomFieldType::Integer => write!(f, "Integer"),
+            CustomFieldType::Boolean => write!(f, "Boolean"),
+            CustomFieldType::Date => write!(f, "Date"),
+            CustomFieldType::Enum(choices) => write!(f, "Enum ({})", choices.join(", ")),
+            CustomFieldType::Markdown => write!(f, "Markdown"),
+        }
+    }
+}
+
+/// Check the values of the fields by their types and write them normalized.
+/// Empty values are kept, they are not set yet.
+pub(crate) fn normalize_fields(fields: &mut [CustomField]) -> Result<(), EleboxError> {
+    for field in fields.iter_mut().filter(|f| !f.value.trim().is_empty()) {
+        field.value = match field.field_type.normalize(&field.value) {
+            Some(value) => value,
+            None if field.field_type == CustomFieldType::Parameter => {
+                return Err(EleboxError::InvalidParameter(
+                    field.name.clone(),
+                    field.value.clone(),
+                ))
+            }
+            None => {
+                return Err(EleboxError::InvalidField(
+                    field.name.clone(),
+                    field.value.clone(),
+                    field.field_type.expected(),
+                ))
+            }
+        };
+    }
+    Ok(())
+}
+
+/// Give the fields the names and types of their definitions, so the same
+/// field is written the same way in all parts.
+pub(crate) fn apply_field_defs(defs: &[DbFieldDef], fields: &mut [CustomField]) {
+    for field in fields.iter_mut() {
+        if let Some(def) = defs
+            .iter()
+            .find(|d| d.name.eq_ignore_ascii_case(field.name.trim()))
+        {
+            field.name = def.name.clone();
+            field.field_type = def.field_type.clone();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum FieldOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// The value contains the text, ignoring case.
+    Contains,
+}
+
+/// Condition on a custom field of any type, compared the way the type sorts.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct FieldFilter {
+    /// Field name, matched ignoring case.
+    pub name: String,
+    pub op: FieldOp,
+    pub value: String,
+}
+
+impl FieldFilter {
+    /// Parse a condition like `Tested=yes`, `Pins>=8`, `Added<2024-01-01`,
+    /// `Status!=Obsolete` or `Notes~smd`.
+    pub fn parse(text: &str) -> Result<Self, EleboxError> {
+        let ops = [
+            ("<=", FieldOp::Le),
+            (">=", FieldOp::Ge),
+            ("!=", FieldOp::Ne),
+            ("=", FieldOp::Eq),
+            ("<", FieldOp::Lt),
+            (">", FieldOp::Gt),
+            ("~", FieldOp::Contains),
+        ];
+        // The first operator in the text, the longer one at the same place
+        let (name, op, value) = ops
+            .iter()
+            .filter_map(|(s, op)| text.find(s).map(|i| (i, s, op)))
+            .min_by_key(|(i, s, _)| (*i, usize::MAX - s.len()))
+            .map(|(i, s, op)| (text[..i].trim(), *op, text[i + s.len()..].trim()))
+            .ok_or_else(|| EleboxError::InvalidParameter(text.to_string(), String::new()))?;
+        if name.is_empty() {
+            return Err(EleboxError::InvalidParameter(
+                text.to_string(),
+                String::new(),
+            ));
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            op,
+            value: value.to_string(),
+        })
+    }
+
+    /// Returns `true` if a field of the fields meets the condition.
+    pub(crate) fn matches(&self, fields: &[CustomField]) -> bool {
+        fields
+            .iter()
+            .filter(|f| f.name.trim().eq_ignore_ascii_case(self.name.trim()))
+            .any(|f| {
+                if self.op == FieldOp::Contains {
+                    return f.value.to_lowercase().contains(&self.value.to_lowercase());
+                }
+
+                let ord = f.field_type.compare(&f.value, &self.value);
+                match self.op {
+                    FieldOp::Eq => ord == Ordering::Equal,
+                    FieldOp::Ne => ord != Ordering::Equal,
+                    FieldOp::Lt => ord == Ordering::Less,
+                    FieldOp::Le => ord != Ordering::Greater,
+                    FieldOp::Gt => ord == Ordering::Greater,
+                    FieldOp::Ge => ord != Ordering::Less,
+                    FieldOp::Contains => unreachable!(),
+                }
+            })
+    }
+}
+
+/// Sort the parts by a custom field, see `CustomFieldType::compare`. The
+/// parts without the field come last.
+pub fn sort_by_field(parts: &mut [Part], name: &str, descending: bool) {
+    let field = |part: &Part| {
+        part.custom_fields
+            .iter()
+            .find(|f| f.name.trim().eq_ignore_ascii_case(name.trim()))
+            .cloned()
+    };
+    parts.sort_by(|a, b| match (field(a), field(b)) {
+        (Some(a), Some(b)) => {
+            let ord = a.field_type.compare(&a.value, &b.value);
+            if descending {
+                ord.reverse()
+            } else {
+                ord
+            }
+        }
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    });
+}
+
+pub struct FieldHandler<'a> {
+    pub(crate) db: &'a dyn Database<DbFieldDef>,
+    pub(crate) part_db: &'a dyn Database<DbPart>,
+    pub(crate) cat_db: &'a dyn Database<DbCategory>,
+}
+
+impl FieldHandler<'_> {
+    fn to_db_item(&self, item: &FieldDef) -> DbFieldDef {
+        DbFieldDef {
+            name: item.name.trim().to_string(),
+            field_type: item.field_type.clone(),
+            description: item.description.clone().unwrap_or_default(),
+        }
+    }
+
+    fn to_item(&self, db_item: DbFieldDef) -> FieldDef {
+        FieldDef {
+            name: db_item.name,
+            field_type: db_item.field_type,
+            description: Some(db_item.description).filter(|s| !s.is_empty()),
+        }
+    }
+
+    /// ID of the definition, names are matched ignoring case.
+    fn find_id(&self, name: &str) -> Result<Id, EleboxError> {
+        if let Ok(id) = self.db.get_id(name) {
+            return Ok(id);
+        }
+
+        self.db
+            .list_with_id()?
+            .into_iter()
+            .find(|(_, def)| def.name.eq_ignore_ascii_case(name.trim()))
+            .map(|(id, _)| id)
+            .ok_or_else(|| EleboxError::NotExists(String::from(ITEM_FIELD), name.to_string()))
+    }
+
+    /// Rename the field in all parts and category templates, and its
+    /// definition if there is one. Returns the number of changed parts.
+    pub fn rename(&self, ori_name: &str, new_name: &str) -> Result<usize, EleboxError> {
+        let same = |name: &str| name.trim().eq_ignore_ascii_case(ori_name.trim());
+        let new_name = new_name.trim();
+        let taken = |name: &str| !same(name) && name.trim().eq_ignore_ascii_case(new_name);
+
+        let parts: Vec<(Id, DbPart)> = self
+            .part_db
+            .list_with_id()?
+            .into_iter()
+            .filter(|(_, part)| part.custom_fields.iter().any(|f| same(&f.name)))
+            .collect();
+        if let Some((_, part)) = parts
+            .iter()
+            .find(|(_, part)| part.custom_fields.iter().any(|f| taken(&f.name)))
+        {
+            return Err(EleboxError::AlreadyExists(
+                String::from(ITEM_FIELD),
+                format!("{} of part {}", new_name, part.name),
+            ));
+        }
+        if self.db.list()?.iter().any(|def| taken(&def.name)) {
+            return Err(EleboxError::AlreadyExists(
+                String::from(ITEM_FIELD),
+                new_name.to_string(),
+            ));
+        }
+
+        let cats: Vec<(Id, DbCategory)> = self
+            .cat_db
+            .list_with_id()?
+            .into_iter()
+            .filter(|(_, cat)| cat.parameters.iter().any(|p| same(&p.name)))
+            .collect();
+        let def = self.find_id(ori_name).ok();
+
+        let count = parts.len();
+        in_transaction(self.db, self.part_db, || {
+            in_transaction(self.cat_db, self.part_db, || {
+                for (id, mut part) in parts {
+                    for field in part.custom_fields.iter_mut().filter(|f| same(&f.name)) {
+                        field.name = new_name.to_string();
+                    }
+                    self.part_db.update(&id, &part)?;
+                }
+                for (id, mut cat) in cats {
+                    for param in cat.parameters.iter_mut().filter(|p| same(&p.name)) {
+                        param.name = new_name.to_string();
+                    }
+                    self.cat_db.update(&id, &cat)?;
+                }
+                if let Some(id) = &def {
+                    let mut db_item = self.db.get(id)?;
+                    db_item.name = new_name.to_string();
+                    self.db.update(id, &db_item)?;
+                }
+                Ok(count)
+            })
+        })
+    }
+}
+
+impl Handler<FieldDef> for FieldHandler<'_> {
+    /// Delete the definition, the fields of the parts are kept.
+    fn delete(&self, name: &str) -> Result<(), EleboxError> {
+        let id = self.find_id(name)?;
+        self.db.delete(&id)?;
+        Ok(())
+    }
+
+    fn add(&self, item: &FieldDef) -> Result<(), EleboxError> {
+        if self.find_id(&item.name).is_ok() {
+            return Err(EleboxError::AlreadyExists(
+                String::from(ITEM_FIELD),
+                item.name.clone(),
+            ));
+        }
+
+        self.db.add(&self.to_db_item(item))?;
+        Ok(())
+    }
+
+    /// Update the definition, a new name is given to the field in all parts.
+    /// The values of the parts are checked against a new type when the parts
+    /// are saved next.
+    fn update(&self, ori_name: &str, new_item: &FieldDef) -> Result<(), EleboxError> {
+        let ori_id = self.find_id(ori_name)?;
+        let ori_item = self.db.get(&ori_id)?;
+        if ori_item.name != new_item.name.trim() {
+            self.rename(&ori_item.name, &new_item.name)?;
+        }
+
+        self.db.update(&ori_id, &self.to_db_item(new_item))?;
+        Ok(())
+    }
+
+    fn get(&self, name: &str) -> Result<FieldDef, EleboxError> {
+        let id = self.find_id(name)?;
+        let db_item = self.db.get(&id)?;
+        Ok(self.to_item(db_item))
+    }
+
+    fn list(&self) -> Result<Vec<FieldDef>, EleboxError> {
+        let db_items = self.db.list()?;
+        Ok(db_items
+            .into_iter()
+            .map(|db_item| self.to_item(db_item))
+            .collect())
+    }
+}
+
+impl Transferable for FieldHandler<'_> {
+    fn export(&self, filename: &PathBuf) -> Result<(), EleboxError> {
+        write_items(filename, self.list()?)
+    }
+
+    fn import(&self, filename: &PathBuf) -> Result<(), EleboxError> {
+        let items: Vec<FieldDef> = read_items(filename)?;
+        for item in items {
+            if let Err(e) = self.add(&item) {
+                match e {
+                    EleboxError::AlreadyExists(_, _) => continue,
+                    others => return Err(others),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Category, Manager, ParamTemplate, PartQuery};
+    use std::fs;
+    use uuid::Uuid;
+
+    fn field(name: &str, field_type: CustomFieldType, value: &str) -> CustomField {
+        CustomField {
+            field_type,
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    fn status() -> CustomFieldType {
+        CustomFieldType::Enum(vec![
+            "Active".to_string(),
+            "NRND".to_string(),
+            "Obsolete".to_string(),
+        ])
+    }
+
+    fn new_manager() -> Manager {
+        let mgr = Manager::in_memory();
+        mgr.category()
+            .add(&Category::new("MCU", None, None))
+            .unwrap();
+        mgr.field()
+            .add(&FieldDef::new("Pins", CustomFieldType::Integer))
+            .unwrap();
+        mgr.field().add(&FieldDef::new("Status", status())).unwrap();
+        mgr
+    }
+
+    fn add_part(mgr: &Manager, name: &str, pins: &str, status: &str) {
+        let mut part = Part::new(name, "MCU", 1);
+        part.custom_fields = vec![
+            field("pins", CustomFieldType::Normal, pins),
+            field("status", CustomFieldType::Normal, status),
+        ];
+        mgr.part().add(&part).unwrap();
+    }
+
+    #[test]
+    fn test_normalize_values() {
+        // Arrange
+        let cases = [
+            (CustomFieldType::Integer, " 42 ", Some("42")),
+            (CustomFieldType::Integer, "4.2", None),
+            (
+                CustomFieldType::Number(Some("mm".to_string())),
+                "2.50 mm",
+                Some("2.5"),
+            ),
+            (CustomFieldType::Number(None), "ten", None),
+            (CustomFieldType::Boolean, "Yes", Some("true")),
+            (CustomFieldType::Boolean, "maybe", None),
+            (CustomFieldType::Date, "2024-2-9", Some("2024-02-09")),
+            (CustomFieldType::Date, "2023-02-29", None),
+            (status(), "obsolete", Some("Obsolete")),
+            (status(), "EOL", None),
+            (CustomFieldType::Markdown, "**bold**", Some("**bold**")),
+        ];
+
+        for (field_type, value, expected) in cases {
+            // Act
+            let normalized = field_type.normalize(value);
+
+            // Assert
+            assert_eq!(normalized.as_deref(), expected, "{value}");
+        }
+    }
+
+    #[test]
+    fn test_sort_by_field() {
+        // Arrange
+        let mgr = new_manager();
+        add_part(&mgr, "A", "100", "Obsolete");
+        add_part(&mgr, "B", "8", "Active");
+        add_part(&mgr, "C", "48", "NRND");
+        mgr.part().add(&Part::new("D", "MCU", 1)).unwrap();
+        let mut parts = mgr.part().list().unwrap();
+
+        // Act
+        sort_by_field(&mut parts, "Pins", false);
+        let by_pins: Vec<_> = parts.iter().map(|p| p.name.clone()).collect();
+        sort_by_field(&mut parts, "status", true);
+        let by_status: Vec<_> = parts.iter().map(|p| p.name.clone()).collect();
+
+        // Assert
+        assert_eq!(by_pins, vec!["B", "C", "A", "D"]);
+        assert_eq!(by_status, vec!["A", "C", "B", "D"]);
+    }
+
+    #[test]
+    fn test_add_checks_field_defs() {
+        // Arrange
+        let mgr = new_manager();
+        let mut part = Part::new("RP2040", "MCU", 1);
+        part.custom_fields = vec![field("PINS", CustomFieldType::Normal, "many")];
+
+        // Act
+        let invalid = mgr.part().add(&part);
+        part.custom_fields[0].value = "56".to_string();
+        let valid = mgr.part().add(&part);
+
+        // Assert
+        assert!(matches!(invalid, Err(EleboxError::InvalidField(_, _, _))));
+        valid.expect("Expected OK");
+        let saved = mgr.part().get("RP2040").unwrap();
+        assert_eq!(
+            saved.custom_fields,
+            vec![field("Pins", CustomFieldType::Integer, "56")]
+        );
+    }
+
+    #[test]
+    fn test_find_by_field() {
+        // Arrange
+        let mgr = new_manager();
+        add_part(&mgr, "A", "100", "Obsolete");
+        add_part(&mgr, "B", "8", "Active");
+        add_part(&mgr, "C", "48", "NRND");
+        let query = PartQuery {
+            fields: vec![
+                FieldFilter::parse("Pins>=10").unwrap(),
+                FieldFilter::parse("Status<obsolete").unwrap(),
+            ],
+            ..Default::default()
+        };
+
+        // Act
+        let parts = mgr.part().find(&query).expect("Expected OK");
+
+        // Assert
+        let names: Vec<_> = parts.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["C"]);
+        assert_eq!(
+            FieldFilter::parse("Notes~a<b").unwrap().op,
+            FieldOp::Contains
+        );
+    }
+
+    #[test]
+    fn test_rename_field() {
+        // Arrange
+        let mgr = new_manager();
+        add_part(&mgr, "A", "100", "Obsolete");
+        add_part(&mgr, "B", "8", "Active");
+        let mut mcu = mgr.category().get("MCU").unwrap();
+        mcu.parameters = vec![ParamTemplate {
+            name: "Pins".to_string(),
+            field_type: CustomFieldType::Integer,
+            unit: "".to_string(),
+        }];
+        mgr.category().update("MCU", &mcu).unwrap();
+
+        // Act
+        let count = mgr
+            .field()
+            .rename("pins", "Pin count")
+            .expect("Expected OK");
+        let taken = mgr.field().rename("Status", "Pin Count");
+
+        // Assert
+        assert_eq!(count, 2);
+        assert!(matches!(taken, Err(EleboxError::AlreadyExists(_, _))));
+        assert!(mgr.field().get("Pins").is_err());
+        assert_eq!(
+            mgr.field().get("Pin count").unwrap().field_type,
+            CustomFieldType::Integer
+        );
+        let part = mgr.part().get("A").unwrap();
+        assert_eq!(part.custom_fields[0].name, "Pin count");
+        let template = mgr.category().template("MCU").unwrap();
+        assert_eq!(template[0].name, "Pin count");
+    }
+
+    #[test]
+    fn test_export_import_typed_fields() {
+        // Arrange
+        let mgr = new_manager();
+        add_part(&mgr, "A", "100", "Obsolete");
+
+        for extension in ["yaml", "json"] {
+            let dir = std::env::temp_dir().join(format!("elebox_test_{}", Uuid::new_v4()));
+            fs::create_dir_all(&dir).unwrap();
+
+            // Act
+            mgr.export(&dir, extension).expect("Expected OK");
+            let new_mgr = Manager::in_memory();
+            let result = new_mgr.import(&dir, extension);
+            let _ = fs::remove_dir_all(&dir);
+
+            // Assert
+            assert!(result.is_ok(), "{extension}");
+            assert_eq!(new_mgr.field().get("Status").unwrap().field_type, status());
+            let part = new_mgr.part().get("A").unwrap();
+            assert_eq!(part.custom_fields[1], field("Status", status(), "Obsolete"));
+        }
+    }
+
+    #[test]
+    fn test_import_invalid_file() {
+        // Arrange
+        let mgr = new_manager();
+        let dir = std::env::temp_dir().join(format!("elebox_test_{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let broken = dir.join("fields.json");
+        fs::write(&broken, "not json").unwrap();
+
+        // Act
+        let wrong_ext = mgr.field().export(&dir.join("fields.toml"));
+        let unreadable = mgr.field().import(&broken);
+        let _ = fs::remove_dir_all(&dir);
+
+        // Assert
+        assert!(matches!(wrong_ext, Err(EleboxError::InvalidFile(_, _))));
+        assert!(matches!(unreadable, Err(EleboxError::InvalidFile(_, _))));
+    }
+}
@@ -0,0 +1,858 @@
+use core::fmt;
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+
+use crate::{
+    category::ROOT_CATEGORY, comm::*, errors::EleboxError, jamm_db::*, ledger::close,
+    location::ROOT_LOCATION, Manager,
+};
+
+/// A consistency problem found by `Manager::fsck()`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Problem {
+    /// A part refers to a category, package, manufacturer, location or
+    /// supplier that does not exist.
+    MissingReference { part: String, item: String, id: Id },
+    /// An item other than a part, e.g. a ledger entry, refers to a part or
+    /// another item that does not exist.
+    DanglingReference {
+        item: String,
+        name: String,
+        target: String,
+        id: Id,
+    },
+    /// A category or location refers to a parent that does not exist.
+    MissingParent {
+        item: String,
+        name: String,
+        parent_id: Id,
+    },
+    /// Categories or locations that are their own ancestors, in parent order.
+    ParentCycle { item: String, names: Vec<String> },
+    /// Items of the same type sharing a name, only the first one can be
+    /// found by name.
+    DuplicateName {
+        item: String,
+        name: String,
+        ids: Vec<Id>,
+    },
+    /// A stored item that cannot be decoded.
+    Undecodable {
+        item: String,
+        id: Id,
+        reason: String,
+    },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::MissingReference { part, item, id } => {
+                write!(f, "part {} refers to missing {} {}", part, item, id)
+            }
+            Problem::DanglingReference {
+                item,
+                name,
+                target,
+                id,
+            } => {
+                write!(f, "{} {} refers to missing {} {}", item, name, target, id)
+            }
+            Problem::MissingParent {
+                item,
+                name,
+                parent_id,
+            } => {
+                write!(
+                    f,
+                    "{} {} refers to missing parent {}",
+                    item, name, parent_id
+                )
+            }
+            Problem::ParentCycle { item, names } => {
+                write!(f, "{} cycle: {}", item, names.join(" -> "))
+            }
+            Problem::DuplicateName { item, name, ids } => {
+                write!(f, "{} {} is duplicated in {}", item, name, ids.join(", "))
+            }
+            Problem::Undecodable { item, id, reason } => {
+                write!(f, "{} {} cannot be decoded: {}", item, id, reason)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FsckIssue {
+    pub problem: Problem,
+    /// `true` if the problem was fixed by the repair mode.
+    pub repaired: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FsckReport {
+    pub issues: Vec<FsckIssue>,
+}
+
+impl FsckReport {
+    /// Returns `true` if no problem was found.
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Problems that are still present.
+    pub fn unresolved(&self) -> impl Iterator<Item = &Problem> {
+        self.issues
+            .iter()
+            .filter(|issue| !issue.repaired)
+            .map(|issue| &issue.problem)
+    }
+
+    fn push(&mut self, problem: Problem, repaired: bool) {
+        self.issues.push(FsckIssue { problem, repaired });
+    }
+}
+
+/// Items that can be decoded, the others are reported.
+fn scan_items<DI: DatabaseItem>(
+    db: &dyn Database<DI>,
+    item: &str,
+    report: &mut FsckReport,
+) -> Result<Vec<(Id, DI)>, EleboxError> {
+    let mut items = Vec::new();
+    for (id, result) in db.scan()? {
+        match result {
+            Ok(value) => items.push((id, value)),
+            Err(err) => report.push(
+                Problem::Undecodable {
+                    item: item.to_string(),
+                    id,
+                    reason: err.to_string(),
+                },
+                false,
+            ),
+        }
+    }
+    Ok(items)
+}
+
+fn check_duplicates<'a, DI: DatabaseItem + 'a>(
+    items: impl IntoIterator<Item = (&'a Id, &'a DI)>,
+    item: &str,
+    report: &mut FsckReport,
+) {
+    let mut names: BTreeMap<String, Vec<Id>> = BTreeMap::new();
+    for (id, value) in items {
+        names.entry(value.get_name()).or_default().push(id.clone());
+    }
+
+    for (name, ids) in names {
+        if ids.len() > 1 {
+            let problem = Problem::DuplicateName {
+                item: item.to_string(),
+                name,
+                ids,
+            };
+            report.push(problem, false);
+        }
+    }
+}
+
+/// Items nested under a parent of the same type.
+trait Nested: DatabaseItem + Clone {
+    /// Parent ID of top-level items.
+    const ROOT: &'static str;
+    fn parent_id(&self) -> &Id;
+    fn set_parent_id(&mut self, parent_id: &str);
+}
+
+impl Nested for DbCategory {
+    const ROOT: &'static str = ROOT_CATEGORY;
+
+    fn parent_id(&self) -> &Id {
+        &self.parent_id
+    }
+
+    fn set_parent_id(&mut self, parent_id: &str) {
+        self.parent_id = parent_id.to_string();
+    }
+}
+
+impl Nested for DbLocation {
+    const ROOT: &'static str = ROOT_LOCATION;
+
+    fn parent_id(&self) -> &Id {
+        &self.parent_id
+    }
+
+    fn set_parent_id(&mut self, parent_id: &str) {
+        self.parent_id = parent_id.to_string();
+    }
+}
+
+/// Find items that are their own ancestors, each cycle is returned once
+/// as IDs in parent order.
+fn find_cycles<DI: Nested>(items: &BTreeMap<Id, DI>) -> Vec<Vec<Id>> {
+    let mut cycles = Vec::new();
+    let mut done: BTreeSet<&Id> = BTreeSet::new();
+
+    for start in items.keys() {
+        let mut path: Vec<&Id> = Vec::new();
+        let mut current = start;
+        while !done.contains(current) {
+            if let Some(pos) = path.iter().position(|id| *id == current) {
+                cycles.push(path[pos..].iter().map(|id| id.to_string()).collect());
+                break;
+            }
+            path.push(current);
+
+            match items.get_key_value(items[current].parent_id()) {
+                Some((parent, _)) => current = parent,
+                None => break,
+            }
+        }
+        done.extend(path);
+    }
+    cycles
+}
+
+/// Report items with a missing parent and cycles of items, the repair moves
+/// the item, or the first one of the cycle, to the top level.
+fn check_parents<DI: Nested>(
+    db: &dyn Database<DI>,
+    items: &BTreeMap<Id, DI>,
+    item: &str,
+    repair: bool,
+    report: &mut FsckReport,
+) -> Result<(), EleboxError> {
+    let move_to_root = |id: &Id| {
+        let mut fixed = items[id].clone();
+        fixed.set_parent_id(DI::ROOT);
+        db.update(id, &fixed)
+    };
+
+    for (id, value) in items {
+        let parent_id = value.parent_id();
+        if parent_id != DI::ROOT && !items.contains_key(parent_id) {
+            let problem = Problem::MissingParent {
+                item: item.to_string(),
+                name: value.get_name(),
+                parent_id: parent_id.clone(),
+            };
+            report.push(problem, repair);
+
+            if repair {
+                move_to_root(id)?;
+            }
+        }
+    }
+
+    for cycle in find_cycles(items) {
+        let names = cycle.iter().map(|id| items[id].get_name()).collect();
+        let problem = Problem::ParentCycle {
+            item: item.to_string(),
+            names,
+        };
+        report.push(problem, repair);
+
+        if repair {
+            move_to_root(&cycle[0])?;
+        }
+    }
+    Ok(())
+}
+
+/// Check all items, and fix what can be fixed without losing data if
+/// `repair` is set:
+///
+/// - Missing packages, manufacturers, locations and suppliers of parts are
+///   cleared, stock in a missing location is kept without a location.
+/// - Categories and locations with a missing parent, or that break a cycle,
+///   are moved to the top level.
+/// - Ledger entries of missing parts with a balance left are closed by an
+///   adjustment, the entries are kept.
+/// - BOM lines of missing parts are removed from their projects, and order
+///   lines of missing parts from their orders.
+///
+/// Missing categories of parts, missing suppliers of orders, duplicate names
+/// and undecodable items are only reported.
+pub(crate) fn fsck(mgr: &Manager, repair: bool) -> Result<FsckReport, EleboxError> {
+    let part_db = &*mgr.part_db;
+    let category_db = &*mgr.category_db;
+    let package_db = &*mgr.package_db;
+    let mfr_db = &*mgr.mfr_db;
+    let location_db = &*mgr.location_db;
+    let supplier_db = &*mgr.supplier_db;
+    let ledger_db = &*mgr.ledger_db;
+    let project_db = &*mgr.project_db;
+    let order_db = &*mgr.order_db;
+    let mut report = FsckReport::default();
+
+    let parts = scan_items(part_db, ITEM_PART, &mut report)?;
+    let cats: BTreeMap<Id, DbCategory> = scan_items(category_db, ITEM_CAT, &mut report)?
+        .into_iter()
+        .collect();
+    let pkgs = scan_items(package_db, ITEM_PKG, &mut report)?;
+    let mfrs = scan_items(mfr_db, ITEM_MFR, &mut report)?;
+    let locs: BTreeMap<Id, DbLocation> = scan_items(location_db, ITEM_LOCATION, &mut report)?
+        .into_iter()
+        .collect();
+    let sups = scan_items(supplier_db, ITEM_SUPPLIER, &mut report)?;
+    let entries = scan_items(ledger_db, ITEM_LEDGER, &mut report)?;
+    let projects = scan_items(project_db, ITEM_PROJECT, &mut report)?;
+    let orders = scan_items(order_db, ITEM_ORDER, &mut report)?;
+
+    check_duplicates(parts.iter().map(|(id, v)| (id, v)), ITEM_PART, &mut report);
+    check_duplicates(&cats, ITEM_CAT, &mut report);
+    check_duplicates(pkgs.iter().map(|(id, v)| (id, v)), ITEM_PKG, &mut report);
+    check_duplicates(mfrs.iter().map(|(id, v)| (id, v)), ITEM_MFR, &mut report);
+    check_duplicates(&locs, ITEM_LOCATION, &mut report);
+    check_duplicates(
+        sups.iter().map(|(id, v)| (id, v)),
+        ITEM_SUPPLIER,
+        &mut report,
+    );
+
+    let part_ids: BTreeSet<&Id> = parts.iter().map(|(id, _)| id).collect();
+    let pkg_ids: BTreeSet<&Id> = pkgs.iter().map(|(id, _)| id).collect();
+    let mfr_ids: BTreeSet<&Id> = mfrs.iter().map(|(id, _)| id).collect();
+    let sup_ids: BTreeSet<&Id> = sups.iter().map(|(id, _)| id).collect();
+
+    for (id, part) in &parts {
+        let mut fixed = part.clone();
+
+        // Also catches the `__DELETE__` placeholder, which is never an ID
+        if !cats.contains_key(&part.category_id) {
+            let problem = Problem::MissingReference {
+                part: part.name.clone(),
+                item: ITEM_CAT.to_string(),
+                id: part.category_id.clone(),
+            };
+            report.push(problem, false);
+        }
+
+        // Empty if the part has none
+        if !part.package_id.is_empty() && !pkg_ids.contains(&part.package_id) {
+            let problem = Problem::MissingReference {
+                part: part.name.clone(),
+                item: ITEM_PKG.to_string(),
+                id: part.package_id.clone(),
+            };
+            report.push(problem, repair);
+            fixed.package_id = String::new();
+        }
+
+        if !part.mfr_id.is_empty() && !mfr_ids.contains(&part.mfr_id) {
+            let problem = Problem::MissingReference {
+                part: part.name.clone(),
+                item: ITEM_MFR.to_string(),
+                id: part.mfr_id.clone(),
+            };
+            report.push(problem, repair);
+            fixed.mfr_id = String::new();
+        }
+
+        if !part.location_id.is_empty() && !locs.contains_key(&part.location_id) {
+            let problem = Problem::MissingReference {
+                part: part.name.clone(),
+                item: ITEM_LOCATION.to_string(),
+                id: part.location_id.clone(),
+            };
+            report.push(problem, repair);
+            fixed.relocate(&part.location_id, "")?;
+        }
+
+        for line in &part.stock {
+            if !line.location_id.is_empty() && !locs.contains_key(&line.location_id) {
+                let problem = Problem::MissingReference {
+                    part: part.name.clone(),
+                    item: ITEM_LOCATION.to_string(),
+                    id: line.location_id.clone(),
+                };
+                report.push(problem, repair);
+                fixed.relocate(&line.location_id, "")?;
+            }
+        }
+
+        for (offer, fixed_offer) in part.suppliers.iter().zip(fixed.suppliers.iter_mut()) {
+            if !offer.supplier_id.is_empty() && !sup_ids.contains(&offer.supplier_id) {
+                let problem = Problem::MissingReference {
+                    part: part.name.clone(),
+                    item: ITEM_SUPPLIER.to_string(),
+                    id: offer.supplier_id.clone(),
+                };
+                report.push(problem, repair);
+                fixed_offer.supplier_id = String::new();
+            }
+        }
+
+        if repair && fixed != *part {
+            part_db.update(id, &fixed)?;
+        }
+    }
+
+    check_parents(category_db, &cats, ITEM_CAT, repair, &mut report)?;
+    check_parents(location_db, &locs, ITEM_LOCATION, repair, &mut report)?;
+
+    // Entries of deleted parts are kept, only a balance left open counts
+    let mut balances: BTreeMap<&Id, i64> = BTreeMap::new();
+    for (_, entry) in &entries {
+        if !part_ids.contains(&entry.part_id) {
+            *balances.entry(&entry.part_id).or_default() += entry.delta as i64;
+        }
+    }
+    let open: Vec<Id> = balances
+        .into_iter()
+        .filter(|(_, balance)| *balance != 0)
+        .map(|(part_id, _)| part_id.clone())
+        .collect();
+    for (id, entry) in &entries {
+        if open.contains(&entry.part_id) {
+            let problem = Problem::DanglingReference {
+                item: ITEM_LEDGER.to_string(),
+                name: id.clone(),
+                target: ITEM_PART.to_string(),
+                id: entry.part_id.clone(),
+            };
+            report.push(problem, repair);
+        }
+    }
+    if repair {
+        close(ledger_db, &open)?;
+    }
+
+    for (id, project) in &projects {
+        let mut fixed = project.clone();
+        for line in &project.bom {
+            if !part_ids.contains(&line.part_id) {
+                let problem = Problem::DanglingReference {
+                    item: ITEM_PROJECT.to_string(),
+                    name: project.name.clone(),
+                    target: ITEM_PART.to_string(),
+                    id: line.part_id.clone(),
+                };
+                report.push(problem, repair);
+                fixed.bom.retain(|l| l.part_id != line.part_id);
+            }
+        }
+
+        if repair && fixed != *project {
+            project_db.update(id, &fixed)?;
+        }
+    }
+
+    for (id, order) in &orders {
+        if !sup_ids.contains(&order.supplier_id) {
+            let problem = Problem::DanglingReference {
+                item: ITEM_ORDER.to_string(),
+                name: order.name.clone(),
+                target: ITEM_SUPPLIER.to_string(),
+                id: order.supplier_id.clone(),
+            };
+            report.push(problem, false);
+        }
+
+        let mut fixed = order.clone();
+        for line in &order.lines {
+            if !part_ids.contains(&line.part_id) {
+                let problem = Problem::DanglingReference {
+                    item: ITEM_ORDER.to_string(),
+                    name: order.name.clone(),
+                    target: ITEM_PART.to_string(),
+                    id: line.part_id.clone(),
+                };
+                report.push(problem, repair);
+                fixed.lines.retain(|l| l.part_id != line.part_id);
+            }
+        }
+
+        if repair && fixed != *order {
+            order_db.update(id, &fixed)?;
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(mgr: &Manager, repair: bool) -> FsckReport {
+        fsck(mgr, repair).expect("Expected OK")
+    }
+
+    fn new_category(name: &str, parent_id: &str) -> DbCategory {
+        DbCategory {
+            name: name.to_string(),
+            parent_id: parent_id.to_string(),
+            alias: "".to_string(),
+            parameters: vec![],
+        }
+    }
+
+    fn new_part(name: &str, category_id: &str, mfr_id: &str) -> DbPart {
+        DbPart {
+            name: name.to_string(),
+            quantity: 1,
+            category_id: category_id.to_string(),
+            package_id: "".to_string(),
+            package_detail: "".to_string(),
+            mfr_id: mfr_id.to_string(),
+            alias: "".to_string(),
+            description: "".to_string(),
+            location_id: "".to_string(),
+            mfr_no: "".to_string(),
+            datasheet_link: "".to_string(),
+            product_link: "".to_string(),
+            image_link: "".to_string(),
+            custom_fields: vec![],
+            suppliers: vec![],
+            starred: false,
+            min_stock: None,
+            reorder_quantity: None,
+            stock: vec![],
+        }
+    }
+
+    /// Categories A and B are each other's parent, C has a missing parent,
+    /// part P1 has a missing manufacturer and P2 a deleted category.
+    fn new_dbs() -> Manager {
+        let dbs = Manager::in_memory();
+        let cat_db = &dbs.category_db;
+        cat_db.update("ID_A", &new_category("A", "ID_B")).unwrap();
+        cat_db.update("ID_B", &new_category("B", "ID_A")).unwrap();
+        cat_db.update("ID_C", &new_category("C", "ID_X")).unwrap();
+
+        let part_db = &dbs.part_db;
+        part_db
+            .update("ID_P1", &new_part("P1", "ID_A", "ID_Y"))
+            .unwrap();
+        part_db
+            .update("ID_P2", &new_part("P2", "__DELETE__", ""))
+            .unwrap();
+        part_db
+            .update("ID_P3", &new_part("P1", "ID_C", ""))
+            .unwrap();
+        dbs
+    }
+
+    #[test]
+    fn test_fsck_report() {
+        // Arrange
+        let dbs = new_dbs();
+
+        // Act
+        let report = check(&dbs, false);
+
+        // Assert
+        let problems: Vec<&Problem> = report.unresolved().collect();
+        assert_eq!(problems.len(), 5);
+        assert!(problems.contains(&&Problem::ParentCycle {
+            item: ITEM_CAT.to_string(),
+            names: vec!["A".to_string(), "B".to_string()],
+        }));
+        assert!(problems.contains(&&Problem::MissingParent {
+            item: ITEM_CAT.to_string(),
+            name: "C".to_string(),
+            parent_id: "ID_X".to_string(),
+        }));
+        assert!(problems.contains(&&Problem::MissingReference {
+            part: "P1".to_string(),
+            item: ITEM_MFR.to_string(),
+            id: "ID_Y".to_string(),
+        }));
+        assert!(problems.contains(&&Problem::MissingReference {
+            part: "P2".to_string(),
+            item: ITEM_CAT.to_string(),
+            id: "__DELETE__".to_string(),
+        }));
+        assert!(problems.contains(&&Problem::DuplicateName {
+            item: ITEM_PART.to_string(),
+            name: "P1".to_string(),
+            ids: vec!["ID_P1".to_string(), "ID_P3".to_string()],
+        }));
+    }
+
+    #[test]
+    fn test_fsck_repair() {
+        // Arrange
+        let dbs = new_dbs();
+
+        // Act
+        let report = check(&dbs, true);
+        let recheck = check(&dbs, false);
+
+        // Assert
+        assert_eq!(report.unresolved().count(), 2);
+        assert_eq!(recheck.unresolved().count(), 2);
+        assert_eq!(
+            dbs.category_db.get("ID_A").unwrap().parent_id,
+            ROOT_CATEGORY
+        );
+        assert_eq!(dbs.category_db.get("ID_B").unwrap().parent_id, "ID_A");
+        assert_eq!(
+            dbs.category_db.get("ID_C").unwrap().parent_id,
+            ROOT_CATEGORY
+        );
+        assert_eq!(dbs.part_db.get("ID_P1").unwrap().mfr_id, "");
+    }
+
+    #[test]
+    fn test_fsck_location_parents() {
+        // Arrange
+        let dbs = Manager::in_memory();
+        let location = |name: &str, parent_id: &str| DbLocation {
+            name: name.to_string(),
+            parent_id: parent_id.to_string(),
+            alias: "".to_string(),
+        };
+        let loc_db = &dbs.location_db;
+        loc_db
+            .update("ID_L1", &location("Lab", ROOT_LOCATION))
+            .unwrap();
+        loc_db
+            .update("ID_L2", &location("Cabinet", "ID_L3"))
+            .unwrap();
+        loc_db
+            .update("ID_L3", &location("Drawer", "ID_L2"))
+            .unwrap();
+        loc_db.update("ID_L4", &location("Shelf", "ID_X")).unwrap();
+
+        // Act
+        let report = check(&dbs, true);
+        let recheck = check(&dbs, false);
+
+        // Assert
+        let problems: Vec<&Problem> = report.issues.iter().map(|i| &i.problem).collect();
+        assert_eq!(
+            problems,
+            vec![
+                &Problem::MissingParent {
+                    item: ITEM_LOCATION.to_string(),
+                    name: "Shelf".to_string(),
+                    parent_id: "ID_X".to_string(),
+                },
+                &Problem::ParentCycle {
+                    item: ITEM_LOCATION.to_string(),
+                    names: vec!["Cabinet".to_string(), "Drawer".to_string()],
+                },
+            ]
+        );
+        assert!(recheck.is_clean());
+        assert_eq!(loc_db.get("ID_L2").unwrap().parent_id, ROOT_LOCATION);
+        assert_eq!(loc_db.get("ID_L3").unwrap().parent_id, "ID_L2");
+        assert_eq!(loc_db.get("ID_L4").unwrap().parent_id, ROOT_LOCATION);
+    }
+
+    #[test]
+    fn test_fsck_stock_locations() {
+        // Arrange
+        let dbs = Manager::in_memory();
+        dbs.category_db
+            .update("ID_A", &new_category("A", ROOT_CATEGORY))
+            .unwrap();
+        let shelf = DbLocation {
+            name: "Shelf".to_string(),
+            parent_id: ROOT_LOCATION.to_string(),
+            alias: "".to_string(),
+        };
+        dbs.location_db.update("ID_L1", &shelf).unwrap();
+        let line = |location_id: &str, quantity: u16| DbStockLine {
+            location_id: location_id.to_string(),
+            quantity,
+        };
+        let mut part = new_part("P1", "ID_A", "");
+        part.location_id = "ID_L1".to_string();
+        part.quantity = 30;
+        part.stock = vec![line("ID_L1", 20), line("ID_X", 10)];
+        dbs.part_db.update("ID_P1", &part).unwrap();
+
+        // Act
+        let report = check(&dbs, true);
+        let recheck = check(&dbs, false);
+
+        // Assert
+        let problems: Vec<&Problem> = report.issues.iter().map(|i| &i.problem).collect();
+        assert_eq!(
+            problems,
+            vec![&Problem::MissingReference {
+                part: "P1".to_string(),
+                item: ITEM_LOCATION.to_string(),
+                id: "ID_X".to_string(),
+            }]
+        );
+        assert!(recheck.is_clean());
+        let fixed = dbs.part_db.get("ID_P1").unwrap();
+        assert_eq!(fixed.quantity, 30);
+        assert_eq!(fixed.stock, vec![line("ID_L1", 20), line("", 10)]);
+    }
+
+    #[test]
+    fn test_fsck_dangling_ledger() {
+        // Arrange
+        let dbs = Manager::in_memory();
+        dbs.category_db
+            .update("ID_A", &new_category("A", ROOT_CATEGORY))
+            .unwrap();
+        dbs.part_db
+            .update("ID_P1", &new_part("P1", "ID_A", ""))
+            .unwrap();
+        let entry = |part_id: &str, delta: i32| DbLedgerEntry {
+            part_id: part_id.to_string(),
+            timestamp: 0,
+            delta,
+            reason: "adjustment".to_string(),
+            note: "".to_string(),
+            project: "".to_string(),
+        };
+        dbs.ledger_db.update("ID_E1", &entry("ID_P1", 1)).unwrap();
+        dbs.ledger_db.update("ID_E2", &entry("ID_X", 1)).unwrap();
+        dbs.ledger_db.update("ID_E3", &entry("ID_Y", 2)).unwrap();
+        dbs.ledger_db.update("ID_E4", &entry("ID_Y", -2)).unwrap();
+
+        // Act
+        let report = check(&dbs, true);
+        let recheck = check(&dbs, false);
+
+        // Assert
+        let problems: Vec<&Problem> = report.issues.iter().map(|i| &i.problem).collect();
+        assert_eq!(
+            problems,
+            vec![&Problem::DanglingReference {
+                item: ITEM_LEDGER.to_string(),
+                name: "ID_E2".to_string(),
+                target: ITEM_PART.to_string(),
+                id: "ID_X".to_string(),
+            }]
+        );
+        assert!(recheck.is_clean());
+        let deltas: Vec<i32> = dbs
+            .ledger_db
+            .list()
+            .unwrap()
+            .iter()
+            .filter(|entry| entry.part_id == "ID_X")
+            .map(|entry| entry.delta)
+            .collect();
+        assert_eq!(deltas.len(), 2);
+        assert_eq!(deltas.iter().sum::<i32>(), 0);
+        assert_eq!(dbs.ledger_db.list().unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_fsck_dangling_bom() {
+        // Arrange
+        let dbs = Manager::in_memory();
+        dbs.category_db
+            .update("ID_A", &new_category("A", ROOT_CATEGORY))
+            .unwrap();
+        dbs.part_db
+            .update("ID_P1", &new_part("P1", "ID_A", ""))
+            .unwrap();
+        let line = |part_id: &str| DbBomItem {
+            part_id: part_id.to_string(),
+            quantity: 1,
+        };
+        let project = DbProject {
+            name: "Clock".to_string(),
+            description: "".to_string(),
+            bom: vec![line("ID_P1"), line("ID_X")],
+        };
+        dbs.project_db.update("ID_J1", &project).unwrap();
+
+        // Act
+        let report = check(&dbs, true);
+        let recheck = check(&dbs, false);
+
+        // Assert
+        let problems: Vec<&Problem> = report.issues.iter().map(|i| &i.problem).collect();
+        assert_eq!(
+            problems,
+            vec![&Problem::DanglingReference {
+                item: ITEM_PROJECT.to_string(),
+                name: "Clock".to_string(),
+                target: ITEM_PART.to_string(),
+                id: "ID_X".to_string(),
+            }]
+        );
+        assert!(recheck.is_clean());
+        assert_eq!(
+            dbs.project_db.get("ID_J1").unwrap().bom,
+            vec![line("ID_P1")]
+        );
+    }
+
+    #[test]
+    fn test_fsck_dangling_order() {
+        // Arrange
+        let dbs = Manager::in_memory();
+        dbs.category_db
+            .update("ID_A", &new_category("A", ROOT_CATEGORY))
+            .unwrap();
+        dbs.part_db
+            .update("ID_P1", &new_part("P1", "ID_A", ""))
+            .unwrap();
+        let line = |part_id: &str| DbOrderLine {
+            part_id: part_id.to_string(),
+            quantity: 10,
+            received: 0,
+        };
+        let order = DbOrder {
+            name: "PO-1".to_string(),
+            supplier_id: "ID_S".to_string(),
+            status: "draft".to_string(),
+            note: "".to_string(),
+            lines: vec![line("ID_P1"), line("ID_X")],
+        };
+        dbs.order_db.update("ID_O1", &order).unwrap();
+
+        // Act
+        let report = check(&dbs, true);
+        let recheck = check(&dbs, false);
+
+        // Assert
+        let problems: Vec<&Problem> = report.issues.iter().map(|i| &i.problem).collect();
+        assert_eq!(
+            problems,
+            vec![
+                &Problem::DanglingReference {
+                    item: ITEM_ORDER.to_string(),
+                    name: "PO-1".to_string(),
+                    target: ITEM_SUPPLIER.to_string(),
+                    id: "ID_S".to_string(),
+                },
+                &Problem::DanglingReference {
+                    item: ITEM_ORDER.to_string(),
+                    name: "PO-1".to_string(),
+                    target: ITEM_PART.to_string(),
+                    id: "ID_X".to_string(),
+                },
+            ]
+        );
+        assert_eq!(report.unresolved().count(), 1);
+        assert_eq!(recheck.unresolved().count(), 1);
+        assert_eq!(
+            dbs.order_db.get("ID_O1").unwrap().lines,
+            vec![line("ID_P1")]
+        );
+    }
+
+    #[test]
+    fn test_fsck_clean() {
+        // Arrange
+        let dbs = Manager::in_memory();
+        dbs.category_db
+            .update("ID_A", &new_category("A", ROOT_CATEGORY))
+            .unwrap();
+        dbs.part_db
+            .update("ID_P1", &new_part("P1", "ID_A", ""))
+            .unwrap();
+
+        // Act
+        let report = check(&dbs, true);
+
+        // Assert
+        assert!(report.is_clean());
+    }
+}
@@ -1,12 +1,17 @@
 use std::{
+    any::Any,
+    collections::BTreeMap,
     fmt::Debug,
+    fs::{File, TryLockError},
     panic,
     path::PathBuf,
     str::{self, from_utf8},
+    sync::{Arc, Mutex, MutexGuard},
 };
 
-use jammdb::DB;
-use serde::{Deserialize, Serialize};
+use jammdb::{Bucket, Tx, DB};
+use rust_decimal::{prelude::FromPrimitive, Decimal};
+use serde::{Deserialize, Deserializer, Serialize};
 use uuid::Uuid;
 
 use crate::{comm::*, DbError};
@@ -15,10 +20,28 @@ pub const PARTS_BUCKET: &str = "parts";
 pub const PACKAGES_BUCKET: &str = "packages";
 pub const MFR_BUCKET: &str = "manufacturers";
 pub const CATEGORIES_BUCKET: &str = "categories";
+pub const LEDGER_BUCKET: &str = "ledger";
+pub const PROJECTS_BUCKET: &str = "projects";
+pub const LOCATIONS_BUCKET: &str = "locations";
+pub const SUPPLIERS_BUCKET: &str = "suppliers";
+pub const ORDERS_BUCKET: &str = "orders";
+pub const FIELDS_BUCKET: &str = "fields";
+
+/// Suffix of the buckets that map item names to IDs, e.g. `parts_name_index`.
+pub const NAME_INDEX_SUFFIX: &str = "_name_index";
+
+/// Bucket of the schema versions, keyed by item bucket name.
+pub const META_BUCKET: &str = "meta";
 
 pub trait DatabaseItem {
     fn get_name(&self) -> String;
     fn get_bucket() -> String;
+
+    /// Whether items have a name to be found by, items without one are not
+    /// in the name index and `get_id()` finds none of them.
+    fn is_named() -> bool {
+        true
+    }
 }
 
 pub type Id = String;
@@ -27,6 +50,19 @@ pub type Id = String;
 pub enum CustomFieldType {
     Normal,
     Link,
+    /// A number with a unit, e.g. `100nF`, see `SiValue`.
+    Parameter,
+    /// A decimal number, with an optional unit that is not part of the value.
+    Number(Option<String>),
+    Integer,
+    /// `true` or `false`.
+    Boolean,
+    /// A day written as `YYYY-MM-DD`.
+    Date,
+    /// One of the choices.
+    Enum(Vec<String>),
+    /// Text with Markdown formatting.
+    Markdown,
 }
 
 #[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
@@ -36,12 +72,54 @@ pub struct CustomField {
     pub value: String,
 }
 
+/// Unit price when ordering at least `quantity`.
 #[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
-pub struct Supplier {
-    pub name: String,
+pub struct PriceBreak {
+    pub quantity: u32,
+    pub price: Decimal,
+}
+
+/// Older releases stored a single `price` as a float, it becomes the price
+/// from one piece on.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PriceBreaksOrPrice {
+    Breaks(Vec<PriceBreak>),
+    Price(Option<f32>),
+}
+
+pub(crate) fn price_breaks<'de, D>(deserializer: D) -> Result<Vec<PriceBreak>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match PriceBreaksOrPrice::deserialize(deserializer)? {
+        PriceBreaksOrPrice::Breaks(breaks) => breaks,
+        PriceBreaksOrPrice::Price(price) => price
+            .and_then(Decimal::from_f32)
+            .map(|price| vec![PriceBreak { quantity: 1, price }])
+            .unwrap_or_default(),
+    })
+}
+
+/// Offer of a supplier for a part.
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
+pub struct DbPartSupplier {
+    /// Supplier name in older releases, see `migrate_suppliers()`.
+    #[serde(alias = "name")]
+    pub supplier_id: Id,
     pub link: String,
-    pub price: Option<f32>, // TODO: change to 'rust_decimal'?
+    #[serde(default, alias = "price", deserialize_with = "price_breaks")]
+    pub price_breaks: Vec<PriceBreak>,
     pub note: String,
+    // Added with the supplier records, missing in older records
+    #[serde(default)]
+    pub sku: String,
+    #[serde(default)]
+    pub min_order: Option<u32>,
+    #[serde(default)]
+    pub pack_size: Option<u32>,
+    #[serde(default)]
+    pub currency: String,
 }
 
 #[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
@@ -54,14 +132,33 @@ pub struct DbPart {
     pub mfr_id: Id,
     pub alias: String,
     pub description: String,
-    pub location: String,
+    /// Free-form text in older releases, see `migrate_locations()`.
+    #[serde(alias = "location")]
+    pub location_id: Id,
     pub mfr_no: String,
     pub datasheet_link: String,
     pub product_link: String,
     pub image_link: String,
+    // Added after the first release, missing in older records
+    #[serde(default)]
     pub custom_fields: Vec<CustomField>,
-    pub suppliers: Vec<Supplier>,
+    #[serde(default)]
+    pub suppliers: Vec<DbPartSupplier>,
+    #[serde(default)]
     pub starred: bool,
+    #[serde(default)]
+    pub min_stock: Option<u16>,
+    #[serde(default)]
+    pub reorder_quantity: Option<u16>,
+    /// Quantity by location, `quantity` is the total of it.
+    #[serde(default)]
+    pub stock: Vec<DbStockLine>,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
+pub struct DbStockLine {
+    pub location_id: Id,
+    pub quantity: u16,
 }
 
 impl DatabaseItem for DbPart {
@@ -74,11 +171,25 @@ impl DatabaseItem for DbPart {
     }
 }
 
-#[derive(Debug, PartialEq, Deserialize, Serialize)]
+/// A field the parts of a category are expected to have, e.g. the
+/// capacitance of capacitors.
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
+pub struct ParamTemplate {
+    pub name: String,
+    pub field_type: CustomFieldType,
+    /// Unit of a `Parameter` field, e.g. `F`, empty for any.
+    #[serde(default)]
+    pub unit: String,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
 pub struct DbCategory {
     pub name: String,
     pub parent_id: Id,
     pub alias: String,
+    /// Own fields of the template, without the inherited ones.
+    #[serde(default)]
+    pub parameters: Vec<ParamTemplate>,
 }
 
 impl DatabaseItem for DbCategory {
@@ -91,7 +202,59 @@ impl DatabaseItem for DbCategory {
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
+pub struct DbSupplier {
+    pub name: String,
+    pub alias: String,
+    pub url: String,
+}
+
+impl DatabaseItem for DbSupplier {
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_bucket() -> String {
+        String::from(SUPPLIERS_BUCKET)
+    }
+}
+
+/// Definition of a custom field shared by all parts.
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
+pub struct DbFieldDef {
+    pub name: String,
+    pub field_type: CustomFieldType,
+    pub description: String,
+}
+
+impl DatabaseItem for DbFieldDef {
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_bucket() -> String {
+        String::from(FIELDS_BUCKET)
+    }
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
+pub struct DbLocation {
+    pub name: String,
+    pub parent_id: Id,
+    pub alias: String,
+}
+
+impl DatabaseItem for DbLocation {
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_bucket() -> String {
+        String::from(LOCATIONS_BUCKET)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct DbPackage {
     pub pkg_type: String, // TODO enum
     pub name: String,
@@ -108,7 +271,7 @@ impl DatabaseItem for DbPackage {
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct DbManufacturer {
     pub name: String,
     pub alias: String,
@@ -125,127 +288,868 @@ impl DatabaseItem for DbManufacturer {
     }
 }
 
+/// One stock movement of a part, entries are only ever appended.
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
+pub struct DbLedgerEntry {
+    pub part_id: Id,
+    /// Unix time in seconds.
+    pub timestamp: i64,
+    pub delta: i32,
+    pub reason: String,
+    pub note: String,
+    pub project: String,
+}
+
+impl DatabaseItem for DbLedgerEntry {
+    // Entries have no name, they are listed by part
+    fn get_name(&self) -> String {
+        String::new()
+    }
+
+    fn get_bucket() -> String {
+        String::from(LEDGER_BUCKET)
+    }
+
+    fn is_named() -> bool {
+        false
+    }
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
+pub struct DbBomItem {
+    pub part_id: Id,
+    /// Quantity per board.
+    pub quantity: u16,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
+pub struct DbProject {
+    pub name: String,
+    pub description: String,
+    pub bom: Vec<DbBomItem>,
+}
+
+impl DatabaseItem for DbProject {
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_bucket() -> String {
+        String::from(PROJECTS_BUCKET)
+    }
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
+pub struct DbOrderLine {
+    pub part_id: Id,
+    pub quantity: u16,
+    /// Quantity received so far.
+    pub received: u16,
+}
+
+/// A purchase order at a supplier.
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
+pub struct DbOrder {
+    pub name: String,
+    pub supplier_id: Id,
+    pub status: String,
+    pub note: String,
+    pub lines: Vec<DbOrderLine>,
+}
+
+impl DatabaseItem for DbOrder {
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_bucket() -> String {
+        String::from(ORDERS_BUCKET)
+    }
+}
+
+type StagedWrite = Box<dyn FnOnce(&Tx) -> Result<(), DbError> + Send>;
+
+/// Changes made in a transaction, they are kept in memory and written in one
+/// jammdb write transaction by the outermost commit.
+#[derive(Default)]
+struct Staging {
+    /// Encoded items by bucket and ID, `None` if deleted.
+    items: BTreeMap<String, BTreeMap<Id, Option<Vec<u8>>>>,
+    /// Schema versions by bucket.
+    versions: BTreeMap<String, u32>,
+    writes: Vec<StagedWrite>,
+}
+
+/// jammdb backend, the file is opened on first access and the handle is kept
+/// open afterwards. Clones share the same handle, so give each `Database`
+/// of a `Manager` a clone rather than a new instance.
+///
+/// Clones also share the transaction, while it is active every read of any
+/// clone on the same thread sees the staged changes. Other threads read and
+/// write the file directly.
+#[derive(Clone)]
 pub struct JammDatabase {
     /// Database file path.
     path: PathBuf,
+    db: Arc<Mutex<Option<DB>>>,
+    staging: Arc<Mutex<Staging>>,
+    tx: Arc<TxLock>,
 }
 
 impl JammDatabase {
     pub fn new(path: &str) -> Self {
         Self {
             path: PathBuf::from(path),
+            db: Arc::new(Mutex::new(None)),
+            staging: Arc::new(Mutex::new(Staging::default())),
+            tx: Arc::new(TxLock::default()),
+        }
+    }
+
+    fn staging(&self) -> MutexGuard<'_, Staging> {
+        self.staging.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Staged changes of the bucket, `None` if it has not been changed in
+    /// the active transaction of this thread.
+    fn staged(&self, bucket: &str) -> Option<BTreeMap<Id, Option<Vec<u8>>>> {
+        if !self.tx.is_owner() {
+            return None;
+        }
+        self.staging().items.get(bucket).cloned()
+    }
+
+    /// Write or delete (`None`) the item, the change is staged if this
+    /// thread has an active transaction.
+    fn write<DI>(&self, id: &str, item: Option<&DI>) -> Result<(), DbError>
+    where
+        DI: Serialize + for<'de> Deserialize<'de> + DatabaseItem + Clone + Send + 'static,
+    {
+        if self.tx.is_owner() {
+            let mut staging = self.staging();
+            let value = item.map(encode).transpose()?;
+            staging
+                .items
+                .entry(DI::get_bucket())
+                .or_default()
+                .insert(id.to_string(), value);
+
+            let id = id.to_string();
+            let item = item.cloned();
+            staging.writes.push(Box::new(move |tx| match &item {
+                Some(item) => put_item(tx, &id, item),
+                None => delete_item::<DI>(tx, &id),
+            }));
+            return Ok(());
+        }
+
+        let db = self.open()?;
+        let tx = db.tx(true)?;
+        match item {
+            Some(item) => put_item(&tx, id, item)?,
+            None => delete_item::<DI>(&tx, id)?,
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// End a level of the transaction, returns whether the outermost one committed.
+    fn end(&self, commit: bool) -> Result<Option<bool>, DbError> {
+        self.tx.end(commit, |commit| {
+            let writes = {
+                let mut staging = self.staging();
+                staging.items.clear();
+                staging.versions.clear();
+                std::mem::take(&mut staging.writes)
+            };
+
+            if commit && !writes.is_empty() {
+                let db = self.open()?;
+                let tx = db.tx(true)?;
+                for write in writes {
+                    write(&tx)?;
+                }
+                tx.commit()?;
+            }
+            Ok(())
+        })
+    }
+
+    fn path_name(&self) -> String {
+        self.path.to_string_lossy().into_owned()
+    }
+
+    /// Get the shared handle, opening the file if not opened yet.
+    fn open(&self) -> Result<DB, DbError> {
+        let mut db = self
+            .db
+            .lock()
+            .map_err(|_| DbError::CannotOpenDb(self.path_name()))?;
+
+        if let Some(db) = db.as_ref() {
+            return Ok(db.clone());
+        }
+
+        // jammdb waits for the file lock, check it first so we can fail instead
+        if self.path.exists() {
+            let file = File::open(&self.path)?;
+            match file.try_lock() {
+                Ok(()) => file.unlock()?,
+                Err(TryLockError::WouldBlock) => return Err(DbError::Locked(self.path_name())),
+                Err(TryLockError::Error(err)) => return Err(DbError::Io(err)),
+            }
         }
+
+        // jammdb panics on files that are not a valid database
+        let new_db = match panic::catch_unwind(|| DB::open(&self.path)) {
+            Ok(res) => res?,
+            Err(cause) => return Err(DbError::Corrupted(panic_message(cause))),
+        };
+        *db = Some(new_db.clone());
+        Ok(new_db)
     }
+
+    /// Rebuild the name index of the item type from scratch, for databases
+    /// created before the index existed.
+    pub fn rebuild_name_index<DI>(&self) -> Result<(), DbError>
+    where
+        DI: for<'de> Deserialize<'de> + DatabaseItem,
+    {
+        let db = self.open()?;
+        let tx = db.tx(true)?;
+        if tx.get_bucket(index_bucket::<DI>()).is_ok() {
+            tx.delete_bucket(index_bucket::<DI>())?;
+        }
+        fill_name_index::<DI>(&tx)?;
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+fn panic_message(cause: Box<dyn Any + Send>) -> String {
+    if let Some(s) = cause.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = cause.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown error".to_string()
+    }
+}
+
+fn get_bucket<'b, 'tx>(tx: &'b Tx<'tx>, name: &str) -> Result<Bucket<'b, 'tx>, DbError> {
+    tx.get_bucket(name.to_string()).map_err(|err| match err {
+        jammdb::Error::BucketMissing => DbError::BucketMissing(name.to_string()),
+        others => DbError::from(others),
+    })
+}
+
+fn encode<DI: Serialize + DatabaseItem>(item: &DI) -> Result<Vec<u8>, DbError> {
+    // Named fields, so fields can be added without breaking older records
+    rmp_serde::to_vec_named(item)
+        .map_err(|err| DbError::Serialization(DI::get_bucket(), err.to_string()))
+}
+
+fn decode<DI>(value: &[u8]) -> Result<DI, DbError>
+where
+    DI: for<'de> Deserialize<'de> + DatabaseItem,
+{
+    rmp_serde::from_slice(value)
+        .map_err(|err| DbError::Serialization(DI::get_bucket(), err.to_string()))
+}
+
+fn decode_id(key: &[u8]) -> Result<Id, DbError> {
+    from_utf8(key)
+        .map(|id| id.to_string())
+        .map_err(|err| DbError::Corrupted(format!("invalid ID, {}", err)))
+}
+
+fn index_bucket<DI: DatabaseItem>() -> String {
+    format!("{}{}", DI::get_bucket(), NAME_INDEX_SUFFIX)
+}
+
+/// Create the name index bucket and fill it from the item bucket.
+fn fill_name_index<DI>(tx: &Tx) -> Result<(), DbError>
+where
+    DI: for<'de> Deserialize<'de> + DatabaseItem,
+{
+    if !DI::is_named() {
+        return Ok(());
+    }
+
+    let bkt = get_bucket(tx, &DI::get_bucket())?;
+    let index = tx.create_bucket(index_bucket::<DI>())?;
+
+    for data in bkt.cursor() {
+        let item: DI = decode(data.kv().value())?;
+
+        // Keep the first one if names are duplicated, same as a full scan
+        if index.get_kv(item.get_name()).is_none() {
+            index.put(item.get_name(), data.kv().key().to_vec())?;
+        }
+    }
+    Ok(())
+}
+
+/// Remove the index entry of the item with the given ID, if any.
+fn unindex<DI>(tx: &Tx, id: &str) -> Result<(), DbError>
+where
+    DI: for<'de> Deserialize<'de> + DatabaseItem,
+{
+    let index = match tx.get_bucket(index_bucket::<DI>()) {
+        Ok(index) if DI::is_named() => index,
+        _ => return Ok(()),
+    };
+
+    let bkt = get_bucket(tx, &DI::get_bucket())?;
+    let old_name = match bkt.get_kv(id) {
+        Some(kv) => decode::<DI>(kv.value())?.get_name(),
+        None => return Ok(()),
+    };
+
+    let indexed = index
+        .get_kv(&old_name)
+        .is_some_and(|kv| kv.value() == id.as_bytes());
+    if indexed {
+        index.delete(&old_name)?;
+    }
+    Ok(())
+}
+
+/// Point the name of the item to the given ID, if the index exists.
+fn index<DI: DatabaseItem>(tx: &Tx, id: &str, item: &DI) -> Result<(), DbError> {
+    if !DI::is_named() {
+        return Ok(());
+    }
+    if let Ok(index) = tx.get_bucket(index_bucket::<DI>()) {
+        index.put(item.get_name(), id.to_string())?;
+    }
+    Ok(())
+}
+
+/// Insert or replace the item and update the name index.
+fn put_item<DI>(tx: &Tx, id: &str, item: &DI) -> Result<(), DbError>
+where
+    DI: Serialize + for<'de> Deserialize<'de> + DatabaseItem,
+{
+    unindex::<DI>(tx, id)?;
+
+    let bkt = get_bucket(tx, &DI::get_bucket())?;
+    bkt.put(id.to_string(), encode(item)?)?;
+    index(tx, id, item)
+}
+
+/// Delete the item and its name index entry.
+fn delete_item<DI>(tx: &Tx, id: &str) -> Result<(), DbError>
+where
+    DI: for<'de> Deserialize<'de> + DatabaseItem,
+{
+    unindex::<DI>(tx, id)?;
+
+    let bkt = get_bucket(tx, &DI::get_bucket())?;
+    bkt.delete(id).map_err(|err| match err {
+        jammdb::Error::KeyValueMissing => DbError::NotExists(DI::get_bucket()),
+        others => DbError::from(others),
+    })?;
+    Ok(())
+}
+
+fn put_version<DI: DatabaseItem>(tx: &Tx, version: u32) -> Result<(), DbError> {
+    let meta = tx.get_or_create_bucket(META_BUCKET)?;
+    meta.put(DI::get_bucket(), version.to_be_bytes().to_vec())?;
+    Ok(())
 }
 
-// TODO return Err
 impl<DI> Database<DI> for JammDatabase
 where
-    DI: Serialize + for<'de> Deserialize<'de> + DatabaseItem + Send + Sync,
+    DI: Serialize + for<'de> Deserialize<'de> + DatabaseItem + Clone + Send + Sync + 'static,
 {
     fn init(&self) -> Result<(), DbError> {
-        let db = DB::open(&self.path).unwrap();
-        let tx = db.tx(true).unwrap();
-        tx.get_or_create_bucket(DI::get_bucket()).unwrap();
-        let _ = tx.commit().unwrap();
+        let db = self.open()?;
+        let tx = db.tx(true)?;
+        tx.get_or_create_bucket(DI::get_bucket())?;
+
+        // Databases created by older versions have no index yet, or one of
+        // items that have no name
+        let has_index = tx.get_bucket(index_bucket::<DI>()).is_ok();
+        if has_index && !DI::is_named() {
+            tx.delete_bucket(index_bucket::<DI>())?;
+        } else if !has_index {
+            fill_name_index::<DI>(&tx)?;
+        }
+        tx.commit()?;
         Ok(())
     }
 
     fn add(&self, item: &DI) -> Result<(), DbError> {
-        let db = DB::open(&self.path).unwrap();
-        let tx = db.tx(true).unwrap();
-        let bkt = tx.get_bucket(DI::get_bucket()).unwrap();
-
-        let value = rmp_serde::to_vec(&item).unwrap();
         let id = Uuid::new_v4().to_string();
-        bkt.put(id, value).unwrap();
-        let _ = tx.commit();
-        Ok(())
+        self.write(&id, Some(item))
     }
 
     fn update(&self, ori_id: &str, new_item: &DI) -> Result<(), DbError> {
-        let db = DB::open(&self.path)?;
-        let tx = db.tx(true).unwrap();
-        let bkt = tx.get_bucket(DI::get_bucket()).unwrap();
-
-        let value = rmp_serde::to_vec(&new_item).unwrap();
-        bkt.put(ori_id, value).unwrap();
-        let _ = tx.commit();
-        Ok(())
+        self.write(ori_id, Some(new_item))
     }
 
     fn get_id(&self, name: &str) -> Result<String, DbError> {
-        let db = DB::open(&self.path).unwrap();
-        let tx = db.tx(false).unwrap();
+        if !DI::is_named() {
+            return Err(DbError::NotExists(DI::get_bucket()));
+        }
 
-        let bkt = tx.get_bucket(DI::get_bucket()).expect(&DI::get_bucket());
+        // The index does not know the staged changes
+        if self.staged(&DI::get_bucket()).is_some() {
+            let items: Vec<(Id, DI)> = self.list_with_id()?;
+            return items
+                .into_iter()
+                .find(|(_, item)| item.get_name() == name)
+                .map(|(id, _)| id)
+                .ok_or_else(|| DbError::NotExists(DI::get_bucket()));
+        }
 
-        for data in bkt.cursor() {
-            let item: DI = rmp_serde::from_slice(data.kv().value()).unwrap();
+        let db = self.open()?;
+        let tx = db.tx(false)?;
 
-            if &item.get_name() == name {
-                let id = from_utf8(data.kv().key()).unwrap();
-                return Ok(id.to_string());
+        if let Ok(index) = tx.get_bucket(index_bucket::<DI>()) {
+            return match index.get_kv(name) {
+                Some(kv) => decode_id(kv.value()),
+                None => Err(DbError::NotExists(DI::get_bucket())),
+            };
+        }
+
+        // Fall back to a full scan if the index has not been built
+        let bkt = get_bucket(&tx, &DI::get_bucket())?;
+        for data in bkt.cursor() {
+            let item: DI = decode(data.kv().value())?;
+            if item.get_name() == name {
+                return decode_id(data.kv().key());
             };
         }
         Err(DbError::NotExists(DI::get_bucket())) // TODO
     }
 
     fn get(&self, id: &str) -> Result<DI, DbError> {
-        let db = DB::open(&self.path).unwrap();
-        let tx = db.tx(false).unwrap();
-        let bkt = tx.get_bucket(DI::get_bucket()).unwrap();
+        if let Some(value) = self
+            .staged(&DI::get_bucket())
+            .and_then(|mut bkt| bkt.remove(id))
+        {
+            return match value {
+                Some(value) => decode(&value),
+                None => Err(DbError::NotExists(DI::get_bucket())),
+            };
+        }
+
+        let db = self.open()?;
+        let tx = db.tx(false)?;
+        let bkt = get_bucket(&tx, &DI::get_bucket())?;
 
-        if let Some(data) = bkt.get(id) {
-            return Ok(rmp_serde::from_slice(data.kv().value()).unwrap());
+        if let Some(kv) = bkt.get_kv(id) {
+            return decode(kv.value());
         }
         Err(DbError::NotExists(DI::get_bucket())) // TODO
     }
 
     fn list(&self) -> Result<Vec<DI>, DbError> {
-        let db = DB::open(&self.path).unwrap();
-        let tx = db.tx(false).unwrap();
-        let bkt = tx.get_bucket(DI::get_bucket()).unwrap();
+        let items = self.list_with_id()?;
+        Ok(items.into_iter().map(|(_, item)| item).collect())
+    }
+
+    fn list_with_id(&self) -> Result<Vec<(Id, DI)>, DbError> {
+        let items = self.scan()?;
+        items
+            .into_iter()
+            .map(|(id, item)| item.map(|item| (id, item)))
+            .collect()
+    }
 
-        let mut items: Vec<DI> = Vec::new();
+    fn scan(&self) -> Result<Scanned<DI>, DbError> {
+        let db = self.open()?;
+        let tx = db.tx(false)?;
+        let bkt = get_bucket(&tx, &DI::get_bucket())?;
+
+        let mut items: Scanned<DI> = Vec::new();
         for data in bkt.cursor() {
-            let item: DI = rmp_serde::from_slice::<DI>(data.kv().value()).unwrap();
-            items.push(item);
+            let id = decode_id(data.kv().key())?;
+            items.push((id, decode(data.kv().value())));
         }
-        Ok(items)
+
+        let staged = match self.staged(&DI::get_bucket()) {
+            Some(staged) => staged,
+            None => return Ok(items),
+        };
+
+        let mut items: BTreeMap<Id, Result<DI, DbError>> = items.into_iter().collect();
+        for (id, value) in staged {
+            match value {
+                Some(value) => items.insert(id, decode(&value)),
+                None => items.remove(&id),
+            };
+        }
+        Ok(items.into_iter().collect())
     }
 
     fn delete(&self, id: &str) -> Result<(), DbError> {
-        let db = DB::open(&self.path).unwrap();
-        let tx = db.tx(true).unwrap();
-        let bkt = tx.get_bucket(DI::get_bucket()).unwrap();
+        if self.tx.is_owner() {
+            Database::<DI>::get(self, id)?;
+        }
+        self.write::<DI>(id, None)
+    }
 
-        bkt.delete(id).unwrap();
-        let _ = tx.commit();
+    fn check(&self) -> Result<(), DbError> {
+        let db = self.open()?;
+        let tx = db.tx(false)?;
+        get_bucket(&tx, &DI::get_bucket())?;
         Ok(())
     }
 
-    fn check(&self) -> Result<(), DbError> {
-        let result = panic::catch_unwind(|| {
-            println!("Db");
-            let db = DB::open(&self.path).unwrap();
-            println!("Tx");
-            let tx = db.tx(false).unwrap();
-            println!("Bk");
-            let _bkt = tx.get_bucket(DI::get_bucket()).unwrap();
-            println!("ok");
-        });
-
-        if result.is_err() {
-            return Err(DbError::AccessFailed("".to_string()));
+    fn get_version(&self) -> Result<u32, DbError> {
+        if self.tx.is_owner() {
+            if let Some(version) = self.staging().versions.get(&DI::get_bucket()) {
+                return Ok(*version);
+            }
+        }
+
+        let db = self.open()?;
+        let tx = db.tx(false)?;
+        let meta = match tx.get_bucket(META_BUCKET) {
+            Ok(meta) => meta,
+            Err(_) => return Ok(0),
+        };
+
+        match meta.get_kv(DI::get_bucket()) {
+            Some(kv) => kv.value().try_into().map(u32::from_be_bytes).map_err(|_| {
+                DbError::Corrupted(format!("invalid version of {}", DI::get_bucket()))
+            }),
+            None => Ok(0),
+        }
+    }
+
+    fn set_version(&self, version: u32) -> Result<(), DbError> {
+        if self.tx.is_owner() {
+            let mut staging = self.staging();
+            staging.versions.insert(DI::get_bucket(), version);
+            staging
+                .writes
+                .push(Box::new(move |tx| put_version::<DI>(tx, version)));
+            return Ok(());
         }
+
+        let db = self.open()?;
+        let tx = db.tx(true)?;
+        put_version::<DI>(&tx, version)?;
+        tx.commit()?;
         Ok(())
     }
+
+    fn begin(&self) -> Result<(), DbError> {
+        self.tx.begin();
+        Ok(())
+    }
+
+    fn commit(&self) -> Result<(), DbError> {
+        match self.end(true)? {
+            Some(false) => Err(DbError::Transaction(
+                "rolled back by an inner transaction".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn rollback(&self) -> Result<(), DbError> {
+        self.end(false).map(|_| ())
+    }
 }
 
-// TODO
 impl From<jammdb::Error> for DbError {
-    fn from(_err: jammdb::Error) -> DbError {
-        DbError::AccessFailed("jammdb error".to_string())
+    fn from(err: jammdb::Error) -> DbError {
+        match err {
+            jammdb::Error::Io(err) => DbError::Io(err),
+            jammdb::Error::InvalidDB(reason) => DbError::Corrupted(reason),
+            others => DbError::AccessFailed(format!("jammdb {}", others)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs};
+
+    fn new_path() -> PathBuf {
+        env::temp_dir().join(format!("elebox_test_{}.db", Uuid::new_v4()))
+    }
+
+    fn new_mfr(name: &str) -> DbManufacturer {
+        DbManufacturer {
+            name: name.to_string(),
+            alias: "".to_string(),
+            url: "".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_index_follows_rename_and_delete() {
+        // Arrange
+        let path = new_path();
+        let db = JammDatabase::new(&path.to_string_lossy());
+        Database::<DbManufacturer>::init(&db).expect("Expected OK");
+        db.add(&new_mfr("TestNameOri")).expect("Expected OK");
+        let id = Database::<DbManufacturer>::get_id(&db, "TestNameOri").expect("Expected OK");
+
+        // Act
+        db.update(&id, &new_mfr("TestNameNew"))
+            .expect("Expected OK");
+        let ori_result = Database::<DbManufacturer>::get_id(&db, "TestNameOri");
+        let new_result = Database::<DbManufacturer>::get_id(&db, "TestNameNew");
+        Database::<DbManufacturer>::delete(&db, &id).expect("Expected OK");
+        let deleted_result = Database::<DbManufacturer>::get_id(&db, "TestNameNew");
+        let _ = fs::remove_file(&path);
+
+        // Assert
+        assert!(matches!(ori_result, Err(DbError::NotExists(_))));
+        assert_eq!(new_result.expect("Expected OK"), id);
+        assert!(matches!(deleted_result, Err(DbError::NotExists(_))));
+    }
+
+    #[test]
+    fn test_init_builds_missing_index() {
+        // Arrange, a database without index as created by older versions
+        let path = new_path();
+        {
+            let db = DB::open(&path).unwrap();
+            let tx = db.tx(true).unwrap();
+            let bkt = tx.create_bucket(MFR_BUCKET).unwrap();
+            let value = rmp_serde::to_vec(&new_mfr("TestName")).unwrap();
+            bkt.put("TestID", value).unwrap();
+            tx.commit().unwrap();
+        }
+        let db = JammDatabase::new(&path.to_string_lossy());
+
+        // Act
+        let scan_result = Database::<DbManufacturer>::get_id(&db, "TestName");
+        Database::<DbManufacturer>::init(&db).expect("Expected OK");
+        let index_result = Database::<DbManufacturer>::get_id(&db, "TestName");
+        db.rebuild_name_index::<DbManufacturer>()
+            .expect("Expected OK");
+        let rebuild_result = Database::<DbManufacturer>::get_id(&db, "TestName");
+        let _ = fs::remove_file(&path);
+
+        // Assert
+        assert_eq!(scan_result.expect("Expected OK"), "TestID");
+        assert_eq!(index_result.expect("Expected OK"), "TestID");
+        assert_eq!(rebuild_result.expect("Expected OK"), "TestID");
+    }
+
+    #[test]
+    fn test_ledger_not_indexed() {
+        // Arrange, the index of an older version mapped parts to entries
+        let path = new_path();
+        {
+            let db = DB::open(&path).unwrap();
+            let tx = db.tx(true).unwrap();
+            tx.create_bucket(LEDGER_BUCKET).unwrap();
+            let index = tx.create_bucket(index_bucket::<DbLedgerEntry>()).unwrap();
+            index.put("TestPartID", "TestID").unwrap();
+            tx.commit().unwrap();
+        }
+        let db = JammDatabase::new(&path.to_string_lossy());
+        let entry = DbLedgerEntry {
+            part_id: "TestPartID".to_string(),
+            timestamp: 0,
+            delta: 1,
+            reason: "".to_string(),
+            note: "".to_string(),
+            project: "".to_string(),
+        };
+
+        // Act
+        Database::<DbLedgerEntry>::init(&db).expect("Expected OK");
+        db.update("TestID1", &entry).expect("Expected OK");
+        db.update("TestID2", &entry).expect("Expected OK");
+        let result = Database::<DbLedgerEntry>::get_id(&db, "TestPartID");
+        let has_index = db
+            .open()
+            .and_then(|jamm| {
+                Ok(jamm
+                    .tx(false)?
+                    .get_bucket(index_bucket::<DbLedgerEntry>())
+                    .is_ok())
+            })
+            .expect("Expected OK");
+        let items: Vec<DbLedgerEntry> = db.list().expect("Expected OK");
+        drop(db);
+        let _ = fs::remove_file(&path);
+
+        // Assert
+        assert!(matches!(result, Err(DbError::NotExists(_))));
+        assert!(!has_index);
+        assert_eq!(items.len(), 2);
+    }
+
+    #[test]
+    fn test_clones_share_handle_across_threads() {
+        // Arrange
+        let path = new_path();
+        let db = JammDatabase::new(&path.to_string_lossy());
+        Database::<DbManufacturer>::init(&db).expect("Expected OK");
+
+        // Act
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let db = db.clone();
+                std::thread::spawn(move || db.add(&new_mfr(&format!("TestName{i}"))))
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap().expect("Expected OK");
+        }
+        let items: Vec<DbManufacturer> = db.list().expect("Expected OK");
+        drop(db);
+        let _ = fs::remove_file(&path);
+
+        // Assert
+        assert_eq!(items.len(), 4);
+    }
+
+    #[test]
+    fn test_open_damaged_file() {
+        // Arrange
+        let path = new_path();
+        fs::write(&path, vec![0xAB; 8192]).expect("Expected OK");
+        let db = JammDatabase::new(&path.to_string_lossy());
+
+        // Act
+        let result = Database::<DbManufacturer>::check(&db);
+        let _ = fs::remove_file(&path);
+
+        // Assert
+        assert!(matches!(result, Err(DbError::Corrupted(_))));
+    }
+
+    #[test]
+    fn test_open_locked_file() {
+        // Arrange
+        let path = new_path();
+        let db = JammDatabase::new(&path.to_string_lossy());
+        Database::<DbManufacturer>::init(&db).expect("Expected OK");
+        let other = JammDatabase::new(&path.to_string_lossy());
+
+        // Act
+        let result = Database::<DbManufacturer>::check(&other);
+        drop(db);
+        let _ = fs::remove_file(&path);
+
+        // Assert
+        assert!(matches!(result, Err(DbError::Locked(_))));
+    }
+
+    #[test]
+    fn test_transaction_staged_until_commit() {
+        // Arrange
+        let path = new_path();
+        let db = JammDatabase::new(&path.to_string_lossy());
+        Database::<DbManufacturer>::init(&db).expect("Expected OK");
+        db.add(&new_mfr("TestNameOri")).expect("Expected OK");
+        let id = Database::<DbManufacturer>::get_id(&db, "TestNameOri").expect("Expected OK");
+
+        // Act, another thread neither sees nor joins the transaction
+        Database::<DbManufacturer>::begin(&db).expect("Expected OK");
+        db.update(&id, &new_mfr("TestNameNew"))
+            .expect("Expected OK");
+        let staged_result = Database::<DbManufacturer>::get_id(&db.clone(), "TestNameNew");
+        let other = db.clone();
+        let (other_ori, other_new) = std::thread::spawn(move || {
+            other.add(&new_mfr("TestNameAdd")).expect("Expected OK");
+            (
+                Database::<DbManufacturer>::get_id(&other, "TestNameOri"),
+                Database::<DbManufacturer>::get_id(&other, "TestNameNew"),
+            )
+        })
+        .join()
+        .unwrap();
+        Database::<DbManufacturer>::rollback(&db).expect("Expected OK");
+        let ori_result = Database::<DbManufacturer>::get_id(&db, "TestNameOri");
+        let add_result = Database::<DbManufacturer>::get_id(&db, "TestNameAdd");
+        let items: Vec<DbManufacturer> = db.list().expect("Expected OK");
+        drop(db);
+        let _ = fs::remove_file(&path);
+
+        // Assert
+        assert_eq!(staged_result.expect("Expected OK"), id);
+        assert_eq!(other_ori.expect("Expected OK"), id);
+        assert!(matches!(other_new, Err(DbError::NotExists(_))));
+        assert_eq!(ori_result.expect("Expected OK"), id);
+        assert!(add_result.is_ok());
+        assert_eq!(items.len(), 2);
+    }
+
+    #[test]
+    fn test_transaction_commit() {
+        // Arrange
+        let path = new_path();
+        let db = JammDatabase::new(&path.to_string_lossy());
+        Database::<DbManufacturer>::init(&db).expect("Expected OK");
+        db.add(&new_mfr("TestNameOri")).expect("Expected OK");
+        let id = Database::<DbManufacturer>::get_id(&db, "TestNameOri").expect("Expected OK");
+
+        // Act
+        Database::<DbManufacturer>::begin(&db).expect("Expected OK");
+        db.update(&id, &new_mfr("TestNameNew"))
+            .expect("Expected OK");
+        Database::<DbManufacturer>::commit(&db).expect("Expected OK");
+        let ori_result = Database::<DbManufacturer>::get_id(&db, "TestNameOri");
+        let new_result = Database::<DbManufacturer>::get_id(&db, "TestNameNew");
+        drop(db);
+        let _ = fs::remove_file(&path);
+
+        // Assert
+        assert!(matches!(ori_result, Err(DbError::NotExists(_))));
+        assert_eq!(new_result.expect("Expected OK"), id);
+    }
+
+    #[test]
+    fn test_version_in_transaction() {
+        // Arrange
+        let path = new_path();
+        let db = JammDatabase::new(&path.to_string_lossy());
+        Database::<DbManufacturer>::init(&db).expect("Expected OK");
+
+        // Act
+        Database::<DbManufacturer>::begin(&db).expect("Expected OK");
+        Database::<DbManufacturer>::set_version(&db, 2).expect("Expected OK");
+        let staged_version = Database::<DbManufacturer>::get_version(&db);
+        Database::<DbManufacturer>::rollback(&db).expect("Expected OK");
+        let rollback_version = Database::<DbManufacturer>::get_version(&db);
+        Database::<DbManufacturer>::begin(&db).expect("Expected OK");
+        Database::<DbManufacturer>::set_version(&db, 3).expect("Expected OK");
+        Database::<DbManufacturer>::commit(&db).expect("Expected OK");
+        let commit_version = Database::<DbManufacturer>::get_version(&db);
+        drop(db);
+        let _ = fs::remove_file(&path);
+
+        // Assert
+        assert_eq!(staged_version.expect("Expected OK"), 2);
+        assert_eq!(rollback_version.expect("Expected OK"), 0);
+        assert_eq!(commit_version.expect("Expected OK"), 3);
+    }
+
+    #[test]
+    fn test_transaction_rollback() {
+        // Arrange
+        let path = new_path();
+        let db = JammDatabase::new(&path.to_string_lossy());
+        Database::<DbManufacturer>::init(&db).expect("Expected OK");
+        db.add(&new_mfr("TestName")).expect("Expected OK");
+        let id = Database::<DbManufacturer>::get_id(&db, "TestName").expect("Expected OK");
+
+        // Act
+        Database::<DbManufacturer>::begin(&db).expect("Expected OK");
+        Database::<DbManufacturer>::delete(&db, &id).expect("Expected OK");
+        let staged_result = Database::<DbManufacturer>::get(&db, &id);
+        Database::<DbManufacturer>::rollback(&db).expect("Expected OK");
+        let result = Database::<DbManufacturer>::get(&db, &id);
+        drop(db);
+        let _ = fs::remove_file(&path);
+
+        // Assert
+        assert!(matches!(staged_result, Err(DbError::NotExists(_))));
+        assert_eq!(result.expect("Expected OK").name, "TestName");
     }
 }
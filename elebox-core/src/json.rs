@@ -13,20 +13,16 @@ impl HumanReadable for JsonFile {
     where
         T: Serialize,
     {
-        // TODO unwrap
-        let contents = serde_json::to_string(&items).unwrap();
-        fs::write(filename, contents).unwrap();
-        Ok(())
+        let contents = serde_json::to_string(&items).map_err(|_| ())?;
+        fs::write(filename, contents).map_err(|_| ())
     }
 
     fn read<T>(filename: &PathBuf) -> Result<Vec<T>, ()>
     where
         T: for<'de> Deserialize<'de>,
     {
-        // TODO unwrap
-        let content = fs::read_to_string(filename).unwrap();
-        let items: Vec<T> = serde_json::from_str(&content).unwrap();
-        Ok(items)
+        let content = fs::read_to_string(filename).map_err(|_| ())?;
+        serde_json::from_str(&content).map_err(|_| ())
     }
 
     fn check_extension(filename: &PathBuf) -> bool {
@@ -0,0 +1,363 @@
+use crate::{errors::EleboxError, BomItem, Part, Project};
+use csv::ReaderBuilder;
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+
+const REF_COLUMNS: [&str; 5] = ["reference", "references", "ref", "refs", "designator"];
+const VALUE_COLUMNS: [&str; 2] = ["value", "val"];
+const FOOTPRINT_COLUMNS: [&str; 1] = ["footprint"];
+const QTY_COLUMNS: [&str; 3] = ["qty", "qnty", "quantity"];
+const DNP_COLUMNS: [&str; 1] = ["dnp"];
+
+/// Names of the symbol field with the manufacturer part number, compared
+/// case-insensitively.
+const MFR_NO_FIELDS: [&str; 6] = [
+    "mpn",
+    "mfr_no",
+    "mfr no",
+    "mfr part number",
+    "manufacturer part number",
+    "manufacturer_part_number",
+];
+
+/// One line of a KiCad BOM, the symbols with the same value, footprint and
+/// manufacturer part number.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct KicadLine {
+    pub references: Vec<String>,
+    pub value: String,
+    pub footprint: String,
+    pub mfr_no: Option<String>,
+    pub quantity: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub enum BomMatch {
+    /// Name of the only matching part.
+    Matched(String),
+    /// Names of all matching parts.
+    Ambiguous(Vec<String>),
+    Missing,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct BomImportLine {
+    pub line: KicadLine,
+    pub status: BomMatch,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct BomImportReport {
+    pub lines: Vec<BomImportLine>,
+}
+
+impl BomImportReport {
+    pub fn matched(&self) -> impl Iterator<Item = &BomImportLine> {
+        self.lines
+            .iter()
+            .filter(|line| matches!(line.status, BomMatch::Matched(_)))
+    }
+
+    pub fn ambiguous(&self) -> impl Iterator<Item = &BomImportLine> {
+        self.lines
+            .iter()
+            .filter(|line| matches!(line.status, BomMatch::Ambiguous(_)))
+    }
+
+    pub fn missing(&self) -> impl Iterator<Item = &BomImportLine> {
+        self.lines
+            .iter()
+            .filter(|line| line.status == BomMatch::Missing)
+    }
+
+    /// A project with the matched lines as BOM, lines of the same part are
+    /// added up. Ambiguous and missing lines are left out.
+    pub fn to_project(&self, name: &str, description: Option<&str>) -> Project {
+        let mut bom: Vec<BomItem> = vec![];
+        for line in &self.lines {
+            let BomMatch::Matched(part) = &line.status else {
+                continue;
+            };
+            match bom.iter_mut().find(|item| &item.part == part) {
+                Some(item) => item.quantity = item.quantity.saturating_add(line.line.quantity),
+                None => bom.push(BomItem::new(part, line.line.quantity)),
+            }
+        }
+        Project::new(name, description, bom)
+    }
+}
+
+/// Read a KiCad BOM, `.xml` files are read as the XML netlist export and
+/// others as BOM CSV. Symbols marked DNP are left out.
+pub fn read_kicad(filename: &PathBuf) -> Result<Vec<KicadLine>, EleboxError> {
+    let invalid =
+        |reason: String| EleboxError::InvalidFile(filename.to_string_lossy().into_owned(), reason);
+
+    let content = fs::read_to_string(filename).map_err(|err| invalid(err.to_string()))?;
+    if filename.extension().is_some_and(|ext| ext == "xml") {
+        parse_netlist(&content).map_err(invalid)
+    } else {
+        parse_csv(&content).map_err(invalid)
+    }
+}
+
+/// Match each line to a part, by manufacturer part number first, then by
+/// the value against the name and alias.
+pub fn match_lines(lines: Vec<KicadLine>, parts: &[Part]) -> BomImportReport {
+    let lines = lines
+        .into_iter()
+        .map(|line| {
+            let status = match_line(&line, parts);
+            BomImportLine { line, status }
+        })
+        .collect();
+    BomImportReport { lines }
+}
+
+fn match_line(line: &KicadLine, parts: &[Part]) -> BomMatch {
+    let find = |field: fn(&Part) -> Option<&str>, key: &str| -> Vec<String> {
+        parts
+            .iter()
+            .filter(|part| field(part).is_some_and(|s| s.eq_ignore_ascii_case(key)))
+            .map(|part| part.name.clone())
+            .collect()
+    };
+
+    let mut candidates = vec![];
+    if let Some(mfr_no) = line.mfr_no.as_deref() {
+        candidates = find(|part| part.mfr_no.as_deref(), mfr_no);
+    }
+    if candidates.is_empty() {
+        candidates = find(|part| Some(&part.name), &line.value);
+    }
+    if candidates.is_empty() {
+        candidates = find(|part| part.alias.as_deref(), &line.value);
+    }
+
+    match candidates.len() {
+        0 => BomMatch::Missing,
+        1 => BomMatch::Matched(candidates.remove(0)),
+        _ => BomMatch::Ambiguous(candidates),
+    }
+}
+
+fn column(header: &[String], names: &[&str]) -> Option<usize> {
+    header.iter().position(|col| names.contains(&col.as_str()))
+}
+
+/// Parse the CSV of the KiCad BOM export, the header is the first row with
+/// a reference column, rows before it are the title of older exporters.
+fn parse_csv(content: &str) -> Result<Vec<KicadLine>, String> {
+    let mut reader = ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(content.as_bytes());
+
+    let mut header: Option<Vec<String>> = None;
+    let mut lines = vec![];
+    for record in reader.records() {
+        let record = record.map_err(|err| err.to_string())?;
+        let Some(header) = &header else {
+            let names: Vec<String> = record.iter().map(|s| s.trim().to_lowercase()).collect();
+            if column(&names, &REF_COLUMNS).is_some() {
+                header = Some(names);
+            }
+            continue;
+        };
+
+        let get = |names: &[&str]| {
+            column(header, names)
+                .and_then(|idx| record.get(idx))
+                .map(|s| s.trim())
+                .unwrap_or("")
+        };
+
+        if !get(&DNP_COLUMNS).is_empty() {
+            continue;
+        }
+
+        let references: Vec<String> = get(&REF_COLUMNS)
+            .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect();
+        if references.is_empty() {
+            continue;
+        }
+
+        let quantity = get(&QTY_COLUMNS).parse().unwrap_or(references.len() as u16);
+        let mfr_no = Some(get(&MFR_NO_FIELDS)).filter(|s| !s.is_empty());
+
+        lines.push(KicadLine {
+            value: get(&VALUE_COLUMNS).to_string(),
+            footprint: get(&FOOTPRINT_COLUMNS).to_string(),
+            mfr_no: mfr_no.map(String::from),
+            references,
+            quantity,
+        });
+    }
+
+    match header {
+        Some(_) => Ok(lines),
+        None => Err("no reference column".to_string()),
+    }
+}
+
+/// Parse the XML netlist, symbols are grouped into lines like the BOM export.
+fn parse_netlist(content: &str) -> Result<Vec<KicadLine>, String> {
+    let doc = roxmltree::Document::parse(content).map_err(|err| err.to_string())?;
+    let components = doc
+        .descendants()
+        .find(|node| node.has_tag_name("components"))
+        .ok_or("no components")?;
+
+    let mut lines: Vec<KicadLine> = vec![];
+    for comp in components
+        .children()
+        .filter(|node| node.has_tag_name("comp"))
+    {
+        let reference = comp.attribute("ref").unwrap_or("").to_string();
+        let child_text = |name: &str| {
+            comp.children()
+                .find(|node| node.has_tag_name(name))
+                .and_then(|node| node.text())
+                .unwrap_or("")
+                .trim()
+                .to_string()
+        };
+
+        // KiCad 7 and later mark DNP symbols with a property
+        let dnp = comp
+            .children()
+            .any(|node| node.has_tag_name("property") && node.attribute("name") == Some("dnp"));
+        if dnp || reference.is_empty() {
+            continue;
+        }
+
+        let mfr_no = comp
+            .descendants()
+            .filter(|node| node.has_tag_name("field"))
+            .find(|node| {
+                let name = node.attribute("name").unwrap_or("").to_lowercase();
+                MFR_NO_FIELDS.contains(&name.as_str())
+            })
+            .and_then(|node| node.text())
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+
+        let value = child_text("value");
+        let footprint = child_text("footprint");
+        match lines.iter_mut().find(|line| {
+            line.value == value && line.footprint == footprint && line.mfr_no == mfr_no
+        }) {
+            Some(line) => {
+                line.references.push(reference);
+                line.quantity += 1;
+            }
+            None => lines.push(KicadLine {
+                references: vec![reference],
+                value,
+                footprint,
+                mfr_no,
+                quantity: 1,
+            }),
+        }
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts() -> Vec<Part> {
+        let mut stm32 = Part::new("STM32F103", "MCU", 5);
+        stm32.mfr_no = Some("STM32F103C8T6".to_string());
+        let mut cap_a = Part::new("100nF 0402", "Caps", 100);
+        cap_a.alias = Some("100nF".to_string());
+        let mut cap_b = Part::new("100nF 0603", "Caps", 100);
+        cap_b.alias = Some("100nF".to_string());
+        vec![stm32, cap_a, cap_b, Part::new("10k", "Resistors", 50)]
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        // Arrange
+        let content = "\"Source:\",\"board.kicad_sch\"\n\
+            \n\
+            \"Refs\",\"Value\",\"Footprint\",\"Qty\",\"DNP\",\"MPN\"\n\
+            \"U1\",\"STM32\",\"LQFP-48\",\"1\",\"\",\"stm32f103c8t6\"\n\
+            \"R1,R2,R3\",\"10k\",\"R_0402\",\"3\",\"\",\"\"\n\
+            \"R4\",\"1k\",\"R_0402\",\"1\",\"DNP\",\"\"\n";
+
+        // Act
+        let lines = parse_csv(content).expect("Expected OK");
+
+        // Assert
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].mfr_no.as_deref(), Some("stm32f103c8t6"));
+        assert_eq!(lines[1].references, vec!["R1", "R2", "R3"]);
+        assert_eq!(lines[1].quantity, 3);
+    }
+
+    #[test]
+    fn test_parse_netlist() {
+        // Arrange
+        let content = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <export version="E">
+              <components>
+                <comp ref="C1"><value>100nF</value><footprint>C_0402</footprint></comp>
+                <comp ref="C2"><value>100nF</value><footprint>C_0402</footprint></comp>
+                <comp ref="C3"><value>100nF</value><footprint>C_0402</footprint>
+                  <property name="dnp"/></comp>
+                <comp ref="U1"><value>STM32</value><footprint>LQFP-48</footprint>
+                  <fields><field name="MPN">STM32F103C8T6</field></fields></comp>
+              </components>
+            </export>"#;
+
+        // Act
+        let lines = parse_netlist(content).expect("Expected OK");
+
+        // Assert
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].references, vec!["C1", "C2"]);
+        assert_eq!(lines[0].quantity, 2);
+        assert_eq!(lines[1].mfr_no.as_deref(), Some("STM32F103C8T6"));
+    }
+
+    #[test]
+    fn test_match_lines() {
+        // Arrange
+        let line = |value: &str, mfr_no: Option<&str>| KicadLine {
+            references: vec!["X1".to_string()],
+            value: value.to_string(),
+            footprint: "".to_string(),
+            mfr_no: mfr_no.map(String::from),
+            quantity: 2,
+        };
+        let lines = vec![
+            line("STM32", Some("stm32f103c8t6")),
+            line("100nF", None),
+            line("10k", None),
+            line("1k", None),
+            line("10k", None),
+        ];
+
+        // Act
+        let report = match_lines(lines, &parts());
+        let project = report.to_project("Board", None);
+
+        // Assert
+        assert_eq!(
+            report.lines[0].status,
+            BomMatch::Matched("STM32F103".to_string())
+        );
+        assert_eq!(report.ambiguous().count(), 1);
+        assert_eq!(report.missing().count(), 1);
+        assert_eq!(report.matched().count(), 3);
+        assert_eq!(
+            project.bom,
+            vec![BomItem::new("STM32F103", 2), BomItem::new("10k", 4)]
+        );
+    }
+}
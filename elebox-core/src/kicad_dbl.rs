@@ -0,0 +1,298 @@
+use crate::{errors::*, Part};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Not `parts`, so it cannot be mistaken for the parts of a SQLite inventory.
+const DBL_TABLE: &str = "kicad_parts";
+
+/// Custom fields of a part that override the symbol and footprint of its package.
+const SYMBOL_FIELD: &str = "Symbol";
+const FOOTPRINT_FIELD: &str = "Footprint";
+
+/// KiCad symbol and footprint of a package, e.g. `Device:C` and
+/// `Capacitor_SMD:C_0402_1005Metric`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct KicadSymbol {
+    pub symbol: String,
+    pub footprint: String,
+}
+
+/// KiCad symbol and footprint by package name.
+pub type KicadMapping = BTreeMap<String, KicadSymbol>;
+
+/// Read a package mapping from a YAML file, e.g.
+///
+/// ```yaml
+/// SMD 0402:
+///   symbol: Device:C
+///   footprint: Capacitor_SMD:C_0402_1005Metric
+/// ```
+pub fn read_kicad_mapping(filename: &PathBuf) -> Result<KicadMapping, EleboxError> {
+    let invalid =
+        |reason: String| EleboxError::InvalidFile(filename.to_string_lossy().into_owned(), reason);
+
+    let content = fs::read_to_string(filename).map_err(|err| invalid(err.to_string()))?;
+    serde_yaml::from_str(&content).map_err(|err| invalid(err.to_string()))
+}
+
+/// Columns of the table and the KiCad fields they are shown as.
+const FIELDS: [(&str, &str); 7] = [
+    ("value", "Value"),
+    ("datasheet", "Datasheet"),
+    ("manufacturer", "Manufacturer"),
+    ("mpn", "MPN"),
+    ("package", "Package"),
+    ("location", "Location"),
+    ("stock", "Stock"),
+];
+
+/// SQLite database of the `.kicad_dbl` file, e.g. `elebox_kicad.sqlite` for
+/// `elebox.kicad_dbl`. Not `elebox.sqlite`, which may be the inventory.
+fn table_filename(filename: &Path) -> PathBuf {
+    let stem = filename
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    filename.with_file_name(format!("{stem}_kicad.sqlite"))
+}
+
+/// Tables in the SQLite database other than the KiCad one.
+fn other_tables(filename: &Path) -> Result<Vec<String>, DbError> {
+    let conn = Connection::open(filename)?;
+    let mut stmt =
+        conn.prepare("SELECT name FROM sqlite_master WHERE type = 'table' AND name != ?1")?;
+    let names = stmt
+        .query_map([DBL_TABLE], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+    Ok(names)
+}
+
+/// Write the `.kicad_dbl` file and the SQLite database next to it, see
+/// `table_filename()`. The table is replaced, so run it again after the
+/// stock changed. A database with other tables, e.g. an inventory, is
+/// refused.
+pub(crate) fn export_dbl(
+    filename: &PathBuf,
+    parts: &[Part],
+    mapping: &KicadMapping,
+) -> Result<(), EleboxError> {
+    let db_filename = table_filename(filename);
+    if db_filename.exists() && !other_tables(&db_filename)?.is_empty() {
+        return Err(EleboxError::InvalidFile(
+            db_filename.to_string_lossy().into_owned(),
+            "it holds other tables than the KiCad parts".to_string(),
+        ));
+    }
+    write_table(&db_filename, parts, mapping)?;
+
+    let db_name = db_filename
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let fields: Vec<_> = FIELDS
+        .iter()
+        .map(|(column, name)| {
+            json!({
+                "column": column,
+                "name": name,
+                "visible_on_add": false,
+                "visible_in_chooser": *column == "stock",
+                "show_name": false,
+            })
+        })
+        .collect();
+
+    let dbl = json!({
+        "meta": { "version": 0 },
+        "name": "elebox",
+        "description": "Parts in the elebox inventory",
+        "source": {
+            "type": "odbc",
+            "dsn": "",
+            "username": "",
+            "password": "",
+            "timeout_seconds": 2,
+            "connection_string": format!("Driver={{SQLite3}};Database=${{CWD}}/{db_name}"),
+        },
+        "libraries": [{
+            "name": "Parts",
+            "table": DBL_TABLE,
+            "key": "name",
+            "symbols": "symbol",
+            "footprints": "footprint",
+            "fields": fields,
+            "properties": {
+                "description": "description",
+                "keywords": "keywords",
+            },
+        }],
+    });
+
+    let content = serde_json::to_string_pretty(&dbl).unwrap_or_default();
+    fs::write(filename, content).map_err(DbError::from)?;
+    Ok(())
+}
+
+fn write_table(filename: &PathBuf, parts: &[Part], mapping: &KicadMapping) -> Result<(), DbError> {
+    let mut conn = Connection::open(filename)?;
+    let tx = conn.transaction()?;
+    tx.execute_batch(&format!(
+        "DROP TABLE IF EXISTS {DBL_TABLE};
+        CREATE TABLE {DBL_TABLE} (
+            name TEXT PRIMARY KEY,
+            symbol TEXT NOT NULL,
+            footprint TEXT NOT NULL,
+            value TEXT NOT NULL,
+            datasheet TEXT NOT NULL,
+            description TEXT NOT NULL,
+            keywords TEXT NOT NULL,
+            category TEXT NOT NULL,
+            manufacturer TEXT NOT NULL,
+            mpn TEXT NOT NULL,
+            package TEXT NOT NULL,
+            location TEXT NOT NULL,
+            stock INTEGER NOT NULL
+        );"
+    ))?;
+
+    {
+        let mut stmt = tx.prepare(&format!(
+            "INSERT INTO {DBL_TABLE} VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)"
+        ))?;
+        for part in parts {
+            let custom = |name: &str| {
+                part.custom_fields
+                    .iter()
+                    .find(|field| field.name.eq_ignore_ascii_case(name))
+                    .map(|field| field.value.clone())
+            };
+            let package = part.package.as_deref().and_then(|pkg| mapping.get(pkg));
+            let symbol = custom(SYMBOL_FIELD)
+                .or_else(|| package.map(|pkg| pkg.symbol.clone()))
+                .unwrap_or_default();
+            let footprint = custom(FOOTPRINT_FIELD)
+                .or_else(|| package.map(|pkg| pkg.footprint.clone()))
+                .unwrap_or_default();
+            let text = |opt: &Option<String>| opt.clone().unwrap_or_default();
+
+            stmt.execute(params![
+                part.name,
+                symbol,
+                footprint,
+                part.name,
+                text(&part.datasheet_link),
+                text(&part.description),
+                text(&part.alias),
+                part.category,
+                text(&part.mfr),
+                text(&part.mfr_no),
+                text(&part.package),
+                text(&part.location),
+                part.quantity,
+            ])?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Category, Handler, Manager};
+    use std::env;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_export_dbl() {
+        // Arrange
+        let dir = env::temp_dir().join(format!("elebox_test_{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let filename = dir.join("elebox.kicad_dbl");
+
+        let mut part = Part::new("100nF", "Capacitors", 50);
+        part.package = Some("SMD 0402".to_string());
+        let mapping = KicadMapping::from([(
+            "SMD 0402".to_string(),
+            KicadSymbol {
+                symbol: "Device:C".to_string(),
+                footprint: "Capacitor_SMD:C_0402_1005Metric".to_string(),
+            },
+        )]);
+
+        // Act
+        export_dbl(&filename, &[part], &mapping).expect("Expected OK");
+        let conn = Connection::open(dir.join("elebox_kicad.sqlite")).unwrap();
+        let row: (String, String, u16) = conn
+            .query_row(
+                "SELECT symbol, footprint, stock FROM kicad_parts WHERE name = '100nF'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        let dbl = fs::read_to_string(&filename).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        // Assert
+        assert_eq!(row.0, "Device:C");
+        assert_eq!(row.1, "Capacitor_SMD:C_0402_1005Metric");
+        assert_eq!(row.2, 50);
+        assert!(dbl.contains("Database=${CWD}/elebox_kicad.sqlite"));
+        assert!(dbl.contains("\"table\": \"kicad_parts\""));
+    }
+
+    #[test]
+    fn test_export_dbl_next_to_inventory() {
+        // Arrange, a SQLite inventory with the same stem as the library
+        let dir = env::temp_dir().join(format!("elebox_test_{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let inventory = dir.join("elebox.sqlite").to_string_lossy().into_owned();
+        let mgr = Manager::open(&inventory).unwrap();
+        mgr.init().unwrap();
+        mgr.category()
+            .add(&Category::new("Capacitors", None, None))
+            .unwrap();
+        mgr.part()
+            .add(&Part::new("100nF", "Capacitors", 50))
+            .unwrap();
+
+        // Act
+        let result = mgr.export_kicad_dbl(&dir.join("elebox.kicad_dbl"), &KicadMapping::new());
+        let parts = mgr.part().list();
+        drop(mgr);
+        let reopened = Manager::open(&inventory).unwrap().part().list();
+        let _ = fs::remove_dir_all(&dir);
+
+        // Assert
+        assert!(result.is_ok());
+        assert_eq!(parts.expect("Expected OK").len(), 1);
+        assert_eq!(reopened.expect("Expected OK").len(), 1);
+    }
+
+    #[test]
+    fn test_export_dbl_refuses_inventory() {
+        // Arrange, an inventory where the table of the library would go
+        let dir = env::temp_dir().join(format!("elebox_test_{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let inventory = dir.join("elebox_kicad.sqlite");
+        let mgr = Manager::open(&inventory.to_string_lossy()).unwrap();
+        mgr.init().unwrap();
+
+        // Act
+        let result = mgr.export_kicad_dbl(&dir.join("elebox.kicad_dbl"), &KicadMapping::new());
+        let tables = other_tables(&inventory).unwrap();
+        drop(mgr);
+        let _ = fs::remove_dir_all(&dir);
+
+        // Assert
+        assert!(matches!(result, Err(EleboxError::InvalidFile(_, _))));
+        assert!(tables.contains(&"parts".to_string()));
+        assert!(!tables.contains(&DBL_TABLE.to_string()));
+    }
+}
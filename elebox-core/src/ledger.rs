@@ -0,0 +1,190 @@
+use crate::{comm::*, errors::*, jamm_db::*, part::DELETED_ITEM};
+use core::fmt;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum StockReason {
+    Restock,
+    Use,
+    Adjustment,
+    Import,
+}
+
+impl StockReason {
+    fn as_str(&self) -> &'static str {
+        match self {
+            StockReason::Restock => "restock",
+            StockReason::Use => "use",
+            StockReason::Adjustment => "adjustment",
+            StockReason::Import => "import",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "restock" => StockReason::Restock,
+            "use" => StockReason::Use,
+            "import" => StockReason::Import,
+            _ => StockReason::Adjustment,
+        }
+    }
+}
+
+impl fmt::Display for StockReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            StockReason::Restock => "Restock",
+            StockReason::Use => "Use",
+            StockReason::Adjustment => "Adjustment",
+            StockReason::Import => "Import",
+        };
+        write!(f, "{s}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct LedgerEntry {
+    pub part: String,
+    /// Unix time in seconds.
+    pub timestamp: i64,
+    pub delta: i32,
+    pub reason: StockReason,
+    pub note: Option<String>,
+    pub project: Option<String>,
+}
+
+/// Append an entry for a stock movement of the part.
+pub(crate) fn record(
+    db: &dyn Database<DbLedgerEntry>,
+    part_id: &str,
+    delta: i32,
+    reason: StockReason,
+    note: Option<&str>,
+    project: Option<&str>,
+) -> Result<(), DbError> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs() as i64);
+
+    let entry = DbLedgerEntry {
+        part_id: part_id.to_string(),
+        timestamp,
+        delta,
+        reason: reason.as_str().to_string(),
+        note: note.unwrap_or("").to_string(),
+        project: project.unwrap_or("").to_string(),
+    };
+
+    // Version 7 IDs are time-ordered, so entries are listed as recorded
+    db.update(&Uuid::now_v7().to_string(), &entry)
+}
+
+/// Bring the balance of each part to zero with a closing adjustment, used
+/// when parts are deleted. The ledger is append-only, so the entries of the
+/// parts are kept.
+pub(crate) fn close(db: &dyn Database<DbLedgerEntry>, part_ids: &[Id]) -> Result<(), DbError> {
+    let entries = db.list()?;
+    for part_id in part_ids {
+        let balance: i64 = entries
+            .iter()
+            .filter(|entry| &entry.part_id == part_id)
+            .map(|entry| entry.delta as i64)
+            .sum();
+        if balance != 0 {
+            record(
+                db,
+                part_id,
+                -balance as i32,
+                StockReason::Adjustment,
+                Some("Part deleted"),
+                None,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Read-only access to the stock ledger, entries are recorded by the
+/// quantity changes of `PartHandler`.
+pub struct LedgerHandler<'a> {
+    pub(crate) db: &'a dyn Database<DbLedgerEntry>,
+    pub(crate) part_db: &'a dyn Database<DbPart>,
+}
+
+impl LedgerHandler<'_> {
+    fn to_item(&self, db_item: DbLedgerEntry) -> Result<LedgerEntry, EleboxError> {
+        let part = match self.part_db.get(&db_item.part_id) {
+            Ok(item) => item.name,
+            Err(DbError::NotExists(_)) => String::from(DELETED_ITEM),
+            Err(err) => return Err(EleboxError::DatabaseError(err)),
+        };
+
+        let non_empty = |s: String| (!s.is_empty()).then_some(s);
+
+        Ok(LedgerEntry {
+            part,
+            timestamp: db_item.timestamp,
+            delta: db_item.delta,
+            reason: StockReason::parse(&db_item.reason),
+            note: non_empty(db_item.note),
+            project: non_empty(db_item.project),
+        })
+    }
+
+    fn list_by(
+        &self,
+        filter: impl Fn(&DbLedgerEntry) -> bool,
+    ) -> Result<Vec<LedgerEntry>, EleboxError> {
+        let db_items = self.db.list()?;
+        db_items
+            .into_iter()
+            .filter(|item| filter(item))
+            .map(|item| self.to_item(item))
+            .collect()
+    }
+
+    /// All entries, oldest first.
+    pub fn list(&self) -> Result<Vec<LedgerEntry>, EleboxError> {
+        self.list_by(|_| true)
+    }
+
+    /// Entries of the part, oldest first.
+    pub fn list_part(&self, name: &str) -> Result<Vec<LedgerEntry>, EleboxError> {
+        let id = self.part_db.get_id(name)?;
+        self.list_by(|item| item.part_id == id)
+    }
+
+    /// Entries recorded from `from` until before `to`, in Unix seconds.
+    pub fn list_range(&self, from: i64, to: i64) -> Result<Vec<LedgerEntry>, EleboxError> {
+        self.list_by(|item| (from..to).contains(&item.timestamp))
+    }
+
+    /// Sum of all stock movements of the part.
+    pub fn balance(&self, name: &str) -> Result<i64, EleboxError> {
+        let id = self.part_db.get_id(name)?;
+        let db_items = self.db.list()?;
+        Ok(db_items
+            .iter()
+            .filter(|item| item.part_id == id)
+            .map(|item| item.delta as i64)
+            .sum())
+    }
+
+    /// Check the current quantity of the part against its ledger.
+    pub fn verify(&self, name: &str) -> Result<(), EleboxError> {
+        let id = self.part_db.get_id(name)?;
+        let quantity = self.part_db.get(&id)?.quantity;
+        let balance = self.balance(name)?;
+
+        if quantity as i64 != balance {
+            return Err(EleboxError::LedgerMismatch(
+                name.to_string(),
+                quantity,
+                balance,
+            ));
+        }
+        Ok(())
+    }
+}
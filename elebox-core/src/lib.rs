@@ -3,20 +3,59 @@ mod comm;
 mod csv;
 mod default_db;
 mod errors;
+mod field;
+mod fsck;
 mod jamm_db;
 mod json;
+mod kicad;
+mod kicad_dbl;
+mod ledger;
+mod location;
 mod manager;
 mod manufacturer;
+mod marking;
+mod memory_db;
+mod migration;
+mod order;
 mod package;
+mod param;
 mod part;
+mod passive;
+mod project;
+mod reorder;
+mod report;
+mod sqlite_db;
+mod stock;
+mod supplier;
 mod yaml;
 
 pub use category::*;
 pub use comm::*;
 pub use default_db::create_default_db;
 pub use errors::*;
+pub use field::{sort_by_field, FieldDef, FieldFilter, FieldHandler, FieldOp};
+pub use fsck::{FsckIssue, FsckReport, Problem};
 pub use jamm_db::*;
+pub use kicad::*;
+pub use kicad_dbl::{read_kicad_mapping, KicadMapping, KicadSymbol};
+pub use ledger::{LedgerEntry, LedgerHandler, StockReason};
+pub use location::{Location, LocationHandler};
 pub use manager::*;
 pub use manufacturer::*;
+pub use marking::Marking;
+pub use memory_db::*;
+pub use migration::*;
+pub use order::{OrderHandler, OrderLine, OrderStatus, PurchaseOrder};
 pub use package::*;
+pub use param::{ParamRange, PartQuery, SiValue};
 pub use part::*;
+pub use passive::{Combination, Connection, PassiveKind, StockedValue, ValueMatches, ValueQuery};
+pub use project::*;
+pub use reorder::{write_bulk_add, BulkAddFormat, ReorderGroup, ReorderItem};
+pub use report::{
+    write_bom_cost, write_valuation, Amount, BomCost, BomCostLine, CategoryValue, StockValue,
+    Valuation,
+};
+pub use sqlite_db::*;
+pub use stock::StockLine;
+pub use supplier::{PartSupplier, Supplier, SupplierHandler};
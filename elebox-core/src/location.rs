@@ -0,0 +1,530 @@
+use crate::{
+    category::build_tree, comm::*, errors::*, jamm_db::*, Manager, Part, PartHandler, TreeNode,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, fmt::Debug, path::PathBuf};
+
+pub(crate) const ROOT_LOCATION: &str = "__ROOT__";
+
+/// A place where parts are stored, e.g. a room, cabinet, drawer or bin
+/// under its parent.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Location {
+    pub name: String,
+    pub parent: Option<String>,
+    pub alias: Option<String>,
+}
+
+impl Location {
+    pub fn new(name: &str, parent: Option<&str>, alias: Option<&str>) -> Self {
+        Self {
+            name: name.to_string(),
+            parent: parent.map(|p| p.to_string()),
+            alias: alias.map(|a| a.to_string()),
+        }
+    }
+}
+
+pub struct LocationHandler<'a> {
+    pub(crate) db: &'a dyn Database<DbLocation>,
+    pub(crate) part: PartHandler<'a>,
+}
+
+/// ID of the location, names are matched ignoring case so "Drawer A3" and
+/// "drawer a3" are the same place.
+pub(crate) fn find_location_id(db: &dyn Database<DbLocation>, name: &str) -> Result<Id, DbError> {
+    if let Ok(id) = db.get_id(name) {
+        return Ok(id);
+    }
+
+    let name = name.trim();
+    db.list_with_id()?
+        .into_iter()
+        .find(|(_, loc)| loc.name.eq_ignore_ascii_case(name))
+        .map(|(id, _)| id)
+        .ok_or_else(|| DbError::NotExists(DbLocation::get_bucket()))
+}
+
+/// ID of the location, a top-level one is added if it does not exist yet.
+pub(crate) fn find_or_add_location(
+    db: &dyn Database<DbLocation>,
+    name: &str,
+) -> Result<Id, DbError> {
+    match find_location_id(db, name) {
+        Err(DbError::NotExists(_)) => {
+            db.add(&DbLocation {
+                name: name.trim().to_string(),
+                parent_id: ROOT_LOCATION.to_string(),
+                alias: "".to_string(),
+            })?;
+            db.get_id(name.trim())
+        }
+        other => other,
+    }
+}
+
+/// Version 2 of the parts, older releases stored a free-form location
+/// instead of the ID of a location record. Turn the text into records.
+pub(crate) fn migrate_locations(mgr: &Manager) -> Result<(), EleboxError> {
+    let db = &*mgr.location_db;
+    // IDs of records are kept, anything else is the old text
+    let ids: BTreeSet<Id> = db.list_with_id()?.into_iter().map(|(id, _)| id).collect();
+
+    for (part_id, part) in mgr.part_db.list_with_id()? {
+        if part.location_id.is_empty() || ids.contains(&part.location_id) {
+            continue;
+        }
+
+        let location_id = match part.location_id.trim() {
+            "" => "".to_string(),
+            name => find_or_add_location(db, name)?,
+        };
+        mgr.part_db.update(
+            &part_id,
+            &DbPart {
+                location_id,
+                ..part
+            },
+        )?;
+    }
+    Ok(())
+}
+
+impl LocationHandler<'_> {
+    fn to_item(&self, db_location: DbLocation) -> Location {
+        let db_parent = match self.db.get(&db_location.parent_id) {
+            Ok(db_item) => Some(db_item.name),
+            Err(_) => None,
+        };
+
+        Location {
+            name: db_location.name,
+            parent: db_parent,
+            alias: match db_location.alias.as_str() {
+                "" => None,
+                other => Some(other.to_string()),
+            },
+        }
+    }
+
+    fn to_db_location(&self, item: &Location) -> Result<DbLocation, EleboxError> {
+        let parent_id = match item.parent.as_deref() {
+            Some("") | None => ROOT_LOCATION.to_string(),
+            Some(parent_name) => find_location_id(self.db, parent_name)?,
+        };
+
+        Ok(DbLocation {
+            name: item.name.to_string(),
+            parent_id,
+            alias: item.alias.clone().unwrap_or_default(),
+        })
+    }
+
+    /// Returns `true` if another location has the name, ignoring case.
+    fn name_taken(&self, name: &str, except: Option<&str>) -> Result<bool, EleboxError> {
+        match find_location_id(self.db, name) {
+            Ok(id) => Ok(except != Some(id.as_str())),
+            Err(DbError::NotExists(_)) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn add_recursion(&self, location: &Location, locs: &[Location]) -> Result<(), EleboxError> {
+        if let Some(parent_name) = &location.parent {
+            if let Some(parent_loc) = locs.iter().find(|l| l.name == *parent_name) {
+                // The parent may have been added already by an earlier item
+                match self.add_recursion(parent_loc, locs) {
+                    Ok(()) | Err(EleboxError::AlreadyExists(_, _)) => {}
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+
+        self.add(location)
+    }
+
+    /// IDs of the location and all the locations under it.
+    fn subtree_ids(&self, id: &str) -> Result<Vec<Id>, EleboxError> {
+        let locs = self.db.list_with_id()?;
+        let mut ids = vec![id.to_string()];
+        let mut i = 0;
+        while i < ids.len() {
+            for (child_id, loc) in &locs {
+                if loc.parent_id == ids[i] && !ids.contains(child_id) {
+                    ids.push(child_id.clone());
+                }
+            }
+            i += 1;
+        }
+        Ok(ids)
+    }
+
+    /// Parts stored in the location or any location under it, e.g. all
+    /// parts in a cabinet.
+    pub fn parts_in(&self, name: &str) -> Result<Vec<Part>, EleboxError> {
+        let id = find_location_id(self.db, name)?;
+        let ids = self.subtree_ids(&id)?;
+
+        let mut parts = vec![];
+        for db_part in self.part.db.list()? {
+            if ids.iter().any(|id| db_part.uses_location(id)) {
+                parts.push(self.part.to_item(db_part)?);
+            }
+        }
+        Ok(parts)
+    }
+
+    /// Delete the location, the parts and child locations that still use it
+    /// are handled by `policy`. `Cascade` deletes the child locations, the
+    /// parts are kept without a location.
+    pub fn delete_with(&self, name: &str, policy: &DeletePolicy) -> Result<(), EleboxError> {
+        let id = find_location_id(self.db, name)?;
+        let part_db = self.part.db;
+        let parts: Vec<(Id, DbPart)> = part_db
+            .list_with_id()?
+            .into_iter()
+            .filter(|(_, part)| part.uses_location(&id))
+            .collect();
+        let children: Vec<(Id, DbLocation)> = self
+            .db
+            .list_with_id()?
+            .into_iter()
+            .filter(|(_, loc)| loc.parent_id == id)
+            .collect();
+
+        if parts.is_empty() && children.is_empty() {
+            self.db.delete(&id)?;
+            return Ok(());
+        }
+
+        match policy {
+            DeletePolicy::Restrict => {
+                let part_names = parts.into_iter().map(|(_, part)| part.name);
+                let loc_names = children.into_iter().map(|(_, loc)| loc.name);
+                Err(EleboxError::StillReferenced(
+                    String::from(ITEM_LOCATION),
+                    name.to_string(),
+                    part_names.chain(loc_names).collect(),
+                ))
+            }
+            DeletePolicy::Reassign(new_name) => {
+                let new_id = find_location_id(self.db, new_name)?;
+                if self.subtree_ids(&id)?.contains(&new_id) {
+                    return Err(EleboxError::CircularLocation(new_name.to_string()));
+                }
+
+                in_transaction(self.db, part_db, || {
+                    for (part_id, mut part) in parts {
+                        part.relocate(&id, &new_id)?;
+                        part_db.update(&part_id, &part)?;
+                    }
+                    for (loc_id, mut loc) in children {
+                        loc.parent_id = new_id.clone();
+                        self.db.update(&loc_id, &loc)?;
+                    }
+                    self.db.delete(&id)?;
+                    Ok(())
+                })
+            }
+            DeletePolicy::Cascade => {
+                let ids = self.subtree_ids(&id)?;
+                in_transaction(self.db, part_db, || {
+                    for (part_id, mut part) in part_db.list_with_id()? {
+                        if ids.iter().any(|id| part.uses_location(id)) {
+                            for loc_id in &ids {
+                                part.relocate(loc_id, "")?;
+                            }
+                            part_db.update(&part_id, &part)?;
+                        }
+                    }
+                    for loc_id in &ids {
+                        self.db.delete(loc_id)?;
+                    }
+                    Ok(())
+                })
+            }
+        }
+    }
+
+    pub fn get_tree(&self) -> Result<Vec<TreeNode>, EleboxError> {
+        let locs = self.list()?;
+        Ok(build_tree(locs.into_iter().map(|l| (l.name, l.parent))))
+    }
+}
+
+impl Handler<Location> for LocationHandler<'_> {
+    /// Delete the location, refused if any part or child location still uses it.
+    fn delete(&self, name: &str) -> Result<(), EleboxError> {
+        self.delete_with(name, &DeletePolicy::Restrict)
+    }
+
+    fn add(&self, item: &Location) -> Result<(), EleboxError> {
+        if self.name_taken(&item.name, None)? {
+            return Err(EleboxError::AlreadyExists(
+                String::from(ITEM_LOCATION),
+                item.name.clone(),
+            ));
+        }
+
+        let db_item = self.to_db_location(item)?;
+        self.db.add(&db_item)?;
+        Ok(())
+    }
+
+    fn update(&self, ori_name: &str, new_item: &Location) -> Result<(), EleboxError> {
+        let ori_id = find_location_id(self.db, ori_name)?;
+
+        if self.name_taken(&new_item.name, Some(&ori_id))? {
+            return Err(EleboxError::AlreadyExists(
+                String::from(ITEM_LOCATION),
+                new_item.name.clone(),
+            ));
+        }
+
+        let db_item = self.to_db_location(new_item)?;
+        if self.subtree_ids(&ori_id)?.contains(&db_item.parent_id) {
+            let parent = new_item.parent.clone().unwrap_or_default();
+            return Err(EleboxError::CircularLocation(parent));
+        }
+
+        self.db.update(&ori_id, &db_item)?;
+        Ok(())
+    }
+
+    fn get(&self, name: &str) -> Result<Location, EleboxError> {
+        let id = find_location_id(self.db, name)?;
+        let db_item = self.db.get(&id)?;
+        Ok(self.to_item(db_item))
+    }
+
+    fn list(&self) -> Result<Vec<Location>, EleboxError> {
+        let db_items = self.db.list()?;
+        Ok(db_items
+            .into_iter()
+            .map(|db_item| self.to_item(db_item))
+            .collect())
+    }
+}
+
+impl Transferable for LocationHandler<'_> {
+    fn export(&self, filename: &PathBuf) -> Result<(), EleboxError> {
+        write_items(filename, self.list()?)
+    }
+
+    fn import(&self, filename: &PathBuf) -> Result<(), EleboxError> {
+        let items: Vec<Location> = read_items(filename)?;
+        for item in &items {
+            match self.add_recursion(item, &items) {
+                Ok(()) | Err(EleboxError::AlreadyExists(_, _)) => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{migrate, Category, StockLine};
+    use uuid::Uuid;
+
+    fn new_manager() -> Manager {
+        let mgr = Manager::in_memory();
+        mgr.category()
+            .add(&Category::new("Capacitors", None, None))
+            .unwrap();
+        mgr.location()
+            .add(&Location::new("Lab", None, None))
+            .unwrap();
+        mgr.location()
+            .add(&Location::new("Cabinet 2", Some("Lab"), None))
+            .unwrap();
+        mgr.location()
+            .add(&Location::new("Drawer A3", Some("Cabinet 2"), None))
+            .unwrap();
+        mgr.location()
+            .add(&Location::new("Shelf", Some("Lab"), None))
+            .unwrap();
+
+        let mut part = Part::new("100nF", "Capacitors", 50);
+        part.location = Some("drawer a3".to_string());
+        mgr.part().add(&part).unwrap();
+
+        let mut part = Part::new("10uF", "Capacitors", 10);
+        part.location = Some("Shelf".to_string());
+        mgr.part().add(&part).unwrap();
+        mgr
+    }
+
+    #[test]
+    fn test_parts_in_subtree() {
+        // Arrange
+        let mgr = new_manager();
+
+        // Act
+        let in_cabinet = mgr.location().parts_in("Cabinet 2").expect("Expected OK");
+        let in_lab = mgr.location().parts_in("Lab").expect("Expected OK");
+
+        // Assert
+        assert_eq!(in_cabinet.len(), 1);
+        assert_eq!(in_cabinet[0].name, "100nF");
+        assert_eq!(in_cabinet[0].location.as_deref(), Some("Drawer A3"));
+        assert_eq!(in_lab.len(), 2);
+    }
+
+    #[test]
+    fn test_transfer_between_locations() {
+        // Arrange
+        let mgr = new_manager();
+
+        // Act
+        mgr.part()
+            .transfer("100nF", 10, None, Some("Shelf"))
+            .expect("Expected OK");
+        let short = mgr.part().update_part_quantity("100nF", -11, Some("Shelf"));
+        mgr.part()
+            .update_part_quantity("100nF", -45, None)
+            .expect("Expected OK");
+        let part = mgr.part().get("100nF").expect("Expected OK");
+        let on_shelf = mgr.location().parts_in("Shelf").expect("Expected OK");
+
+        // Assert
+        assert!(matches!(short, Err(EleboxError::InventoryShortage(_))));
+        assert_eq!(part.quantity, 5);
+        assert_eq!(part.stock, vec![StockLine::new(Some("Shelf"), 5)]);
+        assert_eq!(on_shelf.len(), 2);
+    }
+
+    #[test]
+    fn test_add_same_name_ignoring_case() {
+        // Arrange
+        let mgr = new_manager();
+
+        // Act
+        let result = mgr.location().add(&Location::new("CABINET 2", None, None));
+
+        // Assert
+        assert!(matches!(result, Err(EleboxError::AlreadyExists(_, _))));
+    }
+
+    #[test]
+    fn test_update_circular() {
+        // Arrange
+        let mgr = new_manager();
+
+        // Act
+        let result = mgr
+            .location()
+            .update("Lab", &Location::new("Lab", Some("Drawer A3"), None));
+
+        // Assert
+        assert!(matches!(result, Err(EleboxError::CircularLocation(_))));
+    }
+
+    #[test]
+    fn test_delete_cascade_keeps_parts() {
+        // Arrange
+        let mgr = new_manager();
+
+        // Act
+        mgr.location()
+            .delete_with("Cabinet 2", &DeletePolicy::Cascade)
+            .expect("Expected OK");
+        let part = mgr.part().get("100nF").expect("Expected OK");
+        let tree = mgr.location().get_tree().expect("Expected OK");
+
+        // Assert
+        assert_eq!(part.location, None);
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].children.len(), 1);
+        assert_eq!(tree[0].children[0].name, "Shelf");
+    }
+
+    #[test]
+    fn test_export_import() {
+        // Arrange
+        let mgr = new_manager();
+        let other = Manager::in_memory();
+        let dir = std::env::temp_dir().join(format!("elebox_test_{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("Expected OK");
+        let filename = dir.join("locations.yaml");
+
+        // Act
+        mgr.location().export(&filename).expect("Expected OK");
+        other.location().import(&filename).expect("Expected OK");
+        let wrong_ext = mgr.location().export(&dir.join("locations.txt"));
+        let missing = other.location().import(&dir.join("missing.json"));
+
+        // Assert
+        let drawer = other.location().get("Drawer A3").expect("Expected OK");
+        assert_eq!(drawer.parent.as_deref(), Some("Cabinet 2"));
+        assert!(matches!(wrong_ext, Err(EleboxError::InvalidFile(_, _))));
+        assert!(matches!(missing, Err(EleboxError::InvalidFile(_, _))));
+        std::fs::remove_dir_all(&dir).expect("Expected OK");
+    }
+
+    #[test]
+    fn test_migrate_locations() {
+        // Arrange, free-form locations as stored by older releases
+        let mgr = Manager::in_memory();
+        let shelf = DbLocation {
+            name: "Shelf".to_string(),
+            parent_id: ROOT_LOCATION.to_string(),
+            alias: "".to_string(),
+        };
+        mgr.location_db.update("ID_L1", &shelf).unwrap();
+        let parts = [
+            ("R1", "Drawer A3"),
+            ("R2", "drawer a3 "),
+            ("R3", ""),
+            ("R4", "ID_L1"),
+        ];
+        for (name, location) in parts {
+            mgr.part_db
+                .add(&DbPart {
+                    name: name.to_string(),
+                    quantity: 1,
+                    category_id: "".to_string(),
+                    package_id: "".to_string(),
+                    package_detail: "".to_string(),
+                    mfr_id: "".to_string(),
+                    alias: "".to_string(),
+                    description: "".to_string(),
+                    location_id: location.to_string(),
+                    mfr_no: "".to_string(),
+                    datasheet_link: "".to_string(),
+                    product_link: "".to_string(),
+                    image_link: "".to_string(),
+                    custom_fields: vec![],
+                    suppliers: vec![],
+                    starred: false,
+                    min_stock: None,
+                    reorder_quantity: None,
+                    stock: vec![],
+                })
+                .unwrap();
+        }
+
+        // Act
+        let count = migrate::<DbPart>(&mgr).expect("Expected OK");
+        let again = migrate::<DbPart>(&mgr).expect("Expected OK");
+        let drawer = find_location_id(&*mgr.location_db, "Drawer A3");
+        let parts = mgr.part_db.list().unwrap();
+
+        // Assert
+        assert!(count > 0);
+        assert_eq!(again, 0);
+        assert_eq!(mgr.location_db.list().unwrap().len(), 2);
+        let drawer = drawer.expect("Expected OK");
+        let moved = parts.iter().filter(|p| p.location_id == drawer);
+        assert_eq!(moved.count(), 2);
+        let location_of = |name: &str| {
+            let part = parts.iter().find(|p| p.name == name).unwrap();
+            part.location_id.clone()
+        };
+        assert_eq!(location_of("R3"), "");
+        assert_eq!(location_of("R4"), "ID_L1");
+    }
+}
@@ -1,76 +1,389 @@
 use std::path::PathBuf;
 
 use crate::{
-    CategoryHandler, Database, DbCategory, DbManufacturer, DbPackage, DbPart, EleboxError,
-    ManufacturerHandler, PackageHandler, PartHandler, Transferable,
+    fsck::fsck, is_sqlite_path, kicad_dbl::export_dbl, migrate, migration::check_version,
+    reorder::reorder_groups, report::valuation, CategoryHandler, Database, DbCategory, DbError,
+    DbFieldDef, DbLedgerEntry, DbLocation, DbManufacturer, DbOrder, DbPackage, DbPart, DbProject,
+    DbSupplier, EleboxError, FieldHandler, FsckReport, Handler, JammDatabase, KicadMapping,
+    LedgerHandler, LocationHandler, ManufacturerHandler, MemoryDatabase, OrderHandler,
+    PackageHandler, PartHandler, ProjectHandler, ReorderGroup, SqliteDatabase, SupplierHandler,
+    Transferable, Valuation,
 };
 
 const PART_FILENAME: &str = "elebox_export_parts.yaml";
 const PACKAGE_FILENAME: &str = "elebox_export_packages.yaml";
 const CATEGORY_FILENAME: &str = "elebox_export_categories.yaml";
 const MFR_FILENAME: &str = "elebox_export_mfrs.yaml";
+const PROJECT_FILENAME: &str = "elebox_export_projects.yaml";
+const LOCATION_FILENAME: &str = "elebox_export_locations.yaml";
+const SUPPLIER_FILENAME: &str = "elebox_export_suppliers.yaml";
+const ORDER_FILENAME: &str = "elebox_export_orders.yaml";
+const FIELD_FILENAME: &str = "elebox_export_fields.yaml";
 
 pub struct Manager {
-    part_db: Box<dyn Database<DbPart>>,
-    package_db: Box<dyn Database<DbPackage>>,
-    category_db: Box<dyn Database<DbCategory>>,
-    mfr_db: Box<dyn Database<DbManufacturer>>,
+    pub(crate) part_db: Box<dyn Database<DbPart>>,
+    pub(crate) package_db: Box<dyn Database<DbPackage>>,
+    pub(crate) category_db: Box<dyn Database<DbCategory>>,
+    pub(crate) mfr_db: Box<dyn Database<DbManufacturer>>,
+    pub(crate) ledger_db: Box<dyn Database<DbLedgerEntry>>,
+    pub(crate) project_db: Box<dyn Database<DbProject>>,
+    pub(crate) location_db: Box<dyn Database<DbLocation>>,
+    pub(crate) supplier_db: Box<dyn Database<DbSupplier>>,
+    pub(crate) order_db: Box<dyn Database<DbOrder>>,
+    pub(crate) field_db: Box<dyn Database<DbFieldDef>>,
 }
 
 impl Manager {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         part_db: Box<dyn Database<DbPart>>,
         package_db: Box<dyn Database<DbPackage>>,
         category_db: Box<dyn Database<DbCategory>>,
         mfr_db: Box<dyn Database<DbManufacturer>>,
+        ledger_db: Box<dyn Database<DbLedgerEntry>>,
+        project_db: Box<dyn Database<DbProject>>,
+        location_db: Box<dyn Database<DbLocation>>,
+        supplier_db: Box<dyn Database<DbSupplier>>,
+        order_db: Box<dyn Database<DbOrder>>,
+        field_db: Box<dyn Database<DbFieldDef>>,
     ) -> Self {
         Self {
             part_db,
             package_db,
             category_db,
             mfr_db,
+            ledger_db,
+            project_db,
+            location_db,
+            supplier_db,
+            order_db,
+            field_db,
         }
     }
 
+    /// Open a database file, SQLite is used for `.sqlite`, `.sqlite3` and `.db3` files,
+    /// jammdb otherwise.
+    pub fn open(path: &str) -> Result<Self, DbError> {
+        if is_sqlite_path(path) {
+            let db = SqliteDatabase::open(path)?;
+            return Ok(Self::new(
+                Box::new(db.clone()),
+                Box::new(db.clone()),
+                Box::new(db.clone()),
+                Box::new(db.clone()),
+                Box::new(db.clone()),
+                Box::new(db.clone()),
+                Box::new(db.clone()),
+                Box::new(db.clone()),
+                Box::new(db.clone()),
+                Box::new(db),
+            ));
+        }
+
+        let db = JammDatabase::new(path);
+        Ok(Self::new(
+            Box::new(db.clone()),
+            Box::new(db.clone()),
+            Box::new(db.clone()),
+            Box::new(db.clone()),
+            Box::new(db.clone()),
+            Box::new(db.clone()),
+            Box::new(db.clone()),
+            Box::new(db.clone()),
+            Box::new(db.clone()),
+            Box::new(db),
+        ))
+    }
+
+    /// Create an empty manager that is not backed by any file.
+    pub fn in_memory() -> Self {
+        Self::new(
+            Box::new(MemoryDatabase::<DbPart>::new()),
+            Box::new(MemoryDatabase::<DbPackage>::new()),
+            Box::new(MemoryDatabase::<DbCategory>::new()),
+            Box::new(MemoryDatabase::<DbManufacturer>::new()),
+            Box::new(MemoryDatabase::<DbLedgerEntry>::new()),
+            Box::new(MemoryDatabase::<DbProject>::new()),
+            Box::new(MemoryDatabase::<DbLocation>::new()),
+            Box::new(MemoryDatabase::<DbSupplier>::new()),
+            Box::new(MemoryDatabase::<DbOrder>::new()),
+            Box::new(MemoryDatabase::<DbFieldDef>::new()),
+        )
+    }
+
+    /// Copy all data into an in-memory manager, changes made to the copy
+    /// are not written back, e.g. for a "what-if" inventory. See `copy_into`.
+    pub fn snapshot(&self) -> Result<Self, EleboxError> {
+        let mgr = Self::in_memory();
+        self.copy_into(&mgr)?;
+        Ok(mgr)
+    }
+
+    /// Create the missing buckets and upgrade items stored by older releases.
     pub fn init(&self) -> Result<(), EleboxError> {
         self.part_db.init()?;
         self.category_db.init()?;
         self.package_db.init()?;
         self.mfr_db.init()?;
+        self.ledger_db.init()?;
+        self.project_db.init()?;
+        self.location_db.init()?;
+        self.supplier_db.init()?;
+        self.order_db.init()?;
+        self.field_db.init()?;
+
+        migrate::<DbPart>(self)?;
+        migrate::<DbCategory>(self)?;
+        migrate::<DbPackage>(self)?;
+        migrate::<DbManufacturer>(self)?;
+        migrate::<DbLedgerEntry>(self)?;
+        migrate::<DbProject>(self)?;
+        migrate::<DbLocation>(self)?;
+        migrate::<DbSupplier>(self)?;
+        migrate::<DbOrder>(self)?;
+        migrate::<DbFieldDef>(self)?;
         Ok(())
     }
 
+    /// Check that every item type is initialized and upgraded to the latest
+    /// schema version, `init` upgrades older databases.
     pub fn check(&self) -> Result<(), EleboxError> {
         self.part_db.check()?;
         self.category_db.check()?;
         self.package_db.check()?;
         self.mfr_db.check()?;
+        self.ledger_db.check()?;
+        self.project_db.check()?;
+        self.location_db.check()?;
+        self.supplier_db.check()?;
+        self.order_db.check()?;
+        self.field_db.check()?;
+
+        check_version::<DbPart>(self)?;
+        check_version::<DbCategory>(self)?;
+        check_version::<DbPackage>(self)?;
+        check_version::<DbManufacturer>(self)?;
+        check_version::<DbLedgerEntry>(self)?;
+        check_version::<DbProject>(self)?;
+        check_version::<DbLocation>(self)?;
+        check_version::<DbSupplier>(self)?;
+        check_version::<DbOrder>(self)?;
+        check_version::<DbFieldDef>(self)?;
+        Ok(())
+    }
+
+    /// Run `f` in one transaction over all item types, the changes made by
+    /// the handlers of `tx` are committed if `f` returns `Ok`, or rolled back
+    /// together otherwise.
+    ///
+    /// ```ignore
+    /// manager.transaction(|tx| {
+    ///     tx.category().add(&category)?;
+    ///     tx.part().add(&part)
+    /// })?;
+    /// ```
+    pub fn transaction<T, F>(&self, f: F) -> Result<T, EleboxError>
+    where
+        F: FnOnce(&Manager) -> Result<T, EleboxError>,
+    {
+        self.begin_transaction()?;
+
+        match f(self) {
+            Ok(value) => {
+                self.end_transaction(true)?;
+                Ok(value)
+            }
+            Err(err) => {
+                let _ = self.end_transaction(false);
+                Err(err)
+            }
+        }
+    }
+
+    fn begin_transaction(&self) -> Result<(), DbError> {
+        let begins: [&dyn Fn() -> Result<(), DbError>; 10] = [
+            &|| self.part_db.begin(),
+            &|| self.category_db.begin(),
+            &|| self.package_db.begin(),
+            &|| self.mfr_db.begin(),
+            &|| self.ledger_db.begin(),
+            &|| self.project_db.begin(),
+            &|| self.location_db.begin(),
+            &|| self.supplier_db.begin(),
+            &|| self.order_db.begin(),
+            &|| self.field_db.begin(),
+        ];
+        let rollbacks: [&dyn Fn() -> Result<(), DbError>; 10] = [
+            &|| self.part_db.rollback(),
+            &|| self.category_db.rollback(),
+            &|| self.package_db.rollback(),
+            &|| self.mfr_db.rollback(),
+            &|| self.ledger_db.rollback(),
+            &|| self.project_db.rollback(),
+            &|| self.location_db.rollback(),
+            &|| self.supplier_db.rollback(),
+            &|| self.order_db.rollback(),
+            &|| self.field_db.rollback(),
+        ];
+
+        for (i, begin) in begins.iter().enumerate() {
+            if let Err(err) = begin() {
+                // Roll back the ones already begun, so none is left in the transaction
+                for rollback in &rollbacks[..i] {
+                    let _ = rollback();
+                }
+                return Err(err);
+            }
+        }
         Ok(())
     }
 
+    fn end_transaction(&self, commit: bool) -> Result<(), DbError> {
+        // End all of them even if one fails, so none is left in the transaction
+        let results = if commit {
+            [
+                self.part_db.commit(),
+                self.category_db.commit(),
+                self.package_db.commit(),
+                self.mfr_db.commit(),
+                self.ledger_db.commit(),
+                self.project_db.commit(),
+                self.location_db.commit(),
+                self.supplier_db.commit(),
+                self.order_db.commit(),
+                self.field_db.commit(),
+            ]
+        } else {
+            [
+                self.part_db.rollback(),
+                self.category_db.rollback(),
+                self.package_db.rollback(),
+                self.mfr_db.rollback(),
+                self.ledger_db.rollback(),
+                self.project_db.rollback(),
+                self.location_db.rollback(),
+                self.supplier_db.rollback(),
+                self.order_db.rollback(),
+                self.field_db.rollback(),
+            ]
+        };
+        results.into_iter().collect()
+    }
+
+    /// Check references, category cycles, duplicate names and undecodable
+    /// items. With `repair`, the safe fixes are applied in one transaction,
+    /// see the report for what was fixed.
+    pub fn fsck(&self, repair: bool) -> Result<FsckReport, EleboxError> {
+        let run = |mgr: &Manager| fsck(mgr, repair);
+
+        if repair {
+            self.transaction(run)
+        } else {
+            run(self)
+        }
+    }
+
     pub fn part(&self) -> PartHandler {
         PartHandler {
             db: &*self.part_db,
             pkg_db: &*self.package_db,
             cat_db: &*self.category_db,
             mfr_db: &*self.mfr_db,
+            ledger_db: &*self.ledger_db,
+            loc_db: &*self.location_db,
+            sup_db: &*self.supplier_db,
+            field_db: &*self.field_db,
+            project_db: &*self.project_db,
+            order_db: &*self.order_db,
         }
     }
 
     pub fn category(&self) -> CategoryHandler {
         CategoryHandler {
             db: &*self.category_db,
+            part: self.part(),
         }
     }
 
     pub fn package(&self) -> PackageHandler {
         PackageHandler {
             db: &*self.package_db,
+            part: self.part(),
         }
     }
 
     pub fn manufacturer(&self) -> ManufacturerHandler {
-        ManufacturerHandler { db: &*self.mfr_db }
+        ManufacturerHandler {
+            db: &*self.mfr_db,
+            part: self.part(),
+        }
+    }
+
+    pub fn supplier(&self) -> SupplierHandler<'_> {
+        SupplierHandler {
+            db: &*self.supplier_db,
+            part_db: &*self.part_db,
+            order_db: &*self.order_db,
+        }
+    }
+
+    pub fn order(&self) -> OrderHandler<'_> {
+        OrderHandler {
+            db: &*self.order_db,
+            sup_db: &*self.supplier_db,
+            part: self.part(),
+        }
+    }
+
+    pub fn field(&self) -> FieldHandler<'_> {
+        FieldHandler {
+            db: &*self.field_db,
+            part_db: &*self.part_db,
+            cat_db: &*self.category_db,
+        }
+    }
+
+    pub fn ledger(&self) -> LedgerHandler<'_> {
+        LedgerHandler {
+            db: &*self.ledger_db,
+            part_db: &*self.part_db,
+        }
+    }
+
+    pub fn project(&self) -> ProjectHandler<'_> {
+        ProjectHandler {
+            db: &*self.project_db,
+            part: self.part(),
+        }
+    }
+
+    pub fn location(&self) -> LocationHandler<'_> {
+        LocationHandler {
+            db: &*self.location_db,
+            part: self.part(),
+        }
+    }
+
+    /// Copy all data into another database, e.g. from jammdb to SQLite.
+    /// IDs are kept, so the references between items remain valid. The
+    /// source is upgraded first, as the destination gets the latest schema,
+    /// and nothing is copied if any item fails.
+    pub fn copy_into(&self, dst: &Manager) -> Result<(), EleboxError> {
+        self.init()?;
+        dst.init()?;
+        dst.transaction(|dst| {
+            copy_items(&*self.category_db, &*dst.category_db)?;
+            copy_items(&*self.package_db, &*dst.package_db)?;
+            copy_items(&*self.mfr_db, &*dst.mfr_db)?;
+            copy_items(&*self.location_db, &*dst.location_db)?;
+            copy_items(&*self.supplier_db, &*dst.supplier_db)?;
+            copy_items(&*self.part_db, &*dst.part_db)?;
+            copy_items(&*self.ledger_db, &*dst.ledger_db)?;
+            copy_items(&*self.project_db, &*dst.project_db)?;
+            copy_items(&*self.order_db, &*dst.order_db)?;
+            copy_items(&*self.field_db, &*dst.field_db)?;
+            Ok(())
+        })
     }
 
     pub fn export(&self, path: &PathBuf, extension: &str) -> Result<(), EleboxError> {
@@ -86,49 +399,524 @@ impl Manager {
         let filename = path.join(MFR_FILENAME).with_extension(extension);
         let _ = self.manufacturer().export(&filename)?;
 
+        let filename = path.join(PROJECT_FILENAME).with_extension(extension);
+        self.project().export(&filename)?;
+
+        let filename = path.join(LOCATION_FILENAME).with_extension(extension);
+        self.location().export(&filename)?;
+
+        let filename = path.join(SUPPLIER_FILENAME).with_extension(extension);
+        self.supplier().export(&filename)?;
+
+        let filename = path.join(ORDER_FILENAME).with_extension(extension);
+        self.order().export(&filename)?;
+
+        let filename = path.join(FIELD_FILENAME).with_extension(extension);
+        self.field().export(&filename)?;
+
         Ok(())
     }
 
+    /// Parts at or below their minimum stock, grouped by preferred supplier.
+    /// The parts on open orders count as in stock.
+    pub fn reorder(&self) -> Result<Vec<ReorderGroup>, EleboxError> {
+        let parts = self.part().list()?;
+        let on_order = self.order().on_order()?;
+        Ok(reorder_groups(&parts, &on_order))
+    }
+
+    /// Value of the stock on hand by category, at the prices of the
+    /// preferred suppliers.
+    pub fn valuation(&self) -> Result<Valuation, EleboxError> {
+        let parts = self.part().list()?;
+        Ok(valuation(&parts))
+    }
+
+    /// Export the parts as a KiCad database library, `filename` is the
+    /// `.kicad_dbl` file and the table is written next to it with the
+    /// `.sqlite` extension. Symbols and footprints come from the package
+    /// `mapping`, or from the `Symbol` and `Footprint` custom fields of a part.
+    pub fn export_kicad_dbl(
+        &self,
+        filename: &PathBuf,
+        mapping: &KicadMapping,
+    ) -> Result<(), EleboxError> {
+        let parts = self.part().list()?;
+        export_dbl(filename, &parts, mapping)
+    }
+
+    /// Import all files in one transaction, nothing is imported if any of
+    /// them fails. Missing files are skipped.
     // TODO auto detect type
     pub fn import(&self, path: &PathBuf, extension: &str) -> Result<(), EleboxError> {
-        let filename = path.join(CATEGORY_FILENAME).with_extension(extension);
-        let _ = self.category().import(&filename);
+        self.transaction(|tx| {
+            let filename = path.join(CATEGORY_FILENAME).with_extension(extension);
+            if filename.exists() {
+                tx.category().import(&filename)?;
+            }
 
-        let filename = path.join(PACKAGE_FILENAME).with_extension(extension);
-        let _ = self.package().import(&filename);
+            let filename = path.join(PACKAGE_FILENAME).with_extension(extension);
+            if filename.exists() {
+                tx.package().import(&filename)?;
+            }
 
-        let filename = path.join(MFR_FILENAME).with_extension(extension);
-        let _ = self.manufacturer().import(&filename);
+            let filename = path.join(MFR_FILENAME).with_extension(extension);
+            if filename.exists() {
+                tx.manufacturer().import(&filename)?;
+            }
 
-        let filename = path.join(PART_FILENAME).with_extension(extension);
-        let _ = self.part().import(&filename);
+            let filename = path.join(LOCATION_FILENAME).with_extension(extension);
+            if filename.exists() {
+                tx.location().import(&filename)?;
+            }
 
-        Ok(())
+            let filename = path.join(SUPPLIER_FILENAME).with_extension(extension);
+            if filename.exists() {
+                tx.supplier().import(&filename)?;
+            }
+
+            let filename = path.join(FIELD_FILENAME).with_extension(extension);
+            if filename.exists() {
+                tx.field().import(&filename)?;
+            }
+
+            let filename = path.join(PART_FILENAME).with_extension(extension);
+            if filename.exists() {
+                tx.part().import(&filename)?;
+            }
+
+            let filename = path.join(PROJECT_FILENAME).with_extension(extension);
+            if filename.exists() {
+                tx.project().import(&filename)?;
+            }
+
+            let filename = path.join(ORDER_FILENAME).with_extension(extension);
+            if filename.exists() {
+                tx.order().import(&filename)?;
+            }
+            Ok(())
+        })
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn from(
         part_db: Box<dyn Database<DbPart>>,
         package_db: Box<dyn Database<DbPackage>>,
         category_db: Box<dyn Database<DbCategory>>,
         mfr_db: Box<dyn Database<DbManufacturer>>,
+        ledger_db: Box<dyn Database<DbLedgerEntry>>,
+        project_db: Box<dyn Database<DbProject>>,
+        location_db: Box<dyn Database<DbLocation>>,
+        supplier_db: Box<dyn Database<DbSupplier>>,
+        order_db: Box<dyn Database<DbOrder>>,
+        field_db: Box<dyn Database<DbFieldDef>>,
         path: &PathBuf,
     ) -> Result<Self, EleboxError> {
         // TODO extension
-        let mgr = Self::new(part_db, package_db, category_db, mfr_db);
+        let mgr = Self::new(
+            part_db,
+            package_db,
+            category_db,
+            mfr_db,
+            ledger_db,
+            project_db,
+            location_db,
+            supplier_db,
+            order_db,
+            field_db,
+        );
         let _ = mgr.init();
+        mgr.import(path, "yaml")?;
+        Ok(mgr)
+    }
+}
 
-        let filename = path.join(CATEGORY_FILENAME);
-        let _ = mgr.category().import(&filename)?;
+fn copy_items<DI>(src: &dyn Database<DI>, dst: &dyn Database<DI>) -> Result<(), DbError> {
+    for (id, item) in src.list_with_id()? {
+        dst.update(&id, &item)?;
+    }
+    Ok(())
+}
 
-        let filename = path.join(PACKAGE_FILENAME);
-        let _ = mgr.package().import(&filename)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Category, DeletePolicy, Handler, Manufacturer, Package, PackageType, Part, StockReason,
+    };
+    use std::fs;
+    use uuid::Uuid;
 
-        let filename = path.join(MFR_FILENAME);
-        let _ = mgr.manufacturer().import(&filename)?;
+    fn new_manager() -> Manager {
+        let mgr = Manager::in_memory();
+        mgr.category()
+            .add(&Category::new("Capacitors", None, None))
+            .unwrap();
+        mgr.category()
+            .add(&Category::new("Ceramic Caps", Some("Capacitors"), None))
+            .unwrap();
+        mgr.package()
+            .add(&Package::new("SMD 0402", PackageType::Smt, Some("1005")))
+            .unwrap();
+        mgr.manufacturer()
+            .add(&Manufacturer::new("Murata", None, None))
+            .unwrap();
 
-        let filename = path.join(PART_FILENAME);
-        let _ = mgr.part().import(&filename)?;
+        let mut part = Part::new("100nF", "Ceramic Caps", 50);
+        part.package = Some("SMD 0402".to_string());
+        part.mfr = Some("Murata".to_string());
+        mgr.part().add(&part).unwrap();
+        mgr
+    }
 
-        Ok(mgr)
+    #[test]
+    fn test_part_resolve_names() {
+        // Arrange
+        let mgr = new_manager();
+
+        // Act
+        let part = mgr.part().get("100nF").expect("Expected OK");
+
+        // Assert
+        assert_eq!(part.category, "Ceramic Caps");
+        assert_eq!(part.package.as_deref(), Some("SMD 0402"));
+        assert_eq!(part.mfr.as_deref(), Some("Murata"));
+        assert_eq!(part.quantity, 50);
+    }
+
+    #[test]
+    fn test_part_deleted_reference() {
+        // Arrange, as left by older releases that deleted without checking
+        let mgr = new_manager();
+        let id = mgr.mfr_db.get_id("Murata").unwrap();
+
+        // Act
+        mgr.mfr_db.delete(&id).expect("Expected OK");
+        let part = mgr.part().get("100nF").expect("Expected OK");
+
+        // Assert
+        assert_eq!(part.mfr.as_deref(), Some("__DELETE__"));
+        assert_eq!(part.category, "Ceramic Caps");
+    }
+
+    #[test]
+    fn test_update_part_quantity() {
+        // Arrange
+        let mgr = new_manager();
+
+        // Act
+        mgr.part()
+            .update_part_quantity("100nF", -20, None)
+            .expect("Expected OK");
+        let result = mgr.part().update_part_quantity("100nF", -40, None);
+
+        // Assert
+        assert!(matches!(result, Err(EleboxError::InventoryShortage(_))));
+        assert_eq!(mgr.part().get("100nF").unwrap().quantity, 30);
+    }
+
+    #[test]
+    fn test_export_import() {
+        // Arrange
+        let mgr = new_manager();
+        let dir = std::env::temp_dir().join(format!("elebox_test_{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+
+        // Act
+        mgr.export(&dir, "yaml").expect("Expected OK");
+        let new_mgr = Manager::in_memory();
+        let result = new_mgr.import(&dir, "yaml");
+        let _ = fs::remove_dir_all(&dir);
+
+        // Assert
+        assert!(result.is_ok());
+        let cat = new_mgr.category().get("Ceramic Caps").expect("Expected OK");
+        assert_eq!(cat.parent.as_deref(), Some("Capacitors"));
+
+        let part = new_mgr.part().get("100nF").expect("Expected OK");
+        assert_eq!(part.category, "Ceramic Caps");
+        assert_eq!(part.package.as_deref(), Some("SMD 0402"));
+        assert_eq!(part.mfr.as_deref(), Some("Murata"));
+    }
+
+    #[test]
+    fn test_import_invalid_file() {
+        // Arrange, valid categories but an unreadable parts file
+        let mgr = new_manager();
+        let dir = std::env::temp_dir().join(format!("elebox_test_{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        mgr.export(&dir, "yaml").expect("Expected OK");
+        fs::write(dir.join(PART_FILENAME), "- not: [a part").unwrap();
+
+        // Act
+        let new_mgr = Manager::in_memory();
+        let result = new_mgr.import(&dir, "yaml");
+        let _ = fs::remove_dir_all(&dir);
+
+        // Assert
+        assert!(matches!(result, Err(EleboxError::InvalidFile(_, _))));
+        assert!(new_mgr.category().list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_is_independent() {
+        // Arrange
+        let mgr = new_manager();
+
+        // Act
+        let what_if = mgr.snapshot().expect("Expected OK");
+        what_if
+            .part()
+            .update_part_quantity("100nF", -50, None)
+            .expect("Expected OK");
+
+        // Assert
+        assert_eq!(what_if.part().get("100nF").unwrap().quantity, 0);
+        assert_eq!(mgr.part().get("100nF").unwrap().quantity, 50);
+        assert_eq!(
+            what_if.part().get("100nF").unwrap().category,
+            "Ceramic Caps"
+        );
+    }
+
+    #[test]
+    fn test_transaction_rollback() {
+        // Arrange
+        let mgr = new_manager();
+
+        // Act
+        let result = mgr.transaction(|tx| {
+            tx.category().add(&Category::new("Resistors", None, None))?;
+            tx.part().add(&Part::new("10k", "Resistors", 100))?;
+            tx.part().update_part_quantity("100nF", -60, None)
+        });
+
+        // Assert
+        assert!(matches!(result, Err(EleboxError::InventoryShortage(_))));
+        assert!(mgr.category().get("Resistors").is_err());
+        assert!(mgr.part().get("10k").is_err());
+        assert_eq!(mgr.part().get("100nF").unwrap().quantity, 50);
+    }
+
+    #[test]
+    fn test_transaction_commit() {
+        // Arrange
+        let mgr = new_manager();
+
+        // Act
+        let result = mgr.transaction(|tx| {
+            tx.category().add(&Category::new("Resistors", None, None))?;
+            tx.part().add(&Part::new("10k", "Resistors", 100))
+        });
+
+        // Assert
+        assert!(result.is_ok());
+        assert_eq!(mgr.part().get("10k").unwrap().category, "Resistors");
+    }
+
+    #[test]
+    fn test_delete_restrict() {
+        // Arrange
+        let mgr = new_manager();
+
+        // Act
+        let mfr_result = mgr.manufacturer().delete("Murata");
+        let cat_result = mgr.category().delete("Capacitors");
+
+        // Assert
+        match mfr_result {
+            Err(EleboxError::StillReferenced(_, name, users)) => {
+                assert_eq!(name, "Murata");
+                assert_eq!(users, vec!["100nF".to_string()]);
+            }
+            _ => panic!("Expected StillReferenced"),
+        }
+        match cat_result {
+            Err(EleboxError::StillReferenced(_, _, users)) => {
+                assert_eq!(users, vec!["Ceramic Caps".to_string()]);
+            }
+            _ => panic!("Expected StillReferenced"),
+        }
+        assert!(mgr.manufacturer().get("Murata").is_ok());
+    }
+
+    #[test]
+    fn test_delete_reassign() {
+        // Arrange
+        let mgr = new_manager();
+        mgr.package()
+            .add(&Package::new("SMD 0603", PackageType::Smt, None))
+            .unwrap();
+        mgr.category()
+            .add(&Category::new("Passives", None, None))
+            .unwrap();
+
+        // Act
+        let pkg_result = mgr
+            .package()
+            .delete_with("SMD 0402", &DeletePolicy::Reassign("SMD 0603".to_string()));
+        let circular_result = mgr.category().delete_with(
+            "Capacitors",
+            &DeletePolicy::Reassign("Ceramic Caps".to_string()),
+        );
+        let cat_result = mgr.category().delete_with(
+            "Capacitors",
+            &DeletePolicy::Reassign("Passives".to_string()),
+        );
+
+        // Assert
+        assert!(pkg_result.is_ok());
+        assert!(matches!(
+            circular_result,
+            Err(EleboxError::CircularCategory(_))
+        ));
+        assert!(cat_result.is_ok());
+        assert!(mgr.package().get("SMD 0402").is_err());
+        assert_eq!(
+            mgr.part().get("100nF").unwrap().package.as_deref(),
+            Some("SMD 0603")
+        );
+        assert_eq!(
+            mgr.category()
+                .get("Ceramic Caps")
+                .unwrap()
+                .parent
+                .as_deref(),
+            Some("Passives")
+        );
+    }
+
+    #[test]
+    fn test_delete_cascade() {
+        // Arrange
+        let mgr = new_manager();
+
+        // Act
+        let result = mgr
+            .category()
+            .delete_with("Capacitors", &DeletePolicy::Cascade);
+
+        // Assert
+        assert!(result.is_ok());
+        assert!(mgr.category().list().unwrap().is_empty());
+        assert!(mgr.part().list().unwrap().is_empty());
+        let entries = mgr.ledger().list().unwrap();
+        assert!(!entries.is_empty());
+        assert!(entries.iter().all(|entry| entry.part == "__DELETE__"));
+        assert_eq!(entries.iter().map(|entry| entry.delta).sum::<i32>(), 0);
+        assert!(mgr.manufacturer().get("Murata").is_ok());
+    }
+
+    #[test]
+    fn test_delete_part_keeps_ledger() {
+        // Arrange
+        let mgr = new_manager();
+        mgr.part()
+            .update_part_quantity("100nF", -20, None)
+            .expect("Expected OK");
+
+        // Act
+        mgr.part().delete("100nF").expect("Expected OK");
+        let entries = mgr.ledger().list().expect("Expected OK");
+
+        // Assert
+        let deltas: Vec<i32> = entries.iter().map(|entry| entry.delta).collect();
+        assert_eq!(deltas, vec![50, -20, -30]);
+        assert_eq!(entries[2].reason, StockReason::Adjustment);
+        assert_eq!(entries[2].note.as_deref(), Some("Part deleted"));
+        assert!(mgr.fsck(false).expect("Expected OK").is_clean());
+    }
+
+    #[test]
+    fn test_ledger_records_movements() {
+        // Arrange
+        let mgr = new_manager();
+
+        // Act
+        mgr.part()
+            .move_stock(
+                "100nF",
+                -20,
+                None,
+                StockReason::Use,
+                None,
+                Some("Clock board"),
+            )
+            .expect("Expected OK");
+        mgr.part()
+            .update_part_quantity("100nF", 5, None)
+            .expect("Expected OK");
+        let _ = mgr.part().update_part_quantity("100nF", -100, None);
+        let entries = mgr.ledger().list_part("100nF").expect("Expected OK");
+
+        // Assert
+        let deltas: Vec<i32> = entries.iter().map(|entry| entry.delta).collect();
+        assert_eq!(deltas, vec![50, -20, 5]);
+        assert_eq!(entries[0].reason, StockReason::Adjustment);
+        assert_eq!(entries[1].reason, StockReason::Use);
+        assert_eq!(entries[1].project.as_deref(), Some("Clock board"));
+        assert_eq!(entries[2].reason, StockReason::Restock);
+        assert_eq!(mgr.ledger().balance("100nF").expect("Expected OK"), 35);
+        assert!(mgr.ledger().verify("100nF").is_ok());
+    }
+
+    #[test]
+    fn test_ledger_stock_overflow() {
+        // Arrange
+        let mgr = new_manager();
+
+        // Act
+        let result = mgr.part().update_part_quantity("100nF", i16::MAX, None);
+        let again = mgr.part().update_part_quantity("100nF", i16::MAX, None);
+        let entries = mgr.ledger().list_part("100nF").expect("Expected OK");
+
+        // Assert
+        assert!(result.is_ok());
+        assert!(matches!(again, Err(EleboxError::StockOverflow(_))));
+        let deltas: Vec<i32> = entries.iter().map(|entry| entry.delta).collect();
+        assert_eq!(deltas, vec![50, i16::MAX as i32]);
+        assert_eq!(
+            mgr.part().get("100nF").unwrap().quantity,
+            50 + i16::MAX as u16
+        );
+        assert!(mgr.ledger().verify("100nF").is_ok());
+    }
+
+    #[test]
+    fn test_ledger_verify_mismatch() {
+        // Arrange, quantity changed without going through the handler
+        let mgr = new_manager();
+        let id = mgr.part_db.get_id("100nF").unwrap();
+        let mut part = mgr.part_db.get(&id).unwrap();
+        part.quantity = 42;
+        mgr.part_db.update(&id, &part).unwrap();
+
+        // Act
+        let result = mgr.ledger().verify("100nF");
+
+        // Assert
+        assert!(matches!(
+            result,
+            Err(EleboxError::LedgerMismatch(_, 42, 50))
+        ));
+    }
+
+    #[test]
+    fn test_ledger_range_and_rollback() {
+        // Arrange
+        let mgr = new_manager();
+
+        // Act
+        let _ = mgr.transaction(|tx| {
+            tx.part().update_part_quantity("100nF", 10, None)?;
+            tx.part().update_part_quantity("100nF", -100, None)
+        });
+        let all = mgr.ledger().list().expect("Expected OK");
+        let none = mgr.ledger().list_range(0, 1).expect("Expected OK");
+
+        // Assert
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].part, "100nF");
+        assert!(none.is_empty());
     }
 }
@@ -1,4 +1,4 @@
-use crate::{comm::*, errors::EleboxError, jamm_db::*, json::*, yaml::*};
+use crate::{comm::*, errors::EleboxError, jamm_db::*, part::delete_referenced, PartHandler};
 use serde::{Deserialize, Serialize};
 use std::{fmt::Debug, path::PathBuf};
 
@@ -21,6 +21,7 @@ impl Manufacturer {
 
 pub struct ManufacturerHandler<'a> {
     pub(crate) db: &'a dyn Database<DbManufacturer>,
+    pub(crate) part: PartHandler<'a>,
 }
 
 impl ManufacturerHandler<'_> {
@@ -48,11 +49,19 @@ impl ManufacturerHandler<'_> {
     }
 }
 
+impl ManufacturerHandler<'_> {
+    /// Delete the manufacturer, the parts that still use it are handled by `policy`.
+    pub fn delete_with(&self, name: &str, policy: &DeletePolicy) -> Result<(), EleboxError> {
+        delete_referenced(self.db, &self.part, ITEM_MFR, name, policy, |part| {
+            &mut part.mfr_id
+        })
+    }
+}
+
 impl<'a> Handler<Manufacturer> for ManufacturerHandler<'_> {
+    /// Delete the manufacturer, refused if any part still uses it.
     fn delete(&self, name: &str) -> Result<(), EleboxError> {
-        let id = self.db.get_id(name)?;
-        let _ = self.db.delete(&id)?;
-        Ok(())
+        self.delete_with(name, &DeletePolicy::Restrict)
     }
 
     fn add(&self, item: &Manufacturer) -> Result<(), EleboxError> {
@@ -101,38 +110,16 @@ impl<'a> Handler<Manufacturer> for ManufacturerHandler<'_> {
 
 impl Transferable for ManufacturerHandler<'_> {
     fn export(&self, filename: &PathBuf) -> Result<(), EleboxError> {
-        let items = self.list()?;
-
-        if YamlFile::check_extension(filename) {
-            let _ = YamlFile::write(filename, items);
-        } else if JsonFile::check_extension(filename) {
-            let _ = JsonFile::write(filename, items);
-        } else {
-            todo!()
-        }
-
-        Ok(())
+        write_items(filename, self.list()?)
     }
 
     fn import(&self, filename: &PathBuf) -> Result<(), EleboxError> {
-        let res_items: Result<Vec<Manufacturer>, ()> = if YamlFile::check_extension(filename) {
-            YamlFile::read(filename)
-        } else if JsonFile::check_extension(filename) {
-            JsonFile::read(filename)
-        } else {
-            todo!()
-        };
-
-        if res_items.is_err() {
-            todo!()
-        }
-
-        let items: Vec<Manufacturer> = res_items.unwrap();
+        let items: Vec<Manufacturer> = read_items(filename)?;
         for item in items {
             if let Err(e) = self.add(&item) {
                 match e {
                     EleboxError::AlreadyExists(_, _) => continue,
-                    others => panic!("{}", others.to_string()),
+                    others => return Err(others),
                 }
             }
         }
@@ -144,7 +131,7 @@ impl Transferable for ManufacturerHandler<'_> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::DbError;
+    use crate::{DbError, Manager};
     use mockall::{mock, predicate::*};
 
     mock! {
@@ -155,10 +142,17 @@ mod tests {
             fn get_id(&self, name: &str) -> Result<String, DbError>;
             fn get(&self, id: &str) -> Result<DbManufacturer, DbError>;
             fn list(&self) -> Result<Vec<DbManufacturer>, DbError>;
+            fn list_with_id(&self) -> Result<Vec<(Id, DbManufacturer)>, DbError>;
+            fn scan(&self) -> Result<Scanned<DbManufacturer>, DbError>;
             fn add(&self, item: &DbManufacturer) -> Result<(), DbError>;
             fn update(&self, ori_id: &str, new_item: &DbManufacturer) -> Result<(), DbError>;
             fn delete(&self, id: &str) -> Result<(), DbError>;
             fn check(&self) -> Result<(), DbError>;
+            fn get_version(&self) -> Result<u32, DbError>;
+            fn set_version(&self, version: u32) -> Result<(), DbError>;
+            fn begin(&self) -> Result<(), DbError>;
+            fn commit(&self) -> Result<(), DbError>;
+            fn rollback(&self) -> Result<(), DbError>;
         }
     }
 
@@ -211,7 +205,11 @@ mod tests {
             .returning(|_| Ok(()));
 
         // Act
-        let handler = ManufacturerHandler { db: &mock_db };
+        let mgr = Manager::in_memory();
+        let handler = ManufacturerHandler {
+            db: &mock_db,
+            part: mgr.part(),
+        };
         let result = handler.add(&mfr);
 
         // Assert
@@ -239,7 +237,11 @@ mod tests {
             .returning(|_| Ok(()));
 
         // Act
-        let handler = ManufacturerHandler { db: &mock_db };
+        let mgr = Manager::in_memory();
+        let handler = ManufacturerHandler {
+            db: &mock_db,
+            part: mgr.part(),
+        };
         let result = handler.delete(NAME);
 
         // Assert
@@ -272,7 +274,11 @@ mod tests {
         });
 
         // Act
-        let handler = ManufacturerHandler { db: &mock_db };
+        let mgr = Manager::in_memory();
+        let handler = ManufacturerHandler {
+            db: &mock_db,
+            part: mgr.part(),
+        };
         let result = handler.get(NAME);
 
         // Assert
@@ -305,7 +311,11 @@ mod tests {
         });
 
         // Act
-        let handler = ManufacturerHandler { db: &mock_db };
+        let mgr = Manager::in_memory();
+        let handler = ManufacturerHandler {
+            db: &mock_db,
+            part: mgr.part(),
+        };
         let result = handler.list();
 
         // Assert
@@ -352,7 +362,11 @@ mod tests {
             url: Some(URL.to_string()),
         };
 
-        let handler = ManufacturerHandler { db: &mock_db };
+        let mgr = Manager::in_memory();
+        let handler = ManufacturerHandler {
+            db: &mock_db,
+            part: mgr.part(),
+        };
         let result = handler.update(NAME, &new_item);
 
         // Assert
@@ -401,7 +415,11 @@ mod tests {
             url: Some(URL.to_string()),
         };
 
-        let handler = ManufacturerHandler { db: &mock_db };
+        let mgr = Manager::in_memory();
+        let handler = ManufacturerHandler {
+            db: &mock_db,
+            part: mgr.part(),
+        };
         let result = handler.update(ORI_NAME, &new_item);
 
         // Assert
@@ -440,7 +458,11 @@ mod tests {
             url: Some(URL.to_string()),
         };
 
-        let handler = ManufacturerHandler { db: &mock_db };
+        let mgr = Manager::in_memory();
+        let handler = ManufacturerHandler {
+            db: &mock_db,
+            part: mgr.part(),
+        };
         let result = handler.update(ORI_NAME, &new_item);
 
         // Assert
@@ -467,7 +489,11 @@ mod tests {
         let mock_db = MockMyDatabase::new();
 
         // Act
-        let handler = ManufacturerHandler { db: &mock_db };
+        let mgr = Manager::in_memory();
+        let handler = ManufacturerHandler {
+            db: &mock_db,
+            part: mgr.part(),
+        };
         let result = handler.to_db_item(&item);
 
         // Assert
@@ -493,7 +519,11 @@ mod tests {
         let mock_db = MockMyDatabase::new();
 
         // Act
-        let handler = ManufacturerHandler { db: &mock_db };
+        let mgr = Manager::in_memory();
+        let handler = ManufacturerHandler {
+            db: &mock_db,
+            part: mgr.part(),
+        };
         let result = handler.to_db_item(&item);
 
         // Assert
@@ -519,7 +549,11 @@ mod tests {
         let mock_db = MockMyDatabase::new();
 
         // Act
-        let handler = ManufacturerHandler { db: &mock_db };
+        let mgr = Manager::in_memory();
+        let handler = ManufacturerHandler {
+            db: &mock_db,
+            part: mgr.part(),
+        };
         let item = handler.to_item(&item);
 
         // Assert
@@ -544,7 +578,11 @@ mod tests {
         let mock_db = MockMyDatabase::new();
 
         // Act
-        let handler = ManufacturerHandler { db: &mock_db };
+        let mgr = Manager::in_memory();
+        let handler = ManufacturerHandler {
+            db: &mock_db,
+            part: mgr.part(),
+        };
         let item = handler.to_item(&item);
 
         // Assert
@@ -0,0 +1,421 @@
+use crate::{
+    errors::*,
+    param::{pow10, SiValue},
+    ParamRange, PartQuery, PassiveKind,
+};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Values of the E96 series, indexed by the EIA-96 code minus one.
+const E96: [u16; 96] = [
+    100, 102, 105, 107, 110, 113, 115, 118, 121, 124, 127, 130, 133, 137, 140, 143, 147, 150, 154,
+    158, 162, 165, 169, 174, 178, 182, 187, 191, 196, 200, 205, 210, 215, 221, 226, 232, 237, 243,
+    249, 255, 261, 267, 274, 280, 287, 294, 301, 309, 316, 324, 332, 340, 348, 357, 365, 374, 383,
+    392, 402, 412, 422, 432, 442, 453, 464, 475, 487, 499, 511, 523, 536, 549, 562, 576, 590, 604,
+    619, 634, 649, 665, 681, 698, 715, 732, 750, 768, 787, 806, 825, 845, 866, 887, 909, 931, 953,
+    976,
+];
+
+/// Colour bands by their digit, `None` for the bands that are only used as
+/// multiplier or tolerance.
+const COLOURS: [(&str, Option<u32>); 14] = [
+    ("black", Some(0)),
+    ("brown", Some(1)),
+    ("red", Some(2)),
+    ("orange", Some(3)),
+    ("yellow", Some(4)),
+    ("green", Some(5)),
+    ("blue", Some(6)),
+    ("violet", Some(7)),
+    ("purple", Some(7)),
+    ("grey", Some(8)),
+    ("gray", Some(8)),
+    ("white", Some(9)),
+    ("gold", None),
+    ("silver", None),
+];
+
+/// Value read off a component, e.g. `103` on a resistor as 10kΩ, on a
+/// capacitor as 10nF and on an inductor as 10µH.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Marking {
+    pub kind: PassiveKind,
+    pub value: SiValue,
+    /// e.g. `5%` for a gold band or `0.25pF` for a `C` suffix.
+    pub tolerance: Option<SiValue>,
+}
+
+fn percent(text: &str) -> Option<SiValue> {
+    SiValue::parse(&format!("{text}%"))
+}
+
+impl Marking {
+    /// Decode a marking code like `103`, `4R7`, `1002`, an EIA-96 code like
+    /// `01C`, a capacitor or inductor code with tolerance like `104K`, or
+    /// colour bands like `brown black orange gold`.
+    pub fn decode(code: &str, kind: PassiveKind) -> Result<Self, EleboxError> {
+        let invalid = |reason: &str| EleboxError::InvalidMarking(code.to_string(), reason.into());
+        let text = code.trim();
+        if text.is_empty() {
+            return Err(invalid("it is empty"));
+        }
+
+        let words: Vec<&str> = text
+            .split(|c: char| c.is_whitespace() || c == '-' || c == ',')
+            .filter(|w| !w.is_empty())
+            .collect();
+        if words.len() > 1 {
+            if kind != PassiveKind::Resistor {
+                return Err(invalid("colour bands are only decoded for resistors"));
+            }
+            return Self::decode_bands(&words).ok_or_else(|| invalid("unknown colour bands"));
+        }
+
+        let (value, tolerance) = match kind {
+            PassiveKind::Resistor => (Self::resistor_value(text), None),
+            PassiveKind::Capacitor | PassiveKind::Inductor => {
+                // A letter after the digits is the tolerance, e.g. `104K`
+                let (digits, tolerance) = match text.char_indices().last() {
+                    Some((i, c)) if i > 0 && c.is_ascii_alphabetic() && !matches!(c, 'R' | 'r') => {
+                        (
+                            &text[..i],
+                            Some(
+                                Self::tolerance_letter(c)
+                                    // Absolute tolerances are in picofarads
+                                    .filter(|t| kind == PassiveKind::Capacitor || t.unit == "%")
+                                    .ok_or_else(|| invalid("unknown tolerance letter"))?,
+                            ),
+                        )
+                    }
+                    _ => (text, None),
+                };
+                let value = match kind {
+                    PassiveKind::Capacitor => {
+                        Self::small_value(digits).map(|pf| (pf * pow10(-12), "F"))
+                    }
+                    _ => Self::small_value(digits).map(|uh| (uh * pow10(-6), "H")),
+                };
+                (value, tolerance)
+            }
+        };
+        let (value, unit) = value.ok_or_else(|| invalid("unknown code"))?;
+        let tolerance = tolerance.or_else(|| {
+            // EIA-96 is for 1% resistors
+            let eia96 = text.len() == 3
+                && text.ends_with(|c: char| c.is_ascii_alphabetic())
+                && Self::decimal_point(text).is_none();
+            (kind == PassiveKind::Resistor && eia96)
+                .then(|| percent("1"))
+                .flatten()
+        });
+
+        Ok(Self {
+            kind,
+            value: SiValue {
+                value: value.normalize(),
+                unit: unit.to_string(),
+            },
+            tolerance,
+        })
+    }
+
+    /// Value in ohms of `4R7`, `103`, `1002` or `01C`.
+    fn resistor_value(text: &str) -> Option<(Decimal, &'static str)> {
+        if let Some(value) = Self::decimal_point(text) {
+            return Some((value, "Ω"));
+        }
+
+        let digits: Option<Vec<u32>> = text.chars().map(|c| c.to_digit(10)).collect();
+        match digits.as_deref() {
+            // Zero ohm jumpers
+            Some(digits) if !digits.is_empty() && digits.iter().all(|d| *d == 0) => {
+                Some((Decimal::ZERO, "Ω"))
+            }
+            Some(digits @ [_, _, _]) | Some(digits @ [_, _, _, _]) => {
+                let (exp, significand) = digits.split_last()?;
+                Some((Self::significand(significand) * pow10(*exp as i32), "Ω"))
+            }
+            Some(_) => None,
+            None => {
+                // EIA-96, two digits and a letter. `R` is taken as the
+                // decimal point above, `68R` is 68Ω rather than 4.99Ω.
+                let index: usize = text.get(..2)?.parse().ok()?;
+                let value = Decimal::from(*E96.get(index.checked_sub(1)?)?);
+                let exp = match text.get(2..)? {
+                    "Z" => -3,
+                    "Y" => -2,
+                    "X" | "S" => -1,
+                    "A" => 0,
+                    "B" | "H" => 1,
+                    "C" => 2,
+                    "D" => 3,
+                    "E" => 4,
+                    "F" => 5,
+                    _ => return None,
+                };
+                Some((value * pow10(exp), "Ω"))
+            }
+        }
+    }
+
+    /// Value of a capacitor code in picofarads or an inductor code in
+    /// microhenries, e.g. `4R7`, `47` or `104`. `8` and `9` as multiplier are
+    /// 0.01 and 0.1.
+    fn small_value(text: &str) -> Option<Decimal> {
+        let value = match Self::decimal_point(text) {
+            Some(value) => value,
+            None => {
+                let digits: Vec<u32> = text
+                    .chars()
+                    .map(|c| c.to_digit(10))
+                    .collect::<Option<_>>()?;
+                match digits.as_slice() {
+                    [_] | [_, _] => Self::significand(&digits),
+                    [a, b, exp] => {
+                        let exp = match exp {
+                            8 => -2,
+                            9 => -1,
+                            exp => *exp as i32,
+                        };
+                        Self::significand(&[*a, *b]) * pow10(exp)
+                    }
+                    _ => return None,
+                }
+            }
+        };
+        Some(value)
+    }
+
+    /// Value of a code with `R` as the decimal point, e.g. `4R7` or `R47`.
+    fn decimal_point(text: &str) -> Option<Decimal> {
+        let (int, frac) = text.split_once(['R', 'r'])?;
+        let all_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+        if int.len() + frac.len() == 0 || !all_digits(int) || !all_digits(frac) {
+            return None;
+        }
+        Decimal::from_str(&format!("0{int}.{frac}0")).ok()
+    }
+
+    fn significand(digits: &[u32]) -> Decimal {
+        digits.iter().fold(Decimal::ZERO, |acc, d| {
+            acc * Decimal::TEN + Decimal::from(*d)
+        })
+    }
+
+    /// Tolerance letter of a capacitor or inductor code, e.g. `K` for 10%.
+    fn tolerance_letter(letter: char) -> Option<SiValue> {
+        match letter.to_ascii_uppercase() {
+            'B' => SiValue::parse("0.1pF"),
+            'C' => SiValue::parse("0.25pF"),
+            'D' => SiValue::parse("0.5pF"),
+            'F' => percent("1"),
+            'G' => percent("2"),
+            'J' => percent("5"),
+            'K' => percent("10"),
+            'M' => percent("20"),
+            _ => None,
+        }
+    }
+
+    /// Decode 3 to 6 colour bands, the digits, the multiplier, the
+    /// tolerance and the temperature coefficient which is ignored.
+    fn decode_bands(words: &[&str]) -> Option<Self> {
+        let bands: Vec<(&str, Option<u32>)> = words
+            .iter()
+            .map(|w| {
+                let w = w.to_lowercase();
+                COLOURS.iter().find(|(name, _)| *name == w).copied()
+            })
+            .collect::<Option<_>>()?;
+
+        let (digit_count, has_tolerance) = match bands.len() {
+            3 => (2, false),
+            4 => (2, true),
+            5 | 6 => (3, true),
+            _ => return None,
+        };
+        let digits: Vec<u32> = bands[..digit_count]
+            .iter()
+            .map(|(_, d)| *d)
+            .collect::<Option<_>>()?;
+        let exp = match bands[digit_count] {
+            ("gold", _) => -1,
+            ("silver", _) => -2,
+            (_, Some(d)) => d as i32,
+            _ => return None,
+        };
+        let tolerance = if has_tolerance {
+            let pct = match bands[digit_count + 1].0 {
+                "brown" => "1",
+                "red" => "2",
+                "orange" => "0.05",
+                "yellow" => "0.02",
+                "green" => "0.5",
+                "blue" => "0.25",
+                "violet" | "purple" => "0.1",
+                "grey" | "gray" => "0.05",
+                "gold" => "5",
+                "silver" => "10",
+                _ => return None,
+            };
+            percent(pct)
+        } else {
+            percent("20")
+        };
+
+        Some(Self {
+            kind: PassiveKind::Resistor,
+            value: SiValue {
+                value: (Self::significand(&digits) * pow10(exp)).normalize(),
+                unit: "Ω".to_string(),
+            },
+            tolerance,
+        })
+    }
+
+    /// Query for the parts with this value, in the package if given.
+    pub fn query(&self, package: Option<String>) -> PartQuery {
+        PartQuery {
+            package,
+            parameters: vec![ParamRange {
+                name: self.kind.parameter().to_string(),
+                min: Some(self.value.clone()),
+                max: Some(self.value.clone()),
+            }],
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CustomField, CustomFieldType, Handler, Manager, Package, PackageType, Part};
+
+    fn decode(code: &str, kind: PassiveKind) -> String {
+        let marking = Marking::decode(code, kind).expect("Expected OK");
+        match marking.tolerance {
+            Some(tolerance) => format!("{} ±{}", marking.value, tolerance),
+            None => marking.value.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_decode_resistor_codes() {
+        // Arrange
+        let cases = [
+            ("103", "10kΩ"),
+            ("4R7", "4.7Ω"),
+            ("R47", "470mΩ"),
+            ("1002", "10kΩ"),
+            ("000", "0Ω"),
+            ("01C", "10kΩ ±1%"),
+            ("68X", "49.9Ω ±1%"),
+            ("brown black orange gold", "10kΩ ±5%"),
+            ("Yellow-Violet-Red", "4.7kΩ ±20%"),
+            ("brown black black red brown", "10kΩ ±1%"),
+        ];
+
+        for (code, expected) in cases {
+            // Act
+            let decoded = decode(code, PassiveKind::Resistor);
+
+            // Assert
+            assert_eq!(decoded, expected, "{code}");
+        }
+    }
+
+    #[test]
+    fn test_decode_capacitor_and_inductor_codes() {
+        // Arrange
+        let cases = [
+            ("104", PassiveKind::Capacitor, "100nF"),
+            ("103J", PassiveKind::Capacitor, "10nF ±5%"),
+            ("4R7", PassiveKind::Capacitor, "4.7pF"),
+            ("22", PassiveKind::Capacitor, "22pF"),
+            ("229", PassiveKind::Capacitor, "2.2pF"),
+            ("1R0C", PassiveKind::Capacitor, "1pF ±250fF"),
+            ("100", PassiveKind::Inductor, "10µH"),
+            ("4R7M", PassiveKind::Inductor, "4.7µH ±20%"),
+            ("R10", PassiveKind::Inductor, "100nH"),
+        ];
+
+        for (code, kind, expected) in cases {
+            // Act
+            let decoded = decode(code, kind);
+
+            // Assert
+            assert_eq!(decoded, expected, "{code}");
+        }
+    }
+
+    #[test]
+    fn test_decode_invalid_codes() {
+        // Arrange
+        let cases = [
+            ("", PassiveKind::Resistor),
+            ("97A", PassiveKind::Resistor),
+            ("12345", PassiveKind::Resistor),
+            ("brown pink orange", PassiveKind::Resistor),
+            ("brown black orange", PassiveKind::Capacitor),
+            ("12345", PassiveKind::Capacitor),
+            ("104Q", PassiveKind::Capacitor),
+            ("1R0C", PassiveKind::Inductor),
+        ];
+
+        for (code, kind) in cases {
+            // Act
+            let result = Marking::decode(code, kind);
+
+            // Assert
+            assert!(
+                matches!(result, Err(EleboxError::InvalidMarking(_, _))),
+                "{code}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_find_by_marking() {
+        // Arrange
+        let mgr = Manager::in_memory();
+        mgr.category()
+            .add(&crate::Category::new("Resistors", None, None))
+            .unwrap();
+        mgr.package()
+            .add(&Package::new("SMD 0603", PackageType::Smt, None))
+            .unwrap();
+        mgr.package()
+            .add(&Package::new("SMD 0805", PackageType::Smt, None))
+            .unwrap();
+        for (name, value, package) in [
+            ("R1", "10k", "SMD 0603"),
+            ("R2", "10kΩ", "SMD 0805"),
+            ("R3", "4.7kΩ", "SMD 0805"),
+        ] {
+            let mut part = Part::new(name, "Resistors", 10);
+            part.package = Some(package.to_string());
+            part.custom_fields = vec![CustomField {
+                field_type: CustomFieldType::Parameter,
+                name: "Resistance".to_string(),
+                value: value.to_string(),
+            }];
+            mgr.part().add(&part).unwrap();
+        }
+        let marking = Marking::decode("1002", PassiveKind::Resistor).unwrap();
+
+        // Act
+        let all = mgr.part().find_by_marking(&marking, None);
+        let in_package = mgr.part().find_by_marking(&marking, Some("0805"));
+
+        // Assert
+        let names = |parts: Vec<Part>| {
+            let mut names: Vec<_> = parts.into_iter().map(|p| p.name).collect();
+            names.sort();
+            names
+        };
+        assert_eq!(names(all.expect("Expected OK")), vec!["R1", "R2"]);
+        assert_eq!(names(in_package.expect("Expected OK")), vec!["R2"]);
+    }
+}
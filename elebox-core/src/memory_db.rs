@@ -0,0 +1,272 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard,
+    },
+};
+
+use uuid::Uuid;
+
+use crate::{comm::*, jamm_db::*, DbError};
+
+/// Non-persistent backend that keeps all items in memory, for tests and
+/// scratch inventories. Behaves like `JammDatabase`: items are ordered by ID,
+/// `add()` assigns a new UUID and `update()` inserts or replaces.
+pub struct MemoryDatabase<DI> {
+    items: RwLock<BTreeMap<Id, DI>>,
+    backup: Mutex<Option<Backup<DI>>>,
+    tx: TxLock,
+    version: AtomicU32,
+}
+
+/// Items and version before the outermost transaction began, restored on
+/// rollback.
+struct Backup<DI> {
+    items: BTreeMap<Id, DI>,
+    version: u32,
+}
+
+impl<DI> MemoryDatabase<DI> {
+    pub fn new() -> Self {
+        Self::with_items(BTreeMap::new())
+    }
+
+    fn with_items(items: BTreeMap<Id, DI>) -> Self {
+        Self {
+            items: RwLock::new(items),
+            backup: Mutex::new(None),
+            tx: TxLock::default(),
+            version: AtomicU32::new(0),
+        }
+    }
+
+    // A rollback restores all items, so other threads wait for the
+    // transaction to end rather than having their changes dropped with it
+    fn read(&self) -> Result<RwLockReadGuard<'_, BTreeMap<Id, DI>>, DbError> {
+        self.tx
+            .outside(|| self.items.read())
+            .map_err(|_| DbError::AccessFailed("memory".to_string()))
+    }
+
+    fn write(&self) -> Result<RwLockWriteGuard<'_, BTreeMap<Id, DI>>, DbError> {
+        self.tx
+            .outside(|| self.items.write())
+            .map_err(|_| DbError::AccessFailed("memory".to_string()))
+    }
+
+    fn backup(&self) -> Result<MutexGuard<'_, Option<Backup<DI>>>, DbError> {
+        self.backup
+            .lock()
+            .map_err(|_| DbError::AccessFailed("memory".to_string()))
+    }
+
+    /// End a level of the transaction, returns whether the outermost one committed.
+    fn end(&self, commit: bool) -> Result<Option<bool>, DbError> {
+        self.tx.end(commit, |commit| {
+            let backup = self.backup()?.take();
+            if let Some(backup) = backup.filter(|_| !commit) {
+                *self.write()? = backup.items;
+                self.version.store(backup.version, Ordering::SeqCst);
+            }
+            Ok(())
+        })
+    }
+}
+
+impl<DI: Clone> MemoryDatabase<DI> {
+    /// Copy all items of another database, IDs are kept.
+    pub fn from_database(src: &dyn Database<DI>) -> Result<Self, DbError> {
+        let items = src.list_with_id()?;
+        let db = Self::with_items(items.into_iter().collect());
+        db.version.store(src.get_version()?, Ordering::SeqCst);
+        Ok(db)
+    }
+}
+
+impl<DI> Default for MemoryDatabase<DI> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<DI> Database<DI> for MemoryDatabase<DI>
+where
+    DI: DatabaseItem + Clone + Send + Sync,
+{
+    fn init(&self) -> Result<(), DbError> {
+        Ok(())
+    }
+
+    fn get_id(&self, name: &str) -> Result<String, DbError> {
+        if !DI::is_named() {
+            return Err(DbError::NotExists(DI::get_bucket()));
+        }
+
+        let items = self.read()?;
+        items
+            .iter()
+            .find(|(_, item)| item.get_name() == name)
+            .map(|(id, _)| id.clone())
+            .ok_or_else(|| DbError::NotExists(DI::get_bucket()))
+    }
+
+    fn get(&self, id: &str) -> Result<DI, DbError> {
+        let items = self.read()?;
+        items
+            .get(id)
+            .cloned()
+            .ok_or_else(|| DbError::NotExists(DI::get_bucket()))
+    }
+
+    fn list(&self) -> Result<Vec<DI>, DbError> {
+        let items = self.read()?;
+        Ok(items.values().cloned().collect())
+    }
+
+    fn list_with_id(&self) -> Result<Vec<(Id, DI)>, DbError> {
+        let items = self.read()?;
+        Ok(items
+            .iter()
+            .map(|(id, item)| (id.clone(), item.clone()))
+            .collect())
+    }
+
+    fn scan(&self) -> Result<Scanned<DI>, DbError> {
+        let items = self.list_with_id()?;
+        Ok(items.into_iter().map(|(id, item)| (id, Ok(item))).collect())
+    }
+
+    fn add(&self, item: &DI) -> Result<(), DbError> {
+        let id = Uuid::new_v4().to_string();
+        self.write()?.insert(id, item.clone());
+        Ok(())
+    }
+
+    fn update(&self, ori_id: &str, new_item: &DI) -> Result<(), DbError> {
+        self.write()?.insert(ori_id.to_string(), new_item.clone());
+        Ok(())
+    }
+
+    fn delete(&self, id: &str) -> Result<(), DbError> {
+        match self.write()?.remove(id) {
+            Some(_) => Ok(()),
+            None => Err(DbError::NotExists(DI::get_bucket())),
+        }
+    }
+
+    fn check(&self) -> Result<(), DbError> {
+        self.read().map(|_| ())
+    }
+
+    fn get_version(&self) -> Result<u32, DbError> {
+        Ok(self.version.load(Ordering::SeqCst))
+    }
+
+    fn set_version(&self, version: u32) -> Result<(), DbError> {
+        self.version.store(version, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn begin(&self) -> Result<(), DbError> {
+        if self.tx.begin() {
+            let items = self.read().map(|items| items.clone());
+            let backup = items.and_then(|items| {
+                let version = self.version.load(Ordering::SeqCst);
+                *self.backup()? = Some(Backup { items, version });
+                Ok(())
+            });
+            if let Err(err) = backup {
+                let _ = self.tx.end(false, |_| Ok(()));
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    fn commit(&self) -> Result<(), DbError> {
+        match self.end(true)? {
+            Some(false) => Err(DbError::Transaction(
+                "rolled back by an inner transaction".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn rollback(&self) -> Result<(), DbError> {
+        self.end(false).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_mfr(name: &str) -> DbManufacturer {
+        DbManufacturer {
+            name: name.to_string(),
+            alias: "".to_string(),
+            url: "".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_add_assigns_uuid() {
+        // Arrange
+        let db = MemoryDatabase::<DbManufacturer>::new();
+
+        // Act
+        db.add(&new_mfr("TestName")).expect("Expected OK");
+
+        // Assert
+        let id = db.get_id("TestName").expect("Expected OK");
+        assert!(Uuid::parse_str(&id).is_ok());
+        assert_eq!(db.get(&id).expect("Expected OK").name, "TestName");
+    }
+
+    #[test]
+    fn test_not_existing() {
+        // Arrange
+        let db = MemoryDatabase::<DbManufacturer>::new();
+
+        // Act & Assert
+        assert!(matches!(db.get_id("TestName"), Err(DbError::NotExists(_))));
+        assert!(matches!(db.get("TestID"), Err(DbError::NotExists(_))));
+        assert!(matches!(db.delete("TestID"), Err(DbError::NotExists(_))));
+    }
+
+    #[test]
+    fn test_update_and_delete() {
+        // Arrange
+        let db = MemoryDatabase::<DbManufacturer>::new();
+        db.add(&new_mfr("TestNameOri")).expect("Expected OK");
+        let id = db.get_id("TestNameOri").expect("Expected OK");
+
+        // Act
+        db.update(&id, &new_mfr("TestNameNew"))
+            .expect("Expected OK");
+
+        // Assert
+        assert!(db.get_id("TestNameOri").is_err());
+        assert_eq!(db.get_id("TestNameNew").expect("Expected OK"), id);
+
+        db.delete(&id).expect("Expected OK");
+        assert!(db.list().expect("Expected OK").is_empty());
+    }
+
+    #[test]
+    fn test_from_database_keeps_id() {
+        // Arrange
+        let src = MemoryDatabase::<DbManufacturer>::new();
+        src.add(&new_mfr("TestName")).expect("Expected OK");
+        let id = src.get_id("TestName").expect("Expected OK");
+
+        // Act
+        let copy = MemoryDatabase::from_database(&src).expect("Expected OK");
+        copy.delete(&id).expect("Expected OK");
+
+        // Assert
+        assert_eq!(src.get_id("TestName").expect("Expected OK"), id);
+        assert!(copy.get_id("TestName").is_err());
+    }
+}
@@ -0,0 +1,423 @@
+use crate::{
+    comm::*,
+    errors::EleboxError,
+    jamm_db::*,
+    ledger::{record, StockReason},
+    location::migrate_locations,
+    supplier::migrate_suppliers,
+    DbError, Manager,
+};
+
+/// Upgrade of the stored items of one type to a schema version. A step may
+/// read and write other item types too, e.g. to add records that the items
+/// of this type need.
+pub struct Migration {
+    /// Schema version after the migration is applied.
+    pub version: u32,
+    pub description: &'static str,
+    pub apply: fn(&Manager) -> Result<(), EleboxError>,
+}
+
+/// Items with a schema history, the migrations must be sorted by version.
+pub trait Migratable: Sized {
+    fn migrations() -> Vec<Migration>;
+    /// Database of the items, where the schema version is stored.
+    fn db(mgr: &Manager) -> &dyn Database<Self>;
+}
+
+/// Version 1, records are written with named fields instead of positional
+/// ones. Positional records are still readable, so rewrite all of them.
+fn rewrite_named<DI: Migratable>(mgr: &Manager) -> Result<(), EleboxError> {
+    let db = DI::db(mgr);
+    for (id, item) in db.list_with_id()? {
+        db.update(&id, &item)?;
+    }
+    Ok(())
+}
+
+fn named_fields<DI: Migratable>() -> Migration {
+    Migration {
+        version: 1,
+        description: "write records with named fields",
+        apply: rewrite_named::<DI>,
+    }
+}
+
+/// Version 2 of the ledger, parts stored before the ledger existed have no
+/// entries. Record the difference to their quantity as an adjustment, so
+/// the ledger balances.
+fn open_ledger(mgr: &Manager) -> Result<(), EleboxError> {
+    let entries = mgr.ledger_db.list()?;
+    for (id, part) in mgr.part_db.list_with_id()? {
+        let balance: i64 = entries
+            .iter()
+            .filter(|entry| entry.part_id == id)
+            .map(|entry| entry.delta as i64)
+            .sum();
+        let delta = part.quantity as i64 - balance;
+        if delta != 0 {
+            record(
+                &*mgr.ledger_db,
+                &id,
+                delta as i32,
+                StockReason::Adjustment,
+                Some("Opening balance"),
+                None,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+impl Migratable for DbPart {
+    fn migrations() -> Vec<Migration> {
+        vec![
+            named_fields::<Self>(),
+            Migration {
+                version: 2,
+                description: "turn free-form locations into location records",
+                apply: migrate_locations,
+            },
+            Migration {
+                version: 3,
+                description: "turn supplier names of offers into supplier records",
+                apply: migrate_suppliers,
+            },
+        ]
+    }
+
+    fn db(mgr: &Manager) -> &dyn Database<Self> {
+        &*mgr.part_db
+    }
+}
+
+impl Migratable for DbCategory {
+    fn migrations() -> Vec<Migration> {
+        vec![named_fields::<Self>()]
+    }
+
+    fn db(mgr: &Manager) -> &dyn Database<Self> {
+        &*mgr.category_db
+    }
+}
+
+impl Migratable for DbPackage {
+    fn migrations() -> Vec<Migration> {
+        vec![named_fields::<Self>()]
+    }
+
+    fn db(mgr: &Manager) -> &dyn Database<Self> {
+        &*mgr.package_db
+    }
+}
+
+impl Migratable for DbManufacturer {
+    fn migrations() -> Vec<Migration> {
+        vec![named_fields::<Self>()]
+    }
+
+    fn db(mgr: &Manager) -> &dyn Database<Self> {
+        &*mgr.mfr_db
+    }
+}
+
+impl Migratable for DbLedgerEntry {
+    fn migrations() -> Vec<Migration> {
+        vec![
+            named_fields::<Self>(),
+            Migration {
+                version: 2,
+                description: "record the opening balance of parts",
+                apply: open_ledger,
+            },
+        ]
+    }
+
+    fn db(mgr: &Manager) -> &dyn Database<Self> {
+        &*mgr.ledger_db
+    }
+}
+
+impl Migratable for DbLocation {
+    fn migrations() -> Vec<Migration> {
+        vec![named_fields::<Self>()]
+    }
+
+    fn db(mgr: &Manager) -> &dyn Database<Self> {
+        &*mgr.location_db
+    }
+}
+
+impl Migratable for DbSupplier {
+    fn migrations() -> Vec<Migration> {
+        vec![named_fields::<Self>()]
+    }
+
+    fn db(mgr: &Manager) -> &dyn Database<Self> {
+        &*mgr.supplier_db
+    }
+}
+
+impl Migratable for DbOrder {
+    fn migrations() -> Vec<Migration> {
+        vec![named_fields::<Self>()]
+    }
+
+    fn db(mgr: &Manager) -> &dyn Database<Self> {
+        &*mgr.order_db
+    }
+}
+
+impl Migratable for DbFieldDef {
+    fn migrations() -> Vec<Migration> {
+        vec![named_fields::<Self>()]
+    }
+
+    fn db(mgr: &Manager) -> &dyn Database<Self> {
+        &*mgr.field_db
+    }
+}
+
+impl Migratable for DbProject {
+    fn migrations() -> Vec<Migration> {
+        vec![named_fields::<Self>()]
+    }
+
+    fn db(mgr: &Manager) -> &dyn Database<Self> {
+        &*mgr.project_db
+    }
+}
+
+/// Apply the migrations newer than the stored schema version, each one in
+/// a transaction of the manager together with the new version. Returns the
+/// number of applied migrations.
+pub fn migrate<DI>(mgr: &Manager) -> Result<usize, EleboxError>
+where
+    DI: Migratable + DatabaseItem,
+{
+    let db = DI::db(mgr);
+    let migrations = DI::migrations();
+    let latest = migrations.last().map_or(0, |m| m.version);
+
+    let version = db.get_version()?;
+    if version > latest {
+        return Err(DbError::UnsupportedVersion(DI::get_bucket(), version).into());
+    }
+
+    let mut count = 0;
+    for migration in migrations.iter().filter(|m| m.version > version) {
+        mgr.transaction(|mgr| {
+            (migration.apply)(mgr)?;
+            DI::db(mgr).set_version(migration.version)?;
+            Ok(())
+        })?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Check that the stored schema version is the latest one, without
+/// migrating.
+pub(crate) fn check_version<DI>(mgr: &Manager) -> Result<(), EleboxError>
+where
+    DI: Migratable + DatabaseItem,
+{
+    let latest = DI::migrations().last().map_or(0, |m| m.version);
+    let version = DI::db(mgr).get_version()?;
+    if version > latest {
+        return Err(DbError::UnsupportedVersion(DI::get_bucket(), version).into());
+    }
+    if version < latest {
+        return Err(DbError::OutdatedVersion(DI::get_bucket(), version).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Handler;
+    use jammdb::DB;
+    use serde::Serialize;
+    use std::{env, fs, path::PathBuf};
+    use uuid::Uuid;
+
+    /// `DbPart` as written by releases before custom fields were added.
+    #[derive(Serialize)]
+    struct OldDbPart {
+        name: String,
+        quantity: u16,
+        category_id: Id,
+        package_id: Id,
+        package_detail: String,
+        mfr_id: Id,
+        alias: String,
+        description: String,
+        location: String,
+        mfr_no: String,
+        datasheet_link: String,
+        product_link: String,
+        image_link: String,
+    }
+
+    /// A database file of an older release, with one part of 10 pieces.
+    fn old_database() -> PathBuf {
+        let path = env::temp_dir().join(format!("elebox_test_{}.db", Uuid::new_v4()));
+        let db = DB::open(&path).unwrap();
+        let tx = db.tx(true).unwrap();
+        let bkt = tx.create_bucket(PARTS_BUCKET).unwrap();
+        let part = OldDbPart {
+            name: "TestName".to_string(),
+            quantity: 10,
+            category_id: "".to_string(),
+            package_id: "".to_string(),
+            package_detail: "".to_string(),
+            mfr_id: "".to_string(),
+            alias: "".to_string(),
+            description: "".to_string(),
+            location: "Drawer A3".to_string(),
+            mfr_no: "".to_string(),
+            datasheet_link: "".to_string(),
+            product_link: "".to_string(),
+            image_link: "".to_string(),
+        };
+        bkt.put("TestID", rmp_serde::to_vec(&part).unwrap())
+            .unwrap();
+        tx.commit().unwrap();
+        path
+    }
+
+    #[test]
+    fn test_migrate_positional_records() {
+        // Arrange
+        let path = old_database();
+        let mgr = Manager::open(&path.to_string_lossy()).expect("Expected OK");
+
+        // Act
+        let result = mgr.init();
+        let part = mgr.part().get("TestName");
+        drop(mgr);
+        let db = JammDatabase::new(&path.to_string_lossy());
+        let version = Database::<DbPart>::get_version(&db);
+        drop(db);
+        let value = DB::open(&path)
+            .and_then(|jamm| {
+                let tx = jamm.tx(false)?;
+                let bkt = tx.get_bucket(PARTS_BUCKET)?;
+                Ok(bkt.get_kv("TestID").map(|kv| kv.value().to_vec()))
+            })
+            .unwrap();
+        let _ = fs::remove_file(&path);
+
+        // Assert
+        assert!(result.is_ok());
+        let part = part.expect("Expected OK");
+        assert_eq!(part.quantity, 10);
+        assert_eq!(part.location.as_deref(), Some("Drawer A3"));
+        assert_eq!(version.expect("Expected OK"), 3);
+        // A map of the 16 named fields instead of an array
+        assert_eq!(value.expect("Expected OK")[0], 0xde);
+    }
+
+    #[test]
+    fn test_migrate_opening_ledger() {
+        // Arrange
+        let path = old_database();
+        let mgr = Manager::open(&path.to_string_lossy()).expect("Expected OK");
+
+        // Act
+        mgr.init().expect("Expected OK");
+        let result = mgr.ledger().verify("TestName");
+        let entries = mgr.ledger().list_part("TestName");
+        mgr.init().expect("Expected OK");
+        let again = mgr.ledger().list_part("TestName");
+        drop(mgr);
+        let _ = fs::remove_file(&path);
+
+        // Assert
+        assert!(result.is_ok());
+        let entries = entries.expect("Expected OK");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].delta, 10);
+        assert_eq!(entries[0].reason, StockReason::Adjustment);
+        assert_eq!(again.expect("Expected OK").len(), 1);
+    }
+
+    #[test]
+    fn test_copy_unmigrated_database() {
+        // Arrange, version 1 of the parts with the old free-form location
+        let path = old_database();
+        let src = Manager::open(&path.to_string_lossy()).expect("Expected OK");
+        src.part_db.set_version(1).expect("Expected OK");
+        let dst = Manager::in_memory();
+
+        // Act
+        let result = src.copy_into(&dst);
+        let snapshot = src.snapshot();
+        drop(src);
+        let _ = fs::remove_file(&path);
+
+        // Assert
+        assert!(result.is_ok());
+        let part = dst.part().get("TestName").expect("Expected OK");
+        assert_eq!(part.location.as_deref(), Some("Drawer A3"));
+        assert!(dst.ledger().verify("TestName").is_ok());
+        let snapshot = snapshot.expect("Expected OK");
+        let part = snapshot.part().get("TestName").expect("Expected OK");
+        assert_eq!(part.location.as_deref(), Some("Drawer A3"));
+    }
+
+    #[test]
+    fn test_migrate_newer_version() {
+        // Arrange
+        let mgr = Manager::in_memory();
+        mgr.mfr_db.set_version(99).expect("Expected OK");
+
+        // Act
+        let result = migrate::<DbManufacturer>(&mgr);
+
+        // Assert
+        assert!(matches!(
+            result,
+            Err(EleboxError::DatabaseError(DbError::UnsupportedVersion(
+                _,
+                99
+            )))
+        ));
+    }
+
+    #[test]
+    fn test_check_outdated_version() {
+        // Arrange
+        let mgr = Manager::in_memory();
+        mgr.init().expect("Expected OK");
+        mgr.part_db.set_version(1).expect("Expected OK");
+
+        // Act
+        let outdated = mgr.check();
+        mgr.init().expect("Expected OK");
+        let upgraded = mgr.check();
+
+        // Assert
+        assert!(matches!(
+            outdated,
+            Err(EleboxError::DatabaseError(DbError::OutdatedVersion(_, 1)))
+        ));
+        assert!(upgraded.is_ok());
+    }
+
+    #[test]
+    fn test_migrate_once() {
+        // Arrange
+        let mgr = Manager::in_memory();
+
+        // Act
+        let first = migrate::<DbManufacturer>(&mgr).expect("Expected OK");
+        let second = migrate::<DbManufacturer>(&mgr).expect("Expected OK");
+
+        // Assert
+        assert_eq!(first, 1);
+        assert_eq!(second, 0);
+        assert_eq!(mgr.mfr_db.get_version().expect("Expected OK"), 1);
+    }
+}
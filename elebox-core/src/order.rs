@@ -0,0 +1,570 @@
+use crate::{
+    comm::*, errors::*, jamm_db::*, ledger::StockReason, part::DELETED_ITEM,
+    supplier::find_supplier_id, PartHandler,
+};
+use core::fmt;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt::Debug, path::PathBuf};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub enum OrderStatus {
+    /// Being written, nothing is on order yet.
+    #[default]
+    Draft,
+    /// Placed at the supplier, the parts not received yet are on order.
+    Sent,
+    /// All lines are received.
+    Received,
+}
+
+impl OrderStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            OrderStatus::Draft => "draft",
+            OrderStatus::Sent => "sent",
+            OrderStatus::Received => "received",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "sent" => OrderStatus::Sent,
+            "received" => OrderStatus::Received,
+            _ => OrderStatus::Draft,
+        }
+    }
+}
+
+impl fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            OrderStatus::Draft => "Draft",
+            OrderStatus::Sent => "Sent",
+            OrderStatus::Received => "Received",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl DbOrder {
+    /// Whether parts of the order may still be received.
+    pub(crate) fn is_open(&self) -> bool {
+        OrderStatus::parse(&self.status) != OrderStatus::Received
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct OrderLine {
+    pub part: String,
+    pub quantity: u16,
+    /// Quantity received so far.
+    #[serde(default)]
+    pub received: u16,
+}
+
+impl OrderLine {
+    pub fn new(part: &str, quantity: u16) -> Self {
+        Self {
+            part: part.to_string(),
+            quantity,
+            received: 0,
+        }
+    }
+
+    /// Quantity not received yet.
+    pub fn outstanding(&self) -> u16 {
+        self.quantity.saturating_sub(self.received)
+    }
+}
+
+/// An order of parts at a supplier, the name is the order number.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PurchaseOrder {
+    pub name: String,
+    pub supplier: String,
+    #[serde(default)]
+    pub status: OrderStatus,
+    pub note: Option<String>,
+    pub lines: Vec<OrderLine>,
+}
+
+impl PurchaseOrder {
+    pub fn new(name: &str, supplier: &str, lines: Vec<OrderLine>) -> Self {
+        Self {
+            name: name.to_string(),
+            supplier: supplier.to_string(),
+            status: OrderStatus::Draft,
+            note: None,
+            lines,
+        }
+    }
+}
+
+pub struct OrderHandler<'a> {
+    pub(crate) db: &'a dyn Database<DbOrder>,
+    pub(crate) sup_db: &'a dyn Database<DbSupplier>,
+    pub(crate) part: PartHandler<'a>,
+}
+
+impl OrderHandler<'_> {
+    fn to_db_item(&self, item: &PurchaseOrder) -> Result<DbOrder, EleboxError> {
+        let supplier_id = match find_supplier_id(self.sup_db, &item.supplier) {
+            Ok(id) => id,
+            Err(DbError::NotExists(_)) => {
+                return Err(EleboxError::NotExists(
+                    String::from(ITEM_SUPPLIER),
+                    item.supplier.clone(),
+                ))
+            }
+            Err(err) => return Err(EleboxError::DatabaseError(err)),
+        };
+
+        let mut lines = vec![];
+        for line in &item.lines {
+            let part_id = match self.part.db.get_id(&line.part) {
+                Ok(id) => id,
+                Err(DbError::NotExists(_)) => {
+                    return Err(EleboxError::NotExists(
+                        String::from(ITEM_PART),
+                        line.part.clone(),
+                    ))
+                }
+                Err(err) => return Err(EleboxError::DatabaseError(err)),
+            };
+
+            // A part is on one line only, repeated lines are merged
+            match lines
+                .iter_mut()
+                .find(|l: &&mut DbOrderLine| l.part_id == part_id)
+            {
+                Some(db_line) => {
+                    db_line.quantity = db_line.quantity.saturating_add(line.quantity);
+                    db_line.received = db_line.received.saturating_add(line.received);
+                }
+                None => lines.push(DbOrderLine {
+                    part_id,
+                    quantity: line.quantity,
+                    received: line.received,
+                }),
+            }
+        }
+
+        Ok(DbOrder {
+            name: item.name.to_string(),
+            supplier_id,
+            status: item.status.as_str().to_string(),
+            note: item.note.as_deref().unwrap_or("").to_string(),
+            lines,
+        })
+    }
+
+    fn to_item(&self, db_item: DbOrder) -> Result<PurchaseOrder, EleboxError> {
+        let supplier = match self.sup_db.get(&db_item.supplier_id) {
+            Ok(item) => item.name,
+            Err(DbError::NotExists(_)) => String::from(DELETED_ITEM),
+            Err(err) => return Err(EleboxError::DatabaseError(err)),
+        };
+
+        let mut lines = vec![];
+        for db_line in db_item.lines {
+            let part = match self.part.db.get(&db_line.part_id) {
+                Ok(item) => item.name,
+                Err(DbError::NotExists(_)) => String::from(DELETED_ITEM),
+                Err(err) => return Err(EleboxError::DatabaseError(err)),
+            };
+            lines.push(OrderLine {
+                part,
+                quantity: db_line.quantity,
+                received: db_line.received,
+            });
+        }
+
+        Ok(PurchaseOrder {
+            name: db_item.name,
+            supplier,
+            status: OrderStatus::parse(&db_item.status),
+            note: Some(db_item.note).filter(|s| !s.is_empty()),
+            lines,
+        })
+    }
+
+    /// The order with its ID, refused unless it has the `expected` status.
+    fn get_with_status(
+        &self,
+        name: &str,
+        expected: OrderStatus,
+    ) -> Result<(Id, DbOrder), EleboxError> {
+        let id = self.db.get_id(name)?;
+        let db_item = self.db.get(&id)?;
+        let status = OrderStatus::parse(&db_item.status);
+        if status != expected {
+            return Err(EleboxError::InvalidOrderStatus(
+                name.to_string(),
+                status.as_str().to_string(),
+            ));
+        }
+        Ok((id, db_item))
+    }
+
+    /// Mark a draft order as placed at the supplier, its parts are on order
+    /// from then on.
+    pub fn send(&self, name: &str) -> Result<(), EleboxError> {
+        let (id, mut db_item) = self.get_with_status(name, OrderStatus::Draft)?;
+        db_item.status = OrderStatus::Sent.as_str().to_string();
+        self.db.update(&id, &db_item)?;
+        Ok(())
+    }
+
+    /// Receive `quantity` of a part of a sent order, see `receive_all()`.
+    pub fn receive(
+        &self,
+        name: &str,
+        part: &str,
+        quantity: u16,
+        location: Option<&str>,
+    ) -> Result<(), EleboxError> {
+        let part_id = self.part.db.get_id(part)?;
+        let (id, mut db_item) = self.get_with_status(name, OrderStatus::Sent)?;
+
+        let line = db_item
+            .lines
+            .iter_mut()
+            .find(|line| line.part_id == part_id);
+        let outstanding = line
+            .as_ref()
+            .map_or(0, |line| line.quantity.saturating_sub(line.received));
+        let Some(line) = line.filter(|_| quantity <= outstanding) else {
+            return Err(EleboxError::OverReceipt(
+                name.to_string(),
+                part.to_string(),
+                outstanding,
+            ));
+        };
+
+        line.received += quantity;
+        self.restock(
+            name,
+            &id,
+            db_item,
+            vec![(part.to_string(), quantity)],
+            location,
+        )
+    }
+
+    /// Receive everything still outstanding on a sent order. Lines of
+    /// deleted parts are closed without restocking.
+    pub fn receive_all(&self, name: &str, location: Option<&str>) -> Result<(), EleboxError> {
+        let (id, mut db_item) = self.get_with_status(name, OrderStatus::Sent)?;
+
+        let mut restocks = vec![];
+        for line in db_item.lines.iter_mut() {
+            let quantity = line.quantity.saturating_sub(line.received);
+            line.received = line.quantity;
+            match self.part.db.get(&line.part_id) {
+                Ok(part) if quantity > 0 => restocks.push((part.name, quantity)),
+                Ok(_) | Err(DbError::NotExists(_)) => continue,
+                Err(err) => return Err(EleboxError::DatabaseError(err)),
+            }
+        }
+        self.restock(name, &id, db_item, restocks, location)
+    }
+
+    /// Restock the received parts and record them in the ledger under the
+    /// order, together with the order in one transaction. The order is
+    /// received once nothing is outstanding.
+    fn restock(
+        &self,
+        name: &str,
+        id: &str,
+        mut db_item: DbOrder,
+        restocks: Vec<(String, u16)>,
+        location: Option<&str>,
+    ) -> Result<(), EleboxError> {
+        if db_item
+            .lines
+            .iter()
+            .all(|line| line.received >= line.quantity)
+        {
+            db_item.status = OrderStatus::Received.as_str().to_string();
+        }
+
+        let note = format!("Order {name}");
+        in_transaction(self.db, self.part.db, || {
+            in_transaction(self.part.db, self.part.ledger_db, || {
+                for (part, quantity) in &restocks {
+                    // Stock changes are signed 16-bit, larger receipts take more than one
+                    let mut left = *quantity;
+                    while left > 0 {
+                        let delta = left.min(i16::MAX as u16);
+                        self.part.move_stock(
+                            part,
+                            delta as i16,
+                            location,
+                            StockReason::Restock,
+                            Some(&note),
+                            None,
+                        )?;
+                        left -= delta;
+                    }
+                }
+                self.db.update(id, &db_item)?;
+                Ok(())
+            })
+        })
+    }
+
+    /// Sent orders that are not fully received yet.
+    pub fn list_open(&self) -> Result<Vec<PurchaseOrder>, EleboxError> {
+        Ok(self
+            .list()?
+            .into_iter()
+            .filter(|order| order.status == OrderStatus::Sent)
+            .collect())
+    }
+
+    /// Outstanding quantity of each part on the open orders.
+    pub fn on_order(&self) -> Result<BTreeMap<String, u32>, EleboxError> {
+        let mut quantities = BTreeMap::new();
+        for order in self.list_open()? {
+            for line in order.lines {
+                *quantities.entry(line.part.clone()).or_insert(0) += line.outstanding() as u32;
+            }
+        }
+        Ok(quantities)
+    }
+}
+
+impl Handler<PurchaseOrder> for OrderHandler<'_> {
+    fn delete(&self, name: &str) -> Result<(), EleboxError> {
+        let id = self.db.get_id(name)?;
+        self.db.delete(&id)?;
+        Ok(())
+    }
+
+    fn add(&self, item: &PurchaseOrder) -> Result<(), EleboxError> {
+        if self.db.get_id(&item.name).is_ok() {
+            return Err(EleboxError::AlreadyExists(
+                String::from(ITEM_ORDER),
+                item.name.clone(),
+            ));
+        }
+
+        let db_item = self.to_db_item(item)?;
+        self.db.add(&db_item)?;
+        Ok(())
+    }
+
+    fn update(&self, ori_name: &str, new_item: &PurchaseOrder) -> Result<(), EleboxError> {
+        let ori_id = self.db.get_id(ori_name)?;
+
+        if ori_name != new_item.name && self.db.get_id(&new_item.name).is_ok() {
+            return Err(EleboxError::AlreadyExists(
+                String::from(ITEM_ORDER),
+                new_item.name.clone(),
+            ));
+        }
+
+        let db_item = self.to_db_item(new_item)?;
+        self.db.update(&ori_id, &db_item)?;
+        Ok(())
+    }
+
+    fn get(&self, name: &str) -> Result<PurchaseOrder, EleboxError> {
+        let id = self.db.get_id(name)?;
+        let db_item = self.db.get(&id)?;
+        self.to_item(db_item)
+    }
+
+    fn list(&self) -> Result<Vec<PurchaseOrder>, EleboxError> {
+        let db_items = self.db.list()?;
+        db_items
+            .into_iter()
+            .map(|db_item| self.to_item(db_item))
+            .collect()
+    }
+}
+
+impl Transferable for OrderHandler<'_> {
+    fn export(&self, filename: &PathBuf) -> Result<(), EleboxError> {
+        write_items(filename, self.list()?)
+    }
+
+    fn import(&self, filename: &PathBuf) -> Result<(), EleboxError> {
+        let items: Vec<PurchaseOrder> = read_items(filename)?;
+        for item in items {
+            if let Err(e) = self.add(&item) {
+                match e {
+                    EleboxError::AlreadyExists(_, _) => continue,
+                    others => return Err(others),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Category, Manager, Part, Supplier};
+    use uuid::Uuid;
+
+    fn new_manager() -> Manager {
+        let mgr = Manager::in_memory();
+        mgr.category()
+            .add(&Category::new("Passives", None, None))
+            .unwrap();
+        mgr.supplier()
+            .add(&Supplier::new("Mouser", None, None))
+            .unwrap();
+        mgr.part().add(&Part::new("100nF", "Passives", 5)).unwrap();
+        mgr.part().add(&Part::new("10k", "Passives", 0)).unwrap();
+        mgr.order()
+            .add(&PurchaseOrder::new(
+                "PO-1",
+                "Mouser",
+                vec![OrderLine::new("100nF", 100), OrderLine::new("10k", 50)],
+            ))
+            .unwrap();
+        mgr
+    }
+
+    #[test]
+    fn test_receive_partially() {
+        // Arrange
+        let mgr = new_manager();
+        mgr.order().send("PO-1").expect("Expected OK");
+
+        // Act
+        mgr.order()
+            .receive("PO-1", "100nF", 60, None)
+            .expect("Expected OK");
+
+        // Assert
+        let order = mgr.order().get("PO-1").unwrap();
+        assert_eq!(order.status, OrderStatus::Sent);
+        assert_eq!(order.lines[0].received, 60);
+        assert_eq!(mgr.part().get("100nF").unwrap().quantity, 65);
+        let entry = mgr.ledger().list_part("100nF").unwrap().pop().unwrap();
+        assert_eq!(entry.delta, 60);
+        assert_eq!(entry.reason, StockReason::Restock);
+        assert_eq!(entry.note.as_deref(), Some("Order PO-1"));
+        let on_order = mgr.order().on_order().unwrap();
+        assert_eq!(on_order.get("100nF"), Some(&40));
+        assert_eq!(on_order.get("10k"), Some(&50));
+    }
+
+    #[test]
+    fn test_receive_all() {
+        // Arrange
+        let mgr = new_manager();
+        mgr.order().send("PO-1").expect("Expected OK");
+        mgr.order()
+            .receive("PO-1", "10k", 20, None)
+            .expect("Expected OK");
+
+        // Act
+        let result = mgr.order().receive_all("PO-1", None);
+
+        // Assert
+        assert!(result.is_ok());
+        let order = mgr.order().get("PO-1").unwrap();
+        assert_eq!(order.status, OrderStatus::Received);
+        assert_eq!(mgr.part().get("100nF").unwrap().quantity, 105);
+        assert_eq!(mgr.part().get("10k").unwrap().quantity, 50);
+        assert!(mgr.order().list_open().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_delete_ordered_part() {
+        // Arrange
+        let mgr = new_manager();
+
+        // Act
+        let open_result = mgr.part().delete("10k");
+        mgr.order().send("PO-1").expect("Expected OK");
+        mgr.order().receive_all("PO-1", None).expect("Expected OK");
+        let received_result = mgr.part().delete("10k");
+
+        // Assert
+        assert!(matches!(
+            open_result,
+            Err(EleboxError::StillReferenced(_, _, _))
+        ));
+        assert!(received_result.is_ok());
+        let order = mgr.order().get("PO-1").unwrap();
+        assert_eq!(
+            order.lines,
+            vec![OrderLine {
+                part: "100nF".to_string(),
+                quantity: 100,
+                received: 100,
+            }]
+        );
+        let deleted: i32 = mgr
+            .ledger()
+            .list()
+            .unwrap()
+            .iter()
+            .filter(|entry| entry.part == "__DELETE__")
+            .map(|entry| entry.delta)
+            .sum();
+        assert_eq!(deleted, 0);
+    }
+
+    #[test]
+    fn test_receive_draft_or_too_many() {
+        // Arrange
+        let mgr = new_manager();
+
+        // Act
+        let draft = mgr.order().receive("PO-1", "10k", 10, None);
+        mgr.order().send("PO-1").expect("Expected OK");
+        let resend = mgr.order().send("PO-1");
+        let too_many = mgr.order().receive("PO-1", "10k", 51, None);
+
+        // Assert
+        assert!(matches!(draft, Err(EleboxError::InvalidOrderStatus(_, _))));
+        assert!(matches!(resend, Err(EleboxError::InvalidOrderStatus(_, _))));
+        assert!(matches!(too_many, Err(EleboxError::OverReceipt(_, _, 50))));
+        assert_eq!(mgr.part().get("10k").unwrap().quantity, 0);
+    }
+
+    #[test]
+    fn test_add_unknown_supplier() {
+        // Arrange
+        let mgr = new_manager();
+        let order = PurchaseOrder::new("PO-2", "DigiKey", vec![]);
+
+        // Act
+        let result = mgr.order().add(&order);
+
+        // Assert
+        assert!(matches!(result, Err(EleboxError::NotExists(_, _))));
+    }
+
+    #[test]
+    fn test_export_import() {
+        // Arrange
+        let mgr = new_manager();
+        let dir = std::env::temp_dir().join(format!("elebox_test_{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("Expected OK");
+        let filename = dir.join("orders.yaml");
+
+        // Act
+        mgr.order().export(&filename).expect("Expected OK");
+        mgr.order().delete("PO-1").expect("Expected OK");
+        mgr.order().import(&filename).expect("Expected OK");
+        let wrong_ext = mgr.order().export(&dir.join("orders.xml"));
+        let missing = mgr.order().import(&dir.join("missing.yaml"));
+
+        // Assert
+        let order = mgr.order().get("PO-1").expect("Expected OK");
+        assert_eq!(order.supplier, "Mouser");
+        assert_eq!(order.lines.len(), 2);
+        assert!(matches!(wrong_ext, Err(EleboxError::InvalidFile(_, _))));
+        assert!(matches!(missing, Err(EleboxError::InvalidFile(_, _))));
+        std::fs::remove_dir_all(&dir).expect("Expected OK");
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{comm::*, errors::EleboxError, jamm_db::*, json::*, yaml::*};
+use crate::{comm::*, errors::EleboxError, jamm_db::*, part::delete_referenced, PartHandler};
 use core::fmt;
 use serde::{Deserialize, Serialize};
 use std::{fmt::Debug, path::PathBuf};
@@ -40,6 +40,7 @@ impl Package {
 
 pub struct PackageHandler<'a> {
     pub(crate) db: &'a dyn Database<DbPackage>,
+    pub(crate) part: PartHandler<'a>,
 }
 
 impl PackageHandler<'_> {
@@ -75,11 +76,19 @@ impl PackageHandler<'_> {
     }
 }
 
+impl PackageHandler<'_> {
+    /// Delete the package, the parts that still use it are handled by `policy`.
+    pub fn delete_with(&self, name: &str, policy: &DeletePolicy) -> Result<(), EleboxError> {
+        delete_referenced(self.db, &self.part, ITEM_PKG, name, policy, |part| {
+            &mut part.package_id
+        })
+    }
+}
+
 impl<'a> Handler<Package> for PackageHandler<'_> {
+    /// Delete the package, refused if any part still uses it.
     fn delete(&self, name: &str) -> Result<(), EleboxError> {
-        let id = self.db.get_id(name)?;
-        let _ = self.db.delete(&id)?;
-        Ok(())
+        self.delete_with(name, &DeletePolicy::Restrict)
     }
 
     fn add(&self, item: &Package) -> Result<(), EleboxError> {
@@ -128,38 +137,16 @@ impl<'a> Handler<Package> for PackageHandler<'_> {
 
 impl Transferable for PackageHandler<'_> {
     fn export(&self, filename: &PathBuf) -> Result<(), EleboxError> {
-        let items = self.list()?;
-
-        if YamlFile::check_extension(filename) {
-            let _ = YamlFile::write(filename, items);
-        } else if JsonFile::check_extension(filename) {
-            let _ = JsonFile::write(filename, items);
-        } else {
-            todo!()
-        }
-
-        Ok(())
+        write_items(filename, self.list()?)
     }
 
     fn import(&self, filename: &PathBuf) -> Result<(), EleboxError> {
-        let res_items: Result<Vec<Package>, ()> = if YamlFile::check_extension(filename) {
-            YamlFile::read(filename)
-        } else if JsonFile::check_extension(filename) {
-            JsonFile::read(filename)
-        } else {
-            todo!()
-        };
-
-        if res_items.is_err() {
-            todo!()
-        }
-
-        let items: Vec<Package> = res_items.unwrap();
+        let items: Vec<Package> = read_items(filename)?;
         for item in items {
             if let Err(e) = self.add(&item) {
                 match e {
                     EleboxError::AlreadyExists(_, _) => continue,
-                    others => panic!("{}", others.to_string()),
+                    others => return Err(others),
                 }
             }
         }
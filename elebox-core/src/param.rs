@@ -0,0 +1,535 @@
+use crate::{errors::*, CustomField, CustomFieldType, FieldFilter, ParamTemplate};
+use core::fmt;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// SI prefixes by their power of ten, `u` is accepted for micro and `K` for
+/// kilo as in `4K7`.
+const PREFIXES: [(char, i32); 12] = [
+    ('f', -15),
+    ('p', -12),
+    ('n', -9),
+    ('u', -6),
+    ('µ', -6),
+    ('μ', -6),
+    ('m', -3),
+    ('k', 3),
+    ('K', 3),
+    ('M', 6),
+    ('G', 9),
+    ('T', 12),
+];
+
+/// Units written without a prefix, e.g. `0.5%` instead of `500m%`.
+const PLAIN_UNITS: [&str; 2] = ["%", "°C"];
+
+fn prefix_exponent(c: char) -> Option<i32> {
+    PREFIXES.iter().find(|(p, _)| *p == c).map(|(_, exp)| *exp)
+}
+
+pub(crate) fn pow10(exp: i32) -> Decimal {
+    if exp >= 0 {
+        Decimal::from(10i64.pow(exp as u32))
+    } else {
+        Decimal::new(1, exp.unsigned_abs())
+    }
+}
+
+/// The same unit is written in many ways, e.g. `R`, `ohm` and `Ω`.
+fn normalize_unit(unit: &str) -> String {
+    match unit {
+        "R" | "r" | "ohm" | "ohms" | "Ohm" | "Ohms" | "Ω" | "\u{2126}" => "Ω".to_string(),
+        "hz" | "HZ" => "Hz".to_string(),
+        "v" => "V".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// A number with an optional SI prefix and unit, e.g. `100nF`, `4k7` or
+/// `16V`, normalized to the base unit.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SiValue {
+    pub value: Decimal,
+    /// Empty for plain numbers.
+    pub unit: String,
+}
+
+impl SiValue {
+    /// Parse a value like `0.1uF`, `4k7`, `4R7`, `100 nF` or `±5%`, `None` if
+    /// it is not a number.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text: String = text
+            .trim()
+            .trim_start_matches('±')
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.strip_prefix('+').unwrap_or(&text)),
+        };
+
+        let number_end = text
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(text.len());
+        let (mut number, rest) = (text[..number_end].to_string(), &text[number_end..]);
+        if !number.chars().any(|c| c.is_ascii_digit()) {
+            return None;
+        }
+
+        let mut chars = rest.chars();
+        let first = chars.next();
+        let after = chars.as_str();
+        let fraction_end = after
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(after.len());
+
+        let (exponent, unit) = match first {
+            // The prefix as the decimal point, e.g. `4k7` or `4R7`
+            Some(c)
+                if fraction_end > 0
+                    && !number.contains('.')
+                    && (c == 'R' || c == 'r' || prefix_exponent(c).is_some()) =>
+            {
+                number = format!("{}.{}", number, &after[..fraction_end]);
+                let unit = &after[fraction_end..];
+                match prefix_exponent(c) {
+                    Some(exp) => (exp, unit),
+                    None if unit.is_empty() => (0, "Ω"),
+                    None => (0, unit),
+                }
+            }
+            Some(c) => match prefix_exponent(c) {
+                Some(exp) => (exp, after),
+                None => (0, rest),
+            },
+            None => (0, ""),
+        };
+
+        let is_unit = |c: char| c.is_alphabetic() || c == '%' || c == '°' || c == '/';
+        if !unit.chars().all(is_unit) {
+            return None;
+        }
+
+        let value = Decimal::from_str(&number)
+            .ok()?
+            .checked_mul(pow10(exponent))?;
+        Some(Self {
+            value: if negative { -value } else { value },
+            unit: normalize_unit(unit),
+        })
+    }
+
+    /// Returns `true` if both are in the same unit, a value without a unit
+    /// matches any.
+    fn same_unit(&self, other: &SiValue) -> bool {
+        self.unit.is_empty() || other.unit.is_empty() || self.unit == other.unit
+    }
+}
+
+impl fmt::Display for SiValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.value.normalize();
+        let exponent = if value.is_zero() || PLAIN_UNITS.contains(&self.unit.as_str()) {
+            0
+        } else {
+            [12, 9, 6, 3, 0, -3, -6, -9, -12]
+                .into_iter()
+                .find(|exp| value.abs() >= pow10(*exp))
+                .unwrap_or(-15)
+        };
+
+        let prefix = match exponent {
+            0 => String::new(),
+            // `u` only for parsing, `µ` for display
+            -6 => "µ".to_string(),
+            exp => PREFIXES
+                .iter()
+                .find(|(p, e)| *e == exp && *p != 'K')
+                .map_or(String::new(), |(p, _)| p.to_string()),
+        };
+        let mantissa = (value / pow10(exponent)).normalize();
+        write!(f, "{}{}{}", mantissa, prefix, self.unit)
+    }
+}
+
+/// Range of a parameter, both ends are included.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ParamRange {
+    /// Parameter name, matched ignoring case.
+    pub name: String,
+    pub min: Option<SiValue>,
+    pub max: Option<SiValue>,
+}
+
+impl ParamRange {
+    /// Parse a condition like `Capacitance>=10uF`, `Voltage<=50V`,
+    /// `Resistance=4k7` or `Capacitance=1uF..10uF`.
+    pub fn parse(text: &str) -> Result<Self, EleboxError> {
+        let invalid = || EleboxError::InvalidParameter(text.to_string(), String::new());
+        let (name, op, value) = ["<=", ">=", "="]
+            .into_iter()
+            .find_map(|op| {
+                let (name, value) = text.split_once(op)?;
+                Some((name.trim(), op, value.trim()))
+            })
+            .ok_or_else(invalid)?;
+        if name.is_empty() {
+            return Err(invalid());
+        }
+
+        let parse = |value: &str| {
+            SiValue::parse(value)
+                .ok_or_else(|| EleboxError::InvalidParameter(name.to_string(), value.to_string()))
+        };
+        let (min, max) = match (op, value.split_once("..")) {
+            ("=", Some((min, max))) => (Some(parse(min)?), Some(parse(max)?)),
+            ("=", None) => (Some(parse(value)?), Some(parse(value)?)),
+            (">=", _) => (Some(parse(value)?), None),
+            _ => (None, Some(parse(value)?)),
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            min,
+            max,
+        })
+    }
+
+    /// Returns `true` if a parameter of the fields is in the range.
+    pub(crate) fn matches(&self, fields: &[CustomField]) -> bool {
+        fields
+            .iter()
+            .filter(|f| f.field_type == CustomFieldType::Parameter)
+            .filter(|f| f.name.trim().eq_ignore_ascii_case(self.name.trim()))
+            .filter_map(|f| SiValue::parse(&f.value))
+            .any(|value| {
+                let above = self
+                    .min
+                    .as_ref()
+                    .is_none_or(|min| min.same_unit(&value) && value.value >= min.value);
+                let below = self
+                    .max
+                    .as_ref()
+                    .is_none_or(|max| max.same_unit(&value) && value.value <= max.value);
+                above && below
+            })
+    }
+}
+
+/// Conditions on parts, all of them must match.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct PartQuery {
+    /// The category or any of its subcategories.
+    pub category: Option<String>,
+    /// Package name or alias, matched ignoring case.
+    pub package: Option<String>,
+    pub parameters: Vec<ParamRange>,
+    /// Conditions on custom fields of any type.
+    #[serde(default)]
+    pub fields: Vec<FieldFilter>,
+}
+
+/// Give the fields the types of the template, and the unit of the template
+/// to parameters written without one, e.g. `4k7` as `4.7kΩ`. Empty fields
+/// of the template are left out.
+pub(crate) fn apply_template(
+    template: &[ParamTemplate],
+    fields: &mut Vec<CustomField>,
+) -> Result<(), EleboxError> {
+    let find = |name: &str| {
+        template
+            .iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name.trim()))
+    };
+    fields.retain(|f| !f.value.trim().is_empty() || find(&f.name).is_none());
+
+    for field in fields.iter_mut() {
+        let Some(param) = find(&field.name) else {
+            continue;
+        };
+        field.field_type = param.field_type.clone();
+
+        let unit = normalize_unit(param.unit.trim());
+        if param.field_type != CustomFieldType::Parameter || unit.is_empty() {
+            continue;
+        }
+
+        let invalid = || EleboxError::InvalidParameter(field.name.clone(), field.value.clone());
+        let mut value = SiValue::parse(&field.value).ok_or_else(invalid)?;
+        if value.unit.is_empty() {
+            value.unit = unit;
+        } else if value.unit != unit {
+            return Err(invalid());
+        }
+        field.value = value.to_string();
+    }
+    Ok(())
+}
+
+/// Names of the template fields that are missing or empty.
+pub(crate) fn missing_parameters(
+    template: &[ParamTemplate],
+    fields: &[CustomField],
+) -> Vec<String> {
+    template
+        .iter()
+        .filter(|p| {
+            !fields
+                .iter()
+                .any(|f| f.name.trim().eq_ignore_ascii_case(&p.name) && !f.value.trim().is_empty())
+        })
+        .map(|p| p.name.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Category, Handler, Manager, Package, PackageType, Part};
+
+    fn parameter(name: &str, value: &str) -> CustomField {
+        CustomField {
+            field_type: CustomFieldType::Parameter,
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_si_values() {
+        // Arrange
+        let cases = [
+            ("4k7", Decimal::new(4700, 0), ""),
+            ("4R7", Decimal::new(47, 1), "Ω"),
+            ("100nF", Decimal::new(100, 9), "F"),
+            ("0.1uF", Decimal::new(1, 7), "F"),
+            ("16V", Decimal::new(16, 0), "V"),
+            ("±5%", Decimal::new(5, 0), "%"),
+            ("2.2 MΩ", Decimal::new(2_200_000, 0), "Ω"),
+            ("10mA", Decimal::new(1, 2), "A"),
+            ("1F", Decimal::new(1, 0), "F"),
+        ];
+
+        for (text, value, unit) in cases {
+            // Act
+            let parsed = SiValue::parse(text).expect("Expected OK");
+
+            // Assert
+            assert_eq!(parsed.value, value, "{text}");
+            assert_eq!(parsed.unit, unit, "{text}");
+        }
+        assert!(SiValue::parse("µF").is_none());
+        assert!(SiValue::parse("-40°C ~ 125°C").is_none());
+    }
+
+    #[test]
+    fn test_display_si_values() {
+        // Arrange
+        let values = ["0.1uF", "4k7", "100R", "0.5%", "1.5A", "22pF"];
+
+        // Act
+        let texts: Vec<String> = values
+            .iter()
+            .map(|v| SiValue::parse(v).unwrap().to_string())
+            .collect();
+
+        // Assert
+        assert_eq!(texts, vec!["100nF", "4.7k", "100Ω", "0.5%", "1.5A", "22pF"]);
+    }
+
+    #[test]
+    fn test_parse_range() {
+        // Act
+        let at_least = ParamRange::parse("Capacitance>=10uF").expect("Expected OK");
+        let between = ParamRange::parse("Resistance=1k..10k").expect("Expected OK");
+        let invalid = ParamRange::parse("Voltage>=lots");
+
+        // Assert
+        assert_eq!(at_least.name, "Capacitance");
+        assert_eq!(at_least.min.unwrap().value, Decimal::new(10, 6));
+        assert!(at_least.max.is_none());
+        assert_eq!(between.max.unwrap().value, Decimal::new(10_000, 0));
+        assert!(matches!(invalid, Err(EleboxError::InvalidParameter(_, _))));
+    }
+
+    #[test]
+    fn test_find_parts_by_range() {
+        // Arrange
+        let mgr = Manager::in_memory();
+        mgr.category()
+            .add(&Category::new("Capacitors", None, None))
+            .unwrap();
+        mgr.category()
+            .add(&Category::new("MLCC", Some("Capacitors"), None))
+            .unwrap();
+        mgr.package()
+            .add(&Package::new("0805", PackageType::Smt, Some("2012")))
+            .unwrap();
+        mgr.package()
+            .add(&Package::new("0603", PackageType::Smt, Some("1608")))
+            .unwrap();
+        let caps = [
+            ("22uF 25V", "0805", "22uF", "25V"),
+            ("10uF 16V", "0805", "10uF", "16V"),
+            ("4u7 50V", "0805", "4u7", "50V"),
+            ("10uF 50V", "0603", "10uF", "50V"),
+        ];
+        for (name, package, capacitance, voltage) in caps {
+            let mut part = Part::new(name, "MLCC", 10);
+            part.package = Some(package.to_string());
+            part.custom_fields = vec![
+                parameter("Capacitance", capacitance),
+                parameter("Voltage", voltage),
+            ];
+            mgr.part().add(&part).unwrap();
+        }
+        let query = PartQuery {
+            category: Some("Capacitors".to_string()),
+            package: Some("2012".to_string()),
+            parameters: vec![
+                ParamRange::parse("capacitance>=10µF").unwrap(),
+                ParamRange::parse("Voltage>=25V").unwrap(),
+            ],
+            ..Default::default()
+        };
+
+        // Act
+        let parts = mgr.part().find(&query).expect("Expected OK");
+
+        // Assert
+        let names: Vec<_> = parts.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["22uF 25V"]);
+        assert_eq!(parts[0].custom_fields[0].value, "22µF");
+    }
+
+    #[test]
+    fn test_add_invalid_parameter() {
+        // Arrange
+        let mgr = Manager::in_memory();
+        mgr.category()
+            .add(&Category::new("Resistors", None, None))
+            .unwrap();
+        let mut part = Part::new("10k", "Resistors", 10);
+        part.custom_fields = vec![parameter("Resistance", "ten k")];
+
+        // Act
+        let result = mgr.part().add(&part);
+
+        // Assert
+        assert!(matches!(result, Err(EleboxError::InvalidParameter(_, _))));
+    }
+
+    fn template(name: &str, field_type: CustomFieldType, unit: &str) -> ParamTemplate {
+        ParamTemplate {
+            name: name.to_string(),
+            field_type,
+            unit: unit.to_string(),
+        }
+    }
+
+    fn capacitor_categories(mgr: &Manager) {
+        let mut caps = Category::new("Capacitors", None, None);
+        caps.parameters = vec![
+            template("Capacitance", CustomFieldType::Parameter, "F"),
+            template("Voltage", CustomFieldType::Parameter, "V"),
+        ];
+        mgr.category().add(&caps).unwrap();
+        let mut ceramic = Category::new("Ceramic Caps", Some("Capacitors"), None);
+        ceramic.parameters = vec![
+            template("Dielectric", CustomFieldType::Normal, ""),
+            template("voltage", CustomFieldType::Parameter, ""),
+        ];
+        mgr.category().add(&ceramic).unwrap();
+    }
+
+    #[test]
+    fn test_inherited_template() {
+        // Arrange
+        let mgr = Manager::in_memory();
+        capacitor_categories(&mgr);
+
+        // Act
+        let params = mgr
+            .category()
+            .template("Ceramic Caps")
+            .expect("Expected OK");
+
+        // Assert
+        let names: Vec<_> = params.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["Capacitance", "Dielectric", "voltage"]);
+        assert_eq!(params[2].unit, "");
+    }
+
+    #[test]
+    fn test_add_with_template() {
+        // Arrange
+        let mgr = Manager::in_memory();
+        capacitor_categories(&mgr);
+        let mut part = Part::new("C1", "Ceramic Caps", 10);
+        part.custom_fields = vec![
+            CustomField {
+                field_type: CustomFieldType::Normal,
+                name: "capacitance".to_string(),
+                value: "0.1u".to_string(),
+            },
+            parameter("Voltage", "16V"),
+        ];
+
+        // Act
+        let missing = mgr.part().add(&part);
+        part.custom_fields[0].value = "10V".to_string();
+        part.custom_fields.push(parameter("Dielectric", "X7R"));
+        let wrong_unit = mgr.part().add(&part);
+        part.custom_fields[0].value = "0.1u".to_string();
+        let added = mgr.part().add(&part);
+
+        // Assert
+        assert!(
+            matches!(missing, Err(EleboxError::MissingParameters(_, names)) if names == vec!["Dielectric"])
+        );
+        assert!(matches!(
+            wrong_unit,
+            Err(EleboxError::InvalidParameter(_, _))
+        ));
+        added.expect("Expected OK");
+        let saved = mgr.part().get("C1").unwrap();
+        assert_eq!(
+            saved.custom_fields[0].field_type,
+            CustomFieldType::Parameter
+        );
+        assert_eq!(saved.custom_fields[0].value, "100nF");
+        assert_eq!(saved.custom_fields[2].field_type, CustomFieldType::Normal);
+    }
+
+    #[test]
+    fn test_update_part_older_than_template() {
+        // Arrange
+        let mgr = Manager::in_memory();
+        mgr.category()
+            .add(&Category::new("Capacitors", None, None))
+            .unwrap();
+        let mut part = Part::new("C1", "Capacitors", 10);
+        part.custom_fields = vec![parameter("Capacitance", "1uF")];
+        mgr.part().add(&part).unwrap();
+        let mut caps = mgr.category().get("Capacitors").unwrap();
+        caps.parameters = vec![
+            template("Capacitance", CustomFieldType::Parameter, "F"),
+            template("Voltage", CustomFieldType::Parameter, "V"),
+        ];
+        mgr.category().update("Capacitors", &caps).unwrap();
+
+        // Act
+        part.quantity = 5;
+        let updated = mgr.part().update("C1", &part);
+        part.custom_fields.clear();
+        let removed = mgr.part().update("C1", &part);
+
+        // Assert
+        updated.expect("Expected OK");
+        assert!(
+            matches!(removed, Err(EleboxError::MissingParameters(_, names)) if names == vec!["Capacitance"])
+        );
+    }
+}
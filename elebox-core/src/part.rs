@@ -1,13 +1,27 @@
-use crate::{comm::*, errors::*, jamm_db::*, json::*, yaml::*};
-
+use crate::{
+    comm::*,
+    errors::*,
+    field::{apply_field_defs, normalize_fields},
+    jamm_db::*,
+    ledger::{close, record, StockReason},
+    location::{find_location_id, find_or_add_location},
+    param::{apply_template, missing_parameters, ParamRange, PartQuery, SiValue},
+    passive::{match_values, StockedValue, ValueMatches, ValueQuery},
+    stock::StockLine,
+    supplier::{find_or_add_supplier, find_supplier_id, PartSupplier},
+    CategoryHandler, Marking,
+};
+
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::{fmt::Debug, path::PathBuf};
 
-const DELETED_ITEM: &str = "__DELETE__";
+pub(crate) const DELETED_ITEM: &str = "__DELETE__";
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Part {
     pub name: String,
+    /// Total of `stock`.
     pub quantity: u16,
     pub category: String,
     pub package: Option<String>,
@@ -21,8 +35,19 @@ pub struct Part {
     pub product_link: Option<String>,
     pub image_link: Option<String>,
     pub custom_fields: Vec<CustomField>,
-    pub suppliers: Vec<Supplier>,
+    pub suppliers: Vec<PartSupplier>,
     pub starred: bool,
+    /// The part should be reordered at or below this quantity.
+    #[serde(default)]
+    pub min_stock: Option<u16>,
+    /// Quantity to order, `min_stock` if not set.
+    #[serde(default)]
+    pub reorder_quantity: Option<u16>,
+    /// Quantity by location. If empty, all of `quantity` is in `location`.
+    /// Otherwise a `quantity` different from the total of the lines is added
+    /// to or taken from the stock in `location` when the part is saved.
+    #[serde(default)]
+    pub stock: Vec<StockLine>,
 }
 
 impl Part {
@@ -44,19 +69,29 @@ impl Part {
             custom_fields: vec![],
             suppliers: vec![],
             starred: false,
+            min_stock: None,
+            reorder_quantity: None,
+            stock: vec![],
         }
     }
 }
 
+#[derive(Clone)]
 pub struct PartHandler<'a> {
     pub(crate) db: &'a dyn Database<DbPart>,
     pub(crate) pkg_db: &'a dyn Database<DbPackage>,
     pub(crate) cat_db: &'a dyn Database<DbCategory>,
     pub(crate) mfr_db: &'a dyn Database<DbManufacturer>,
+    pub(crate) ledger_db: &'a dyn Database<DbLedgerEntry>,
+    pub(crate) loc_db: &'a dyn Database<DbLocation>,
+    pub(crate) sup_db: &'a dyn Database<DbSupplier>,
+    pub(crate) field_db: &'a dyn Database<DbFieldDef>,
+    pub(crate) project_db: &'a dyn Database<DbProject>,
+    pub(crate) order_db: &'a dyn Database<DbOrder>,
 }
 
 impl PartHandler<'_> {
-    fn to_item(&self, db_part: DbPart) -> Result<Part, EleboxError> {
+    pub(crate) fn to_item(&self, db_part: DbPart) -> Result<Part, EleboxError> {
         let category = match self.cat_db.get(&db_part.category_id) {
             Ok(item) => item.name,
             Err(err) => match err {
@@ -65,22 +100,40 @@ impl PartHandler<'_> {
             },
         };
 
+        // Empty ID if the part has no package
         let package = match self.pkg_db.get(&db_part.package_id) {
             Ok(item) => Some(item.name),
+            Err(_) if db_part.package_id.is_empty() => None,
             Err(err) => match err {
                 DbError::NotExists(_) => Some(String::from(DELETED_ITEM)),
                 _ => return Err(EleboxError::DatabaseError(err)),
             },
         };
 
+        // Empty ID if the part has no manufacturer
         let mfr = match self.mfr_db.get(&db_part.mfr_id) {
             Ok(item) => Some(item.name),
+            Err(_) if db_part.mfr_id.is_empty() => None,
             Err(err) => match err {
                 DbError::NotExists(_) => Some(String::from(DELETED_ITEM)),
                 _ => return Err(EleboxError::DatabaseError(err)),
             },
         };
 
+        let location = self.location_name(&db_part.location_id)?;
+        let mut stock = vec![];
+        for line in db_part.stock_lines() {
+            stock.push(StockLine {
+                location: self.location_name(&line.location_id)?,
+                quantity: line.quantity,
+            });
+        }
+
+        let mut suppliers = vec![];
+        for offer in db_part.suppliers {
+            suppliers.push(self.to_part_supplier(offer)?);
+        }
+
         let part = Part {
             name: db_part.name,
             category,
@@ -90,14 +143,17 @@ impl PartHandler<'_> {
             mfr,
             alias: Some(db_part.alias),
             description: Some(db_part.description),
-            location: Some(db_part.location),
+            location,
             mfr_no: Some(db_part.mfr_no),
             datasheet_link: Some(db_part.datasheet_link),
             product_link: Some(db_part.product_link),
             image_link: Some(db_part.image_link),
             custom_fields: db_part.custom_fields,
-            suppliers: db_part.suppliers,
+            suppliers,
             starred: db_part.starred,
+            min_stock: db_part.min_stock,
+            reorder_quantity: db_part.reorder_quantity,
+            stock,
         };
 
         Ok(part)
@@ -134,54 +190,430 @@ impl PartHandler<'_> {
             None => "".to_string(),
         };
 
+        let location_id = self.location_id(item.location.as_deref())?;
+        let mut stock = vec![];
+        for line in &item.stock {
+            stock.push(DbStockLine {
+                location_id: self.location_id(line.location.as_deref())?,
+                quantity: line.quantity,
+            });
+        }
+
+        let mut suppliers = vec![];
+        for offer in &item.suppliers {
+            suppliers.push(self.to_db_part_supplier(offer)?);
+        }
+
+        let mut custom_fields = item.custom_fields.clone();
+        normalize_fields(&mut custom_fields)?;
+
         let unwrap_or_empty = |opt: &Option<String>| opt.as_deref().unwrap_or("").to_string();
 
-        let db_part = DbPart {
+        let mut db_part = DbPart {
             name: item.name.to_string(),
-            quantity: item.quantity,
+            quantity: 0,
             category_id,
             mfr_id,
             package_id,
             package_detail: unwrap_or_empty(&item.package_detail),
             alias: unwrap_or_empty(&item.alias),
             description: unwrap_or_empty(&item.description),
-            location: unwrap_or_empty(&item.location),
+            location_id,
             mfr_no: unwrap_or_empty(&item.mfr_no),
             datasheet_link: unwrap_or_empty(&item.datasheet_link),
             product_link: unwrap_or_empty(&item.product_link),
             image_link: unwrap_or_empty(&item.image_link),
-            custom_fields: item.custom_fields.clone(),
-            suppliers: item.suppliers.clone(),
+            custom_fields,
+            suppliers,
             starred: item.starred,
+            min_stock: item.min_stock,
+            reorder_quantity: item.reorder_quantity,
+            stock: vec![],
         };
 
+        // A changed total is added to or taken from the part's location
+        db_part.set_stock(stock)?;
+        if item.quantity > db_part.quantity {
+            let location_id = db_part.location_id.clone();
+            db_part.put_stock(&location_id, item.quantity - db_part.quantity)?;
+        } else {
+            db_part.take_stock(None, db_part.quantity - item.quantity)?;
+        }
+
         Ok(db_part)
     }
 
-    pub fn update_part_quantity(&self, name: &str, increment: i16) -> Result<(), EleboxError> {
+    /// Name of the location, `None` for an empty ID.
+    fn location_name(&self, id: &str) -> Result<Option<String>, EleboxError> {
+        match self.loc_db.get(id) {
+            Ok(item) => Ok(Some(item.name)),
+            Err(_) if id.is_empty() => Ok(None),
+            Err(err) => match err {
+                DbError::NotExists(_) => Ok(Some(String::from(DELETED_ITEM))),
+                _ => Err(EleboxError::DatabaseError(err)),
+            },
+        }
+    }
+
+    /// ID of the location, empty for `None`. Locations are places, a part
+    /// cannot refer to one that does not exist.
+    fn location_id(&self, name: Option<&str>) -> Result<Id, EleboxError> {
+        match name {
+            Some("") | None => Ok("".to_string()),
+            Some(name) => match find_location_id(self.loc_db, name) {
+                Ok(id) => Ok(id),
+                Err(DbError::NotExists(_)) => Err(EleboxError::NotExists(
+                    String::from(ITEM_LOCATION),
+                    name.to_string(),
+                )),
+                Err(err) => Err(EleboxError::DatabaseError(err)),
+            },
+        }
+    }
+
+    fn to_part_supplier(&self, offer: DbPartSupplier) -> Result<PartSupplier, EleboxError> {
+        let supplier = match self.sup_db.get(&offer.supplier_id) {
+            Ok(item) => item.name,
+            Err(_) if offer.supplier_id.is_empty() => "".to_string(),
+            Err(err) => match err {
+                DbError::NotExists(_) => String::from(DELETED_ITEM),
+                _ => return Err(EleboxError::DatabaseError(err)),
+            },
+        };
+        let some_text = |s: String| Some(s).filter(|s| !s.is_empty());
+
+        Ok(PartSupplier {
+            supplier,
+            sku: some_text(offer.sku),
+            link: some_text(offer.link),
+            min_order: offer.min_order,
+            pack_size: offer.pack_size,
+            currency: some_text(offer.currency),
+            price_breaks: offer.price_breaks,
+            note: some_text(offer.note),
+        })
+    }
+
+    /// Suppliers are records like locations, a part cannot refer to one that
+    /// does not exist. Price breaks are kept sorted by quantity.
+    fn to_db_part_supplier(&self, offer: &PartSupplier) -> Result<DbPartSupplier, EleboxError> {
+        let supplier_id = match offer.supplier.as_str() {
+            "" => "".to_string(),
+            name => match find_supplier_id(self.sup_db, name) {
+                Ok(id) => id,
+                Err(DbError::NotExists(_)) => {
+                    return Err(EleboxError::NotExists(
+                        String::from(ITEM_SUPPLIER),
+                        name.to_string(),
+                    ))
+                }
+                Err(err) => return Err(EleboxError::DatabaseError(err)),
+            },
+        };
+
+        let mut price_breaks = offer.price_breaks.clone();
+        price_breaks.sort_by_key(|b| b.quantity);
+
+        Ok(DbPartSupplier {
+            supplier_id,
+            link: offer.link.clone().unwrap_or_default(),
+            price_breaks,
+            note: offer.note.clone().unwrap_or_default(),
+            sku: offer.sku.clone().unwrap_or_default(),
+            min_order: offer.min_order,
+            pack_size: offer.pack_size,
+            currency: offer
+                .currency
+                .as_deref()
+                .unwrap_or_default()
+                .trim()
+                .to_uppercase(),
+        })
+    }
+
+    /// Restock with a positive `increment`, use with a negative one. See
+    /// `move_stock()` for the location.
+    pub fn update_part_quantity(
+        &self,
+        name: &str,
+        increment: i16,
+        location: Option<&str>,
+    ) -> Result<(), EleboxError> {
+        let reason = if increment < 0 {
+            StockReason::Use
+        } else {
+            StockReason::Restock
+        };
+        self.move_stock(name, increment, location, reason, None, None)
+    }
+
+    /// Change the quantity of the part and record it in the ledger, e.g. with
+    /// the project the parts were used for. Without a `location`, restocks go
+    /// to the part's location and uses take from it first, then from the others.
+    pub fn move_stock(
+        &self,
+        name: &str,
+        delta: i16,
+        location: Option<&str>,
+        reason: StockReason,
+        note: Option<&str>,
+        project: Option<&str>,
+    ) -> Result<(), EleboxError> {
         let id = self.db.get_id(name)?;
         let mut db_item = self.db.get(id.as_str())?;
+        let location_id = match location {
+            Some(_) => Some(self.location_id(location)?),
+            None => None,
+        };
 
-        let new_qty = db_item.quantity as i16 + increment;
-        if new_qty < 0 {
-            return Err(EleboxError::InventoryShortage(name.to_string()));
+        if delta >= 0 {
+            let to_id = location_id.unwrap_or_else(|| db_item.location_id.clone());
+            db_item.put_stock(&to_id, delta.unsigned_abs())?;
         } else {
-            db_item.quantity = new_qty as u16;
+            db_item.take_stock(location_id.as_deref(), delta.unsigned_abs())?;
         }
 
-        self.db.update(&id, &db_item)?;
-        Ok(())
+        in_transaction(self.db, self.ledger_db, || {
+            self.db.update(&id, &db_item)?;
+            record(self.ledger_db, &id, delta as i32, reason, note, project)?;
+            Ok(())
+        })
     }
-}
 
-impl<'a> Handler<Part> for PartHandler<'_> {
-    fn delete(&self, name: &str) -> Result<(), EleboxError> {
+    /// Parts matching all conditions of the query, e.g. the capacitors of at
+    /// least 10µF rated for 25V or more in 0805.
+    pub fn find(&self, query: &PartQuery) -> Result<Vec<Part>, EleboxError> {
+        let category_ids = match &query.category {
+            Some(name) => {
+                let handler = CategoryHandler {
+                    db: self.cat_db,
+                    part: self.clone(),
+                };
+                Some(handler.subtree_ids(&self.cat_db.get_id(name)?)?)
+            }
+            None => None,
+        };
+        let package_ids: Option<Vec<Id>> = match &query.package {
+            Some(name) => Some(
+                self.pkg_db
+                    .list_with_id()?
+                    .into_iter()
+                    .filter(|(_, pkg)| {
+                        pkg.name.eq_ignore_ascii_case(name) || pkg.alias.eq_ignore_ascii_case(name)
+                    })
+                    .map(|(id, _)| id)
+                    .collect(),
+            ),
+            None => None,
+        };
+
+        let mut parts = vec![];
+        for db_part in self.db.list()? {
+            let in_category = category_ids
+                .as_ref()
+                .is_none_or(|ids| ids.contains(&db_part.category_id));
+            let in_package = package_ids
+                .as_ref()
+                .is_none_or(|ids| ids.contains(&db_part.package_id));
+            let in_range = query
+                .parameters
+                .iter()
+                .all(|range| range.matches(&db_part.custom_fields));
+            let fields_match = query
+                .fields
+                .iter()
+                .all(|filter| filter.matches(&db_part.custom_fields));
+            if in_category && in_package && in_range && fields_match {
+                parts.push(self.to_item(db_part)?);
+            }
+        }
+        Ok(parts)
+    }
+
+    /// Parts with the value of the marking, e.g. a `Resistance` of 10kΩ for
+    /// `103` on a resistor. The package can be given by its size alone, e.g.
+    /// `0805` for `SMD 0805`.
+    pub fn find_by_marking(
+        &self,
+        marking: &Marking,
+        package: Option<&str>,
+    ) -> Result<Vec<Part>, EleboxError> {
+        let package = self.package_or_size(package)?;
+        self.find(&marking.query(package))
+    }
+
+    /// The stocked values nearest to the target and the best series and
+    /// parallel combinations of them, from the parts with enough quantity.
+    pub fn nearest_values(&self, query: &ValueQuery) -> Result<ValueMatches, EleboxError> {
+        let kind = query.kind;
+        let target = &query.target;
+        if target.value <= Decimal::ZERO || !(target.unit.is_empty() || target.unit == kind.unit())
+        {
+            return Err(EleboxError::InvalidParameter(
+                kind.parameter().to_string(),
+                target.to_string(),
+            ));
+        }
+
+        let parts = self.find(&PartQuery {
+            package: self.package_or_size(query.package.as_deref())?,
+            parameters: vec![ParamRange {
+                name: kind.parameter().to_string(),
+                min: None,
+                max: None,
+            }],
+            ..Default::default()
+        })?;
+        let stock = parts
+            .into_iter()
+            .filter_map(|part| {
+                let mut value = part
+                    .custom_fields
+                    .iter()
+                    .filter(|f| f.field_type == CustomFieldType::Parameter)
+                    .filter(|f| f.name.trim().eq_ignore_ascii_case(kind.parameter()))
+                    .find_map(|f| SiValue::parse(&f.value))?;
+                if !value.unit.is_empty() && value.unit != kind.unit() {
+                    return None;
+                }
+                value.unit = kind.unit().to_string();
+                Some(StockedValue {
+                    part: part.name,
+                    value,
+                    quantity: part.quantity,
+                })
+            })
+            .collect();
+        Ok(match_values(query, stock))
+    }
+
+    /// The package name, or `SMD` and the size if no package has the name,
+    /// e.g. `0805` for `SMD 0805`.
+    fn package_or_size(&self, package: Option<&str>) -> Result<Option<String>, EleboxError> {
+        let Some(name) = package.map(str::trim) else {
+            return Ok(None);
+        };
+        let known =
+            self.pkg_db.list()?.iter().any(|pkg| {
+                pkg.name.eq_ignore_ascii_case(name) || pkg.alias.eq_ignore_ascii_case(name)
+            });
+        Ok(Some(if known {
+            name.to_string()
+        } else {
+            format!("SMD {}", name)
+        }))
+    }
+
+    /// Move stock of the part between locations, `None` is the part's
+    /// location. The total does not change, so nothing is recorded in the ledger.
+    pub fn transfer(
+        &self,
+        name: &str,
+        quantity: u16,
+        from: Option<&str>,
+        to: Option<&str>,
+    ) -> Result<(), EleboxError> {
         let id = self.db.get_id(name)?;
-        let _ = self.db.delete(&id)?;
+        let mut db_item = self.db.get(&id)?;
+        let from_id = match from {
+            Some(_) => self.location_id(from)?,
+            None => db_item.location_id.clone(),
+        };
+        let to_id = match to {
+            Some(_) => self.location_id(to)?,
+            None => db_item.location_id.clone(),
+        };
+
+        db_item.take_stock(Some(&from_id), quantity)?;
+        db_item.put_stock(&to_id, quantity)?;
+        self.db.update(&id, &db_item)?;
         Ok(())
     }
 
-    fn add(&self, item: &Part) -> Result<(), EleboxError> {
+    /// Fields the parts of the category are expected to have, see
+    /// `CategoryHandler::template`. Empty for a missing category.
+    pub fn template(&self, category: &str) -> Result<Vec<ParamTemplate>, EleboxError> {
+        let handler = CategoryHandler {
+            db: self.cat_db,
+            part: self.clone(),
+        };
+        match self.cat_db.get_id(category) {
+            Ok(id) => handler.template_of(&id),
+            Err(DbError::NotExists(_)) => Ok(vec![]),
+            Err(err) => Err(EleboxError::DatabaseError(err)),
+        }
+    }
+
+    /// Check the part against the template of its category and give its
+    /// fields the names and types of the field definitions. When updating,
+    /// the fields also missing in `ori_fields` may be left out, so parts
+    /// saved before the template can still be changed.
+    fn check_template(
+        &self,
+        item: &Part,
+        ori_fields: Option<&[CustomField]>,
+    ) -> Result<Part, EleboxError> {
+        let template = self.template(&item.category)?;
+        let mut part = item.clone();
+        apply_template(&template, &mut part.custom_fields)?;
+        apply_field_defs(&self.field_db.list()?, &mut part.custom_fields);
+
+        let ori_missing = ori_fields.map_or(vec![], |f| missing_parameters(&template, f));
+        let missing: Vec<String> = missing_parameters(&template, &part.custom_fields)
+            .into_iter()
+            .filter(|name| !ori_missing.contains(name))
+            .collect();
+        if !missing.is_empty() {
+            return Err(EleboxError::MissingParameters(item.name.clone(), missing));
+        }
+        Ok(part)
+    }
+
+    /// Delete the parts together with their lines of received orders, their
+    /// ledger entries are kept and closed. Refused while a project, or an
+    /// order not received yet, still uses any of them.
+    pub(crate) fn delete_ids(&self, ids: &[Id]) -> Result<(), EleboxError> {
+        in_transaction(self.db, self.ledger_db, || {
+            in_transaction(self.project_db, self.order_db, || {
+                let projects = self.project_db.list()?;
+                let orders = self.order_db.list_with_id()?;
+                for id in ids {
+                    let projects = projects
+                        .iter()
+                        .filter(|project| project.bom.iter().any(|b| &b.part_id == id))
+                        .map(|project| project.name.clone());
+                    let orders = orders
+                        .iter()
+                        .filter(|(_, order)| order.is_open())
+                        .filter(|(_, order)| order.lines.iter().any(|l| &l.part_id == id))
+                        .map(|(_, order)| order.name.clone());
+                    let users: Vec<String> = projects.chain(orders).collect();
+                    if !users.is_empty() {
+                        return Err(EleboxError::StillReferenced(
+                            String::from(ITEM_PART),
+                            self.db.get(id)?.name,
+                            users,
+                        ));
+                    }
+                }
+
+                for (order_id, mut order) in orders {
+                    let count = order.lines.len();
+                    order.lines.retain(|line| !ids.contains(&line.part_id));
+                    if order.lines.len() != count {
+                        self.order_db.update(&order_id, &order)?;
+                    }
+                }
+                close(self.ledger_db, ids)?;
+                for id in ids {
+                    self.db.delete(id)?;
+                }
+                Ok(())
+            })
+        })
+    }
+
+    fn add_with_reason(&self, item: &Part, reason: StockReason) -> Result<(), EleboxError> {
         if self.db.get_id(&item.name).is_ok() {
             return Err(EleboxError::AlreadyExists(
                 String::from(ITEM_PART),
@@ -190,8 +622,103 @@ impl<'a> Handler<Part> for PartHandler<'_> {
         }
 
         let db_item = self.to_db_item(item)?;
-        let _ = self.db.add(&db_item)?;
+        in_transaction(self.db, self.ledger_db, || {
+            self.db.add(&db_item)?;
+            if db_item.quantity > 0 {
+                let id = self.db.get_id(&item.name)?;
+                record(
+                    self.ledger_db,
+                    &id,
+                    db_item.quantity as i32,
+                    reason,
+                    None,
+                    None,
+                )?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Parts that refer to the given ID, `field` selects the reference.
+pub(crate) fn parts_using(
+    part_db: &dyn Database<DbPart>,
+    id: &str,
+    field: fn(&mut DbPart) -> &mut Id,
+) -> Result<Vec<(Id, DbPart)>, DbError> {
+    let parts = part_db.list_with_id()?;
+    Ok(parts
+        .into_iter()
+        .filter_map(|(part_id, mut part)| (*field(&mut part) == id).then_some((part_id, part)))
+        .collect())
+}
+
+/// Delete a package or manufacturer, the parts that still refer to it are
+/// handled by `policy`.
+pub(crate) fn delete_referenced<DI>(
+    db: &dyn Database<DI>,
+    part: &PartHandler,
+    item: &str,
+    name: &str,
+    policy: &DeletePolicy,
+    field: fn(&mut DbPart) -> &mut Id,
+) -> Result<(), EleboxError> {
+    let part_db = part.db;
+    let id = db.get_id(name)?;
+    let parts = parts_using(part_db, &id, field)?;
+
+    let new_id = match policy {
+        DeletePolicy::Reassign(new_name) => Some(db.get_id(new_name)?),
+        _ => None,
+    };
+
+    // Reassigning to itself would keep the references
+    let restrict = *policy == DeletePolicy::Restrict || new_id.as_ref() == Some(&id);
+    if restrict && !parts.is_empty() {
+        let names = parts.into_iter().map(|(_, part)| part.name).collect();
+        return Err(EleboxError::StillReferenced(
+            item.to_string(),
+            name.to_string(),
+            names,
+        ));
+    }
+    if parts.is_empty() {
+        db.delete(&id)?;
+        return Ok(());
+    }
+
+    in_transaction(db, part_db, || {
+        match &new_id {
+            Some(new_id) => {
+                for (part_id, mut db_part) in parts {
+                    *field(&mut db_part) = new_id.clone();
+                    part_db.update(&part_id, &db_part)?;
+                }
+            }
+            None => {
+                let ids: Vec<Id> = parts.into_iter().map(|(part_id, _)| part_id).collect();
+                part.delete_ids(&ids)?;
+            }
+        }
+        db.delete(&id)?;
         Ok(())
+    })
+}
+
+impl<'a> Handler<Part> for PartHandler<'_> {
+    /// Delete the part, refused while a project or an order not received
+    /// yet still uses it. Its ledger entries are kept, closed by an
+    /// adjustment of the quantity left.
+    fn delete(&self, name: &str) -> Result<(), EleboxError> {
+        let id = self.db.get_id(name)?;
+        self.delete_ids(&[id])
+    }
+
+    /// Add the part, its initial quantity is recorded as an adjustment.
+    /// All fields of the category template are required.
+    fn add(&self, item: &Part) -> Result<(), EleboxError> {
+        let part = self.check_template(item, None)?;
+        self.add_with_reason(&part, StockReason::Adjustment)
     }
 
     fn update(&self, ori_name: &str, new_item: &Part) -> Result<(), EleboxError> {
@@ -204,9 +731,27 @@ impl<'a> Handler<Part> for PartHandler<'_> {
             ));
         }
 
+        let ori_part = self.db.get(&ori_id)?;
+        let ori_qty = ori_part.quantity;
+        let new_item = &self.check_template(new_item, Some(&ori_part.custom_fields))?;
         let db_part = self.to_db_item(new_item)?;
-        let _ = self.db.update(ori_id.as_str(), &db_part)?;
-        Ok(())
+        in_transaction(self.db, self.ledger_db, || {
+            self.db.update(ori_id.as_str(), &db_part)?;
+
+            // Editing the quantity directly is an adjustment
+            let delta = new_item.quantity as i32 - ori_qty as i32;
+            if delta != 0 {
+                record(
+                    self.ledger_db,
+                    &ori_id,
+                    delta,
+                    StockReason::Adjustment,
+                    None,
+                    None,
+                )?;
+            }
+            Ok(())
+        })
     }
 
     fn get(&self, name: &str) -> Result<Part, EleboxError> {
@@ -230,38 +775,28 @@ impl<'a> Handler<Part> for PartHandler<'_> {
 
 impl Transferable for PartHandler<'_> {
     fn export(&self, filename: &PathBuf) -> Result<(), EleboxError> {
-        let items = self.list()?;
-
-        if YamlFile::check_extension(filename) {
-            let _ = YamlFile::write(filename, items);
-        } else if JsonFile::check_extension(filename) {
-            let _ = JsonFile::write(filename, items);
-        } else {
-            todo!()
-        }
-
-        Ok(())
+        write_items(filename, self.list()?)
     }
 
     fn import(&self, filename: &PathBuf) -> Result<(), EleboxError> {
-        let res_items: Result<Vec<Part>, ()> = if YamlFile::check_extension(filename) {
-            YamlFile::read(filename)
-        } else if JsonFile::check_extension(filename) {
-            JsonFile::read(filename)
-        } else {
-            todo!()
-        };
-
-        if res_items.is_err() {
-            todo!()
-        }
-
-        let parts: Vec<Part> = res_items.unwrap();
+        let parts: Vec<Part> = read_items(filename)?;
         for part in parts {
-            if let Err(e) = self.add(&part) {
+            // Exports of older releases have free-form locations and suppliers
+            if let Some(name) = part.location.as_deref().filter(|s| !s.trim().is_empty()) {
+                find_or_add_location(self.loc_db, name)?;
+            }
+            for offer in part
+                .suppliers
+                .iter()
+                .filter(|s| !s.supplier.trim().is_empty())
+            {
+                find_or_add_supplier(self.sup_db, &offer.supplier)?;
+            }
+
+            if let Err(e) = self.add_with_reason(&part, StockReason::Import) {
                 match e {
                     EleboxError::AlreadyExists(_, _) => continue,
-                    others => panic!("{}", others.to_string()),
+                    others => return Err(others),
                 }
             }
         }
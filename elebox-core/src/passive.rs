@@ -0,0 +1,433 @@
+use crate::param::SiValue;
+use core::fmt;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Kind of passive component, with the parameter its value is stored in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub enum PassiveKind {
+    #[default]
+    Resistor,
+    Capacitor,
+    Inductor,
+}
+
+impl PassiveKind {
+    /// Name of the parameter the value is stored in, as in the templates of
+    /// the default categories.
+    pub fn parameter(&self) -> &'static str {
+        match self {
+            PassiveKind::Resistor => "Resistance",
+            PassiveKind::Capacitor => "Capacitance",
+            PassiveKind::Inductor => "Inductance",
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            PassiveKind::Resistor => "Ω",
+            PassiveKind::Capacitor => "F",
+            PassiveKind::Inductor => "H",
+        }
+    }
+
+    /// Capacitors add up in parallel, resistors and inductors in series.
+    fn adds_in_series(&self) -> bool {
+        *self != PassiveKind::Capacitor
+    }
+}
+
+/// Search for the stocked values near a target, see
+/// `PartHandler::nearest_values`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ValueQuery {
+    pub kind: PassiveKind,
+    pub target: SiValue,
+    /// Largest deviation from the target, in percent.
+    pub tolerance: Decimal,
+    /// Package name or alias, or the size alone like `0805`.
+    pub package: Option<String>,
+    /// Number of circuits to build, a part used twice in a combination needs
+    /// twice as many in stock.
+    pub quantity: u16,
+    /// Most parts in a combination, 1 for none.
+    pub max_parts: usize,
+    /// Most values and combinations returned.
+    pub limit: usize,
+}
+
+impl ValueQuery {
+    pub fn new(kind: PassiveKind, target: SiValue) -> Self {
+        Self {
+            kind,
+            target,
+            tolerance: Decimal::from(5),
+            package: None,
+            quantity: 1,
+            max_parts: 3,
+            limit: 5,
+        }
+    }
+}
+
+/// A part with the value of its parameter.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct StockedValue {
+    pub part: String,
+    pub value: SiValue,
+    pub quantity: u16,
+}
+
+/// How the parts of a combination are connected, the first part is the
+/// single one in the mixed connections. Written with `+` for series and `∥`
+/// for parallel.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum Connection {
+    Single,
+    Series,
+    Parallel,
+    /// The first part in series with the others in parallel.
+    SeriesParallel,
+    /// The first part in parallel with the others in series.
+    ParallelSeries,
+}
+
+/// Parts that together give a value near the target.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Combination {
+    pub connection: Connection,
+    pub parts: Vec<StockedValue>,
+    /// Rounded to 4 significant digits.
+    pub value: SiValue,
+    /// Deviation from the target in percent, e.g. `-0.73`.
+    pub deviation: Decimal,
+}
+
+impl fmt::Display for Combination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let values: Vec<String> = self.parts.iter().map(|p| p.value.to_string()).collect();
+        match (self.connection, values.as_slice()) {
+            (Connection::SeriesParallel, [a, b, c]) => write!(f, "{} + ({} ∥ {})", a, b, c),
+            (Connection::ParallelSeries, [a, b, c]) => write!(f, "{} ∥ ({} + {})", a, b, c),
+            (Connection::Parallel, _) => write!(f, "{}", values.join(" ∥ ")),
+            _ => write!(f, "{}", values.join(" + ")),
+        }
+    }
+}
+
+/// The stocked values near the target and the best combinations of them.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct ValueMatches {
+    /// Single parts, the nearest first.
+    pub nearest: Vec<Combination>,
+    /// Two or more parts, the nearest first.
+    pub combinations: Vec<Combination>,
+}
+
+/// Value of the parts connected the way, `None` if it cannot be computed.
+fn combine<T>(kind: PassiveKind, connection: Connection, values: &[T]) -> Option<T>
+where
+    T: Copy + std::ops::Add<Output = T> + std::ops::Div<Output = T> + From<u8>,
+{
+    let sum = |values: &[T]| values.iter().fold(T::from(0), |acc, v| acc + *v);
+    let reciprocal = |values: &[T]| {
+        let one = T::from(1);
+        let total = values.iter().fold(T::from(0), |acc, v| acc + one / *v);
+        one / total
+    };
+    // Series of resistors is parallel of capacitors
+    let series = |values: &[T]| match kind.adds_in_series() {
+        true => sum(values),
+        false => reciprocal(values),
+    };
+    let parallel = |values: &[T]| match kind.adds_in_series() {
+        true => reciprocal(values),
+        false => sum(values),
+    };
+
+    match (connection, values) {
+        (Connection::Single, [a]) => Some(*a),
+        (Connection::Series, _) => Some(series(values)),
+        (Connection::Parallel, _) => Some(parallel(values)),
+        (Connection::SeriesParallel, [a, b, c]) => Some(series(&[*a, parallel(&[*b, *c])])),
+        (Connection::ParallelSeries, [a, b, c]) => Some(parallel(&[*a, series(&[*b, *c])])),
+        _ => None,
+    }
+}
+
+/// Candidate while searching, with the indices of the stocked values.
+struct Candidate {
+    error: f64,
+    connection: Connection,
+    indices: Vec<usize>,
+}
+
+/// Find the stocked values within the tolerance of the target, and combine
+/// them in series and parallel. Of the parts with the same value only the
+/// one with the most stock is used.
+pub(crate) fn match_values(query: &ValueQuery, stock: Vec<StockedValue>) -> ValueMatches {
+    let mut values: Vec<StockedValue> = vec![];
+    for item in stock {
+        if item.value.value <= Decimal::ZERO || item.quantity < query.quantity {
+            continue;
+        }
+        match values
+            .iter_mut()
+            .find(|v| v.value.value == item.value.value)
+        {
+            Some(v) if v.quantity < item.quantity => *v = item,
+            Some(_) => {}
+            None => values.push(item),
+        }
+    }
+
+    // The larger values first, e.g. `10kΩ + 3.6kΩ`
+    values.sort_by_key(|v| std::cmp::Reverse(v.value.value));
+
+    let Some(target) = query.target.value.to_f64().filter(|t| *t > 0.0) else {
+        return ValueMatches::default();
+    };
+    let floats: Vec<f64> = values
+        .iter()
+        .map(|v| v.value.value.to_f64().unwrap_or(f64::NAN))
+        .collect();
+    // A little more than the tolerance, the exact check is done on the results
+    let max_error = query.tolerance.to_f64().unwrap_or(0.0) / 100.0 * (1.0 + 1e-9);
+
+    let mut singles = vec![];
+    let mut combinations = vec![];
+    let mut consider = |connection: Connection, indices: &[usize]| {
+        let mut inputs = [0.0; 3];
+        for (input, i) in inputs.iter_mut().zip(indices) {
+            *input = floats[*i];
+        }
+        let Some(value) = combine(query.kind, connection, &inputs[..indices.len()]) else {
+            return;
+        };
+        let error = (value - target).abs() / target;
+        if !error.is_finite() || error > max_error {
+            return;
+        }
+        // Enough stock for every use of the parts
+        let enough = indices.iter().all(|i| {
+            let uses = indices.iter().filter(|j| *j == i).count() as u32;
+            u32::from(values[*i].quantity) >= uses * u32::from(query.quantity)
+        });
+        if !enough {
+            return;
+        }
+
+        let found = if indices.len() == 1 {
+            &mut singles
+        } else {
+            &mut combinations
+        };
+        found.push(Candidate {
+            error,
+            connection,
+            indices: indices.to_vec(),
+        });
+        // Keep the memory bounded on large inventories
+        if found.len() > 10_000 {
+            found.sort_by(|a, b| a.error.total_cmp(&b.error));
+            found.truncate(query.limit);
+        }
+    };
+
+    let n = values.len();
+    for i in 0..n {
+        consider(Connection::Single, &[i]);
+        if query.max_parts < 2 {
+            continue;
+        }
+        for j in i..n {
+            consider(Connection::Series, &[i, j]);
+            consider(Connection::Parallel, &[i, j]);
+            if query.max_parts < 3 {
+                continue;
+            }
+            for k in j..n {
+                let indices = [i, j, k];
+                consider(Connection::Series, &indices);
+                consider(Connection::Parallel, &indices);
+                // Each value once as the single one of the mixed connections
+                for pos in 0..3 {
+                    if pos > 0 && indices[pos] == indices[pos - 1] {
+                        continue;
+                    }
+                    let (b, c) = match pos {
+                        0 => (indices[1], indices[2]),
+                        1 => (indices[0], indices[2]),
+                        _ => (indices[0], indices[1]),
+                    };
+                    let ordered = [indices[pos], b, c];
+                    consider(Connection::SeriesParallel, &ordered);
+                    consider(Connection::ParallelSeries, &ordered);
+                }
+            }
+        }
+    }
+
+    let finish = |candidates: Vec<Candidate>| {
+        let mut found: Vec<Combination> = candidates
+            .into_iter()
+            .filter_map(|candidate| {
+                let parts: Vec<StockedValue> = candidate
+                    .indices
+                    .iter()
+                    .map(|i| values[*i].clone())
+                    .collect();
+                let inputs: Vec<Decimal> = parts.iter().map(|p| p.value.value).collect();
+                let value = combine(query.kind, candidate.connection, &inputs)?;
+                let deviation =
+                    (value - query.target.value) / query.target.value * Decimal::ONE_HUNDRED;
+                (deviation.abs() <= query.tolerance).then(|| Combination {
+                    connection: candidate.connection,
+                    parts,
+                    value: SiValue {
+                        value: value.round_sf(4).unwrap_or(value).normalize(),
+                        unit: query.kind.unit().to_string(),
+                    },
+                    deviation: deviation.round_dp(2).normalize(),
+                })
+            })
+            .collect();
+        found.sort_by(|a, b| {
+            a.deviation
+                .abs()
+                .cmp(&b.deviation.abs())
+                .then(a.parts.len().cmp(&b.parts.len()))
+        });
+        found.truncate(query.limit);
+        found
+    };
+
+    ValueMatches {
+        nearest: finish(singles),
+        combinations: finish(combinations),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CustomField, CustomFieldType, Handler, Manager, Part};
+
+    fn stocked(part: &str, value: &str, quantity: u16) -> StockedValue {
+        StockedValue {
+            part: part.to_string(),
+            value: SiValue::parse(value).unwrap(),
+            quantity,
+        }
+    }
+
+    fn query(kind: PassiveKind, target: &str) -> ValueQuery {
+        ValueQuery::new(kind, SiValue::parse(target).unwrap())
+    }
+
+    #[test]
+    fn test_nearest_values() {
+        // Arrange
+        let stock = vec![
+            stocked("R12k", "12kΩ", 10),
+            stocked("R13k", "13kΩ", 10),
+            stocked("R15k", "15kΩ", 10),
+            stocked("R13k-old", "13kΩ", 50),
+        ];
+
+        // Act
+        let mut query = query(PassiveKind::Resistor, "13.7k");
+        query.tolerance = Decimal::from(6);
+        let matches = match_values(&query, stock);
+
+        // Assert
+        let nearest: Vec<_> = matches
+            .nearest
+            .iter()
+            .map(|c| (c.parts[0].part.as_str(), c.deviation.to_string()))
+            .collect();
+        assert_eq!(nearest, vec![("R13k-old", "-5.11".to_string())]);
+    }
+
+    #[test]
+    fn test_combinations() {
+        // Arrange
+        let stock = vec![
+            stocked("R10k", "10kΩ", 10),
+            stocked("R3k6", "3.6kΩ", 1),
+            stocked("R1k", "1kΩ", 1),
+            stocked("R100", "100Ω", 10),
+        ];
+        let mut query = query(PassiveKind::Resistor, "13.7k");
+        query.tolerance = Decimal::ONE;
+
+        // Act
+        let matches = match_values(&query, stock);
+
+        // Assert
+        assert!(matches.nearest.is_empty());
+        let best = &matches.combinations[0];
+        assert_eq!(best.to_string(), "10kΩ + 3.6kΩ + 100Ω");
+        assert_eq!(best.value.to_string(), "13.7kΩ");
+        assert_eq!(best.deviation, Decimal::ZERO);
+        // Only one 1k and one 3.6k in stock
+        let uses = |c: &Combination, name: &str| c.parts.iter().filter(|p| p.part == name).count();
+        assert!(matches
+            .combinations
+            .iter()
+            .all(|c| uses(c, "R1k") <= 1 && uses(c, "R3k6") <= 1));
+    }
+
+    #[test]
+    fn test_capacitor_combinations() {
+        // Arrange
+        let stock = vec![stocked("C100n", "100nF", 10), stocked("C47n", "47nF", 10)];
+        let mut query = query(PassiveKind::Capacitor, "50nF");
+        query.max_parts = 2;
+        query.quantity = 5;
+
+        // Act
+        let matches = match_values(&query, stock);
+
+        // Assert
+        let found: Vec<_> = matches
+            .combinations
+            .iter()
+            .map(|c| (c.to_string(), c.value.to_string()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![("100nF + 100nF".to_string(), "50nF".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_nearest_values_in_stock() {
+        // Arrange
+        let mgr = Manager::in_memory();
+        mgr.category()
+            .add(&crate::Category::new("Inductors", None, None))
+            .unwrap();
+        for (name, value, quantity) in [("L1", "10uH", 5), ("L2", "4.7uH", 5), ("L3", "22uH", 0)] {
+            let mut part = Part::new(name, "Inductors", quantity);
+            part.custom_fields = vec![CustomField {
+                field_type: CustomFieldType::Parameter,
+                name: "Inductance".to_string(),
+                value: value.to_string(),
+            }];
+            mgr.part().add(&part).unwrap();
+        }
+        let query = query(PassiveKind::Inductor, "15uH");
+
+        // Act
+        let matches = mgr.part().nearest_values(&query).expect("Expected OK");
+
+        // Assert
+        assert!(matches.nearest.is_empty());
+        let found: Vec<_> = matches.combinations.iter().map(|c| c.to_string()).collect();
+        assert_eq!(found[0], "10µH + (10µH ∥ 10µH)");
+        assert!(found.contains(&"10µH + 4.7µH".to_string()));
+        assert!(found.iter().all(|c| !c.contains("22µH")));
+    }
+}
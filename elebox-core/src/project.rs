@@ -0,0 +1,455 @@
+use crate::{
+    comm::*,
+    errors::*,
+    jamm_db::*,
+    kicad::{match_lines, read_kicad, BomImportReport},
+    ledger::StockReason,
+    part::DELETED_ITEM,
+    report::{bom_cost, BomCost},
+    PartHandler,
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Debug, path::PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct BomItem {
+    pub part: String,
+    /// Quantity per board.
+    pub quantity: u16,
+}
+
+impl BomItem {
+    pub fn new(part: &str, quantity: u16) -> Self {
+        Self {
+            part: part.to_string(),
+            quantity,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Project {
+    pub name: String,
+    pub description: Option<String>,
+    pub bom: Vec<BomItem>,
+}
+
+impl Project {
+    pub fn new(name: &str, description: Option<&str>, bom: Vec<BomItem>) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.map(|s| s.to_string()),
+            bom,
+        }
+    }
+}
+
+/// A part that is not in stock enough for the builds.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Shortage {
+    pub part: String,
+    pub required: u32,
+    pub available: u16,
+}
+
+pub struct ProjectHandler<'a> {
+    pub(crate) db: &'a dyn Database<DbProject>,
+    pub(crate) part: PartHandler<'a>,
+}
+
+impl ProjectHandler<'_> {
+    fn to_db_item(&self, item: &Project) -> Result<DbProject, EleboxError> {
+        let mut bom = vec![];
+        for bom_item in &item.bom {
+            let part_id = match self.part.db.get_id(&bom_item.part) {
+                Ok(id) => id,
+                Err(DbError::NotExists(_)) => {
+                    return Err(EleboxError::NotExists(
+                        String::from(ITEM_PART),
+                        bom_item.part.clone(),
+                    ))
+                }
+                Err(err) => return Err(EleboxError::DatabaseError(err)),
+            };
+            bom.push(DbBomItem {
+                part_id,
+                quantity: bom_item.quantity,
+            });
+        }
+
+        Ok(DbProject {
+            name: item.name.to_string(),
+            description: item.description.as_deref().unwrap_or("").to_string(),
+            bom,
+        })
+    }
+
+    fn to_item(&self, db_item: DbProject) -> Result<Project, EleboxError> {
+        let mut bom = vec![];
+        for db_bom_item in db_item.bom {
+            let part = match self.part.db.get(&db_bom_item.part_id) {
+                Ok(item) => item.name,
+                Err(DbError::NotExists(_)) => String::from(DELETED_ITEM),
+                Err(err) => return Err(EleboxError::DatabaseError(err)),
+            };
+            bom.push(BomItem::new(&part, db_bom_item.quantity));
+        }
+
+        Ok(Project {
+            name: db_item.name,
+            description: Some(db_item.description).filter(|s| !s.is_empty()),
+            bom,
+        })
+    }
+
+    /// The parts of the project with their quantity per board, summed over
+    /// the BOM lines of each part, and their stock. Deleted parts have no
+    /// stock.
+    fn stock(&self, name: &str) -> Result<Vec<(u32, Option<DbPart>)>, EleboxError> {
+        let id = self.db.get_id(name)?;
+        let project = self.db.get(&id)?;
+
+        let mut quantities: Vec<(Id, u32)> = vec![];
+        for bom_item in project.bom {
+            match quantities
+                .iter_mut()
+                .find(|(id, _)| *id == bom_item.part_id)
+            {
+                Some((_, quantity)) => *quantity += bom_item.quantity as u32,
+                None => quantities.push((bom_item.part_id, bom_item.quantity as u32)),
+            }
+        }
+
+        let mut items = vec![];
+        for (part_id, quantity) in quantities {
+            let part = match self.part.db.get(&part_id) {
+                Ok(part) => Some(part),
+                Err(DbError::NotExists(_)) => None,
+                Err(err) => return Err(EleboxError::DatabaseError(err)),
+            };
+            items.push((quantity, part));
+        }
+        Ok(items)
+    }
+
+    /// How many boards can be built from the current stock, 0 if the BOM
+    /// is empty.
+    pub fn buildable(&self, name: &str) -> Result<u16, EleboxError> {
+        let items = self.stock(name)?;
+        let boards = items
+            .iter()
+            .filter(|(quantity, _)| *quantity > 0)
+            .map(|(quantity, part)| {
+                let available = part.as_ref().map_or(0, |part| part.quantity);
+                (available as u32 / quantity) as u16
+            })
+            .min();
+        Ok(boards.unwrap_or(0))
+    }
+
+    /// Parts without enough stock to build `boards` boards.
+    pub fn shortages(&self, name: &str, boards: u16) -> Result<Vec<Shortage>, EleboxError> {
+        let items = self.stock(name)?;
+        Ok(items
+            .into_iter()
+            .filter_map(|(quantity, part)| {
+                let required = quantity.saturating_mul(boards as u32);
+                let (part, available) = match part {
+                    Some(part) => (part.name, part.quantity),
+                    None => (String::from(DELETED_ITEM), 0),
+                };
+                (required > available as u32).then_some(Shortage {
+                    part,
+                    required,
+                    available,
+                })
+            })
+            .collect())
+    }
+
+    /// Cost of the project per board and in total when building `boards`
+    /// boards, using the price breaks of the quantity for all of them.
+    pub fn cost(&self, name: &str, boards: u16) -> Result<BomCost, EleboxError> {
+        let project = self.get(name)?;
+        let parts = self.part.list()?;
+        Ok(bom_cost(&project, &parts, boards))
+    }
+
+    /// Read a KiCad BOM CSV or XML netlist and match its lines to the parts,
+    /// see `BomImportReport::to_project()` to save it as a project.
+    pub fn import_kicad(&self, filename: &PathBuf) -> Result<BomImportReport, EleboxError> {
+        let lines = read_kicad(filename)?;
+        let parts = self.part.list()?;
+        Ok(match_lines(lines, &parts))
+    }
+
+    /// Take the parts of `boards` boards from the stock in one transaction,
+    /// nothing is taken if any part is short. The uses are recorded in the
+    /// ledger under the project.
+    pub fn build(&self, name: &str, boards: u16) -> Result<(), EleboxError> {
+        if let Some(shortage) = self.shortages(name, boards)?.first() {
+            return Err(EleboxError::InventoryShortage(shortage.part.clone()));
+        }
+
+        let items = self.stock(name)?;
+        let note = format!("Build x{boards}");
+        in_transaction(self.part.db, self.part.ledger_db, || {
+            for (quantity, part) in items {
+                let Some(part) = part else { continue };
+                let required = quantity.saturating_mul(boards as u32);
+                if required == 0 {
+                    continue;
+                }
+
+                let delta = i16::try_from(required)
+                    .map_err(|_| EleboxError::InventoryShortage(part.name.clone()))?;
+                self.part.move_stock(
+                    &part.name,
+                    -delta,
+                    None,
+                    StockReason::Use,
+                    Some(&note),
+                    Some(name),
+                )?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl Handler<Project> for ProjectHandler<'_> {
+    fn delete(&self, name: &str) -> Result<(), EleboxError> {
+        let id = self.db.get_id(name)?;
+        self.db.delete(&id)?;
+        Ok(())
+    }
+
+    fn add(&self, item: &Project) -> Result<(), EleboxError> {
+        if self.db.get_id(&item.name).is_ok() {
+            return Err(EleboxError::AlreadyExists(
+                String::from(ITEM_PROJECT),
+                item.name.clone(),
+            ));
+        }
+
+        let db_item = self.to_db_item(item)?;
+        self.db.add(&db_item)?;
+        Ok(())
+    }
+
+    fn update(&self, ori_name: &str, new_item: &Project) -> Result<(), EleboxError> {
+        let ori_id = self.db.get_id(ori_name)?;
+
+        if ori_name != new_item.name && self.db.get_id(&new_item.name).is_ok() {
+            return Err(EleboxError::AlreadyExists(
+                String::from(ITEM_PROJECT),
+                new_item.name.clone(),
+            ));
+        }
+
+        let db_item = self.to_db_item(new_item)?;
+        self.db.update(&ori_id, &db_item)?;
+        Ok(())
+    }
+
+    fn get(&self, name: &str) -> Result<Project, EleboxError> {
+        let id = self.db.get_id(name)?;
+        let db_item = self.db.get(&id)?;
+        self.to_item(db_item)
+    }
+
+    fn list(&self) -> Result<Vec<Project>, EleboxError> {
+        let db_items = self.db.list()?;
+        db_items
+            .into_iter()
+            .map(|db_item| self.to_item(db_item))
+            .collect()
+    }
+}
+
+impl Transferable for ProjectHandler<'_> {
+    fn export(&self, filename: &PathBuf) -> Result<(), EleboxError> {
+        write_items(filename, self.list()?)
+    }
+
+    fn import(&self, filename: &PathBuf) -> Result<(), EleboxError> {
+        let items: Vec<Project> = read_items(filename)?;
+        for item in items {
+            if let Err(e) = self.add(&item) {
+                match e {
+                    EleboxError::AlreadyExists(_, _) => continue,
+                    others => return Err(others),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Category, Manager, Part};
+    use uuid::Uuid;
+
+    fn new_manager() -> Manager {
+        let mgr = Manager::in_memory();
+        mgr.category()
+            .add(&Category::new("Passives", None, None))
+            .unwrap();
+        mgr.part().add(&Part::new("100nF", "Passives", 50)).unwrap();
+        mgr.part().add(&Part::new("10k", "Passives", 7)).unwrap();
+        mgr.project()
+            .add(&Project::new(
+                "Clock",
+                None,
+                vec![BomItem::new("100nF", 10), BomItem::new("10k", 2)],
+            ))
+            .unwrap();
+        mgr
+    }
+
+    #[test]
+    fn test_buildable() {
+        // Arrange
+        let mgr = new_manager();
+
+        // Act
+        let boards = mgr.project().buildable("Clock").expect("Expected OK");
+        let shortages = mgr.project().shortages("Clock", 4).expect("Expected OK");
+
+        // Assert
+        assert_eq!(boards, 3);
+        assert_eq!(
+            shortages,
+            vec![Shortage {
+                part: "10k".to_string(),
+                required: 8,
+                available: 7,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_buildable_repeated_part() {
+        // Arrange
+        let mgr = new_manager();
+        let bom = vec![
+            BomItem::new("10k", 2),
+            BomItem::new("100nF", 1),
+            BomItem::new("10k", 2),
+        ];
+        mgr.project().add(&Project::new("Amp", None, bom)).unwrap();
+
+        // Act
+        let boards = mgr.project().buildable("Amp").expect("Expected OK");
+        let shortages = mgr.project().shortages("Amp", 2).expect("Expected OK");
+
+        // Assert
+        assert_eq!(boards, 1);
+        assert_eq!(
+            shortages,
+            vec![Shortage {
+                part: "10k".to_string(),
+                required: 8,
+                available: 7,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_build_consumes_stock() {
+        // Arrange
+        let mgr = new_manager();
+
+        // Act
+        mgr.project().build("Clock", 2).expect("Expected OK");
+
+        // Assert
+        assert_eq!(mgr.part().get("100nF").unwrap().quantity, 30);
+        assert_eq!(mgr.part().get("10k").unwrap().quantity, 3);
+        let entries = mgr.ledger().list_part("10k").unwrap();
+        assert_eq!(entries.last().unwrap().delta, -4);
+        assert_eq!(entries.last().unwrap().project.as_deref(), Some("Clock"));
+    }
+
+    #[test]
+    fn test_build_shortage() {
+        // Arrange
+        let mgr = new_manager();
+
+        // Act
+        let result = mgr.project().build("Clock", 4);
+
+        // Assert
+        assert!(matches!(result, Err(EleboxError::InventoryShortage(_))));
+        assert_eq!(mgr.part().get("100nF").unwrap().quantity, 50);
+        assert_eq!(mgr.part().get("10k").unwrap().quantity, 7);
+    }
+
+    #[test]
+    fn test_delete_used_part() {
+        // Arrange
+        let mgr = new_manager();
+
+        // Act
+        let result = mgr.part().delete("10k");
+
+        // Assert
+        assert!(matches!(
+            result,
+            Err(EleboxError::StillReferenced(_, _, ref users)) if users == &["Clock"]
+        ));
+        assert!(mgr.part().get("10k").is_ok());
+    }
+
+    #[test]
+    fn test_add_unknown_part() {
+        // Arrange
+        let mgr = new_manager();
+        let project = Project::new("Amp", None, vec![BomItem::new("NE5532", 1)]);
+
+        // Act
+        let result = mgr.project().add(&project);
+
+        // Assert
+        assert!(matches!(result, Err(EleboxError::NotExists(_, _))));
+    }
+
+    #[test]
+    fn test_import_invalid_file() {
+        // Arrange
+        let mgr = new_manager();
+        let dir = std::env::temp_dir().join(format!("elebox_test_{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("Expected OK");
+
+        // Act
+        let wrong_ext = mgr.project().export(&dir.join("projects.txt"));
+        let missing = mgr.project().import(&dir.join("missing.yaml"));
+
+        // Assert
+        assert!(matches!(wrong_ext, Err(EleboxError::InvalidFile(_, _))));
+        assert!(matches!(missing, Err(EleboxError::InvalidFile(_, _))));
+        std::fs::remove_dir_all(&dir).expect("Expected OK");
+    }
+
+    #[test]
+    fn test_export_import() {
+        // Arrange
+        let mgr = new_manager();
+        let dir = std::env::temp_dir().join(format!("elebox_test_{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("Expected OK");
+        let filename = dir.join("projects.json");
+
+        // Act
+        mgr.project().export(&filename).expect("Expected OK");
+        mgr.project().delete("Clock").expect("Expected OK");
+        mgr.project().import(&filename).expect("Expected OK");
+
+        // Assert
+        let project = mgr.project().get("Clock").expect("Expected OK");
+        assert_eq!(project.bom.len(), 2);
+        std::fs::remove_dir_all(&dir).expect("Expected OK");
+    }
+}
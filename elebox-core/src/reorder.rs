@@ -0,0 +1,249 @@
+use crate::{errors::*, Part};
+use csv::WriterBuilder;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs::File, path::PathBuf};
+
+/// A part at or below its minimum stock.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ReorderItem {
+    pub part: String,
+    pub mfr: Option<String>,
+    pub mfr_no: Option<String>,
+    pub quantity: u16,
+    pub min_stock: u16,
+    /// Outstanding on open orders.
+    pub on_order: u32,
+    /// Quantity to order, at least the minimum order of the supplier in
+    /// whole packs.
+    pub order_quantity: u32,
+    /// Order number, link and unit price at the supplier.
+    pub sku: Option<String>,
+    pub link: Option<String>,
+    pub price: Option<Decimal>,
+    pub currency: Option<String>,
+}
+
+/// The parts to order from one supplier, `None` for parts without any.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ReorderGroup {
+    pub supplier: Option<String>,
+    pub items: Vec<ReorderItem>,
+}
+
+/// CSV column layouts accepted by the bulk add or BOM upload of distributors.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum BulkAddFormat {
+    DigiKey,
+    Mouser,
+    Lcsc,
+}
+
+impl BulkAddFormat {
+    fn header(&self) -> [&'static str; 4] {
+        match self {
+            BulkAddFormat::DigiKey => [
+                "Quantity",
+                "Part Number",
+                "Manufacturer Part Number",
+                "Customer Reference",
+            ],
+            BulkAddFormat::Mouser => [
+                "Mouser Part Number",
+                "Mfr Part Number",
+                "Quantity",
+                "Customer Part Number",
+            ],
+            BulkAddFormat::Lcsc => [
+                "Quantity",
+                "LCSC Part Number",
+                "Manufacture Part Number",
+                "Customer Part Number",
+            ],
+        }
+    }
+
+    /// The order number at the supplier and the manufacturer part number go
+    /// in their own columns, either can be empty.
+    fn record(&self, item: &ReorderItem) -> [String; 4] {
+        let qty = item.order_quantity.to_string();
+        let sku = item.sku.clone().unwrap_or_default();
+        let mfr_no = item.mfr_no.clone().unwrap_or_default();
+        let reference = item.part.clone();
+        match self {
+            BulkAddFormat::DigiKey | BulkAddFormat::Lcsc => [qty, sku, mfr_no, reference],
+            BulkAddFormat::Mouser => [sku, mfr_no, qty, reference],
+        }
+    }
+}
+
+/// Parts at or below their minimum stock, counting the quantity `on_order`
+/// by part name, grouped by their preferred supplier, which is the first one
+/// listed. Groups are sorted by supplier name with the parts without a
+/// supplier last.
+pub(crate) fn reorder_groups(
+    parts: &[Part],
+    on_order: &BTreeMap<String, u32>,
+) -> Vec<ReorderGroup> {
+    let mut groups: Vec<ReorderGroup> = vec![];
+
+    for part in parts {
+        let ordered = on_order.get(&part.name).copied().unwrap_or(0);
+        let Some(min_stock) = part
+            .min_stock
+            .filter(|min| part.quantity as u32 + ordered <= *min as u32)
+        else {
+            continue;
+        };
+
+        let supplier = part.suppliers.first();
+        let wanted = part.reorder_quantity.unwrap_or(min_stock).max(1) as u32;
+        let order_quantity = supplier.map_or(wanted, |s| s.order_quantity(wanted));
+        let item = ReorderItem {
+            part: part.name.clone(),
+            mfr: part.mfr.clone(),
+            mfr_no: part.mfr_no.clone().filter(|s| !s.is_empty()),
+            quantity: part.quantity,
+            min_stock,
+            on_order: ordered,
+            order_quantity,
+            sku: supplier.and_then(|s| s.sku.clone()),
+            link: supplier.and_then(|s| s.link.clone()),
+            price: supplier.and_then(|s| s.unit_price(order_quantity)),
+            currency: supplier.and_then(|s| s.currency.clone()),
+        };
+
+        let name = supplier.map(|s| s.supplier.clone());
+        match groups.iter_mut().find(|g| g.supplier == name) {
+            Some(group) => group.items.push(item),
+            None => groups.push(ReorderGroup {
+                supplier: name,
+                items: vec![item],
+            }),
+        }
+    }
+
+    groups.sort_by(|a, b| {
+        let no_supplier = a.supplier.is_none().cmp(&b.supplier.is_none());
+        no_supplier.then_with(|| a.supplier.cmp(&b.supplier))
+    });
+    groups
+}
+
+/// Write the items as a CSV file for the bulk add of a distributor, with the
+/// part name as the customer reference.
+pub fn write_bulk_add(
+    filename: &PathBuf,
+    items: &[ReorderItem],
+    format: BulkAddFormat,
+) -> Result<(), EleboxError> {
+    let file = File::create(filename).map_err(DbError::from)?;
+    let mut writer = WriterBuilder::new().from_writer(file);
+
+    let invalid = |err: csv::Error| {
+        EleboxError::InvalidFile(filename.to_string_lossy().into_owned(), err.to_string())
+    };
+    writer.write_record(format.header()).map_err(invalid)?;
+    for item in items {
+        writer.write_record(format.record(item)).map_err(invalid)?;
+    }
+    writer.flush().map_err(DbError::from)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PartSupplier, PriceBreak};
+    use std::{env, fs};
+    use uuid::Uuid;
+
+    fn new_part(name: &str, quantity: u16, min_stock: Option<u16>, supplier: Option<&str>) -> Part {
+        let mut part = Part::new(name, "Resistors", quantity);
+        part.min_stock = min_stock;
+        part.mfr_no = Some(format!("MPN-{name}"));
+        if let Some(supplier) = supplier {
+            let mut offer = PartSupplier::new(supplier);
+            offer.price_breaks = vec![PriceBreak {
+                quantity: 1,
+                price: Decimal::new(1, 2),
+            }];
+            part.suppliers = vec![offer];
+        }
+        part
+    }
+
+    #[test]
+    fn test_reorder_groups() {
+        // Arrange
+        let mut reel = new_part("10k", 100, Some(200), Some("Mouser"));
+        reel.reorder_quantity = Some(4000);
+        reel.suppliers[0].pack_size = Some(5000);
+        let parts = vec![
+            new_part("1k", 10, Some(10), Some("Mouser")),
+            reel,
+            new_part("100R", 0, Some(5), None),
+            new_part("4k7", 50, Some(20), Some("DigiKey")),
+            new_part("22R", 3, None, Some("DigiKey")),
+            new_part("47k", 1, Some(2), Some("DigiKey")),
+        ];
+
+        // Act
+        let groups = reorder_groups(&parts, &BTreeMap::new());
+
+        // Assert
+        let suppliers: Vec<_> = groups.iter().map(|g| g.supplier.as_deref()).collect();
+        assert_eq!(suppliers, vec![Some("DigiKey"), Some("Mouser"), None]);
+        assert_eq!(groups[0].items.len(), 1);
+        assert_eq!(groups[0].items[0].part, "47k");
+        assert_eq!(groups[1].items[0].order_quantity, 10);
+        assert_eq!(groups[1].items[1].order_quantity, 5000);
+        assert_eq!(groups[1].items[1].price, Some(Decimal::new(1, 2)));
+        assert_eq!(groups[2].items[0].order_quantity, 5);
+    }
+
+    #[test]
+    fn test_reorder_on_order() {
+        // Arrange
+        let parts = vec![
+            new_part("1k", 2, Some(10), Some("Mouser")),
+            new_part("10k", 2, Some(10), Some("Mouser")),
+        ];
+        let on_order = BTreeMap::from([("1k".to_string(), 9), ("10k".to_string(), 5)]);
+
+        // Act
+        let groups = reorder_groups(&parts, &on_order);
+
+        // Assert
+        assert_eq!(groups[0].items.len(), 1);
+        assert_eq!(groups[0].items[0].part, "10k");
+        assert_eq!(groups[0].items[0].on_order, 5);
+    }
+
+    #[test]
+    fn test_write_bulk_add() {
+        // Arrange
+        let filename = env::temp_dir().join(format!("elebox_test_{}.csv", Uuid::new_v4()));
+        let mut groups = reorder_groups(
+            &[
+                new_part("1k", 1, Some(10), Some("Mouser")),
+                new_part("10k", 1, Some(10), Some("Mouser")),
+            ],
+            &BTreeMap::new(),
+        );
+        groups[0].items[1].sku = Some("603-RC0603FR-0710KL".to_string());
+
+        // Act
+        write_bulk_add(&filename, &groups[0].items, BulkAddFormat::Mouser).expect("Expected OK");
+        let content = fs::read_to_string(&filename).unwrap();
+        let _ = fs::remove_file(&filename);
+
+        // Assert
+        assert_eq!(
+            content,
+            "Mouser Part Number,Mfr Part Number,Quantity,Customer Part Number\n\
+             ,MPN-1k,10,1k\n\
+             603-RC0603FR-0710KL,MPN-10k,10,10k\n"
+        );
+    }
+}
@@ -0,0 +1,341 @@
+use crate::{errors::*, part::DELETED_ITEM, Part, PartSupplier, Project};
+use csv::WriterBuilder;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::{fmt, fs::File, path::PathBuf};
+
+/// A sum of money, `currency` is `None` for prices without a currency code.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Amount {
+    pub value: Decimal,
+    pub currency: Option<String>,
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.currency {
+            Some(currency) => write!(f, "{} {}", self.value, currency),
+            None => write!(f, "{}", self.value),
+        }
+    }
+}
+
+/// Add `value` to the total of its currency, prices in different currencies
+/// are never added up.
+fn add_amount(totals: &mut Vec<Amount>, value: Decimal, currency: &Option<String>) {
+    match totals.iter_mut().find(|t| t.currency == *currency) {
+        Some(total) => total.value += value,
+        None => totals.push(Amount {
+            value,
+            currency: currency.clone(),
+        }),
+    }
+}
+
+/// Unit price of the preferred supplier, the first one with prices, when
+/// buying `quantity`. Below the first price break its price is used.
+fn unit_price(part: &Part, quantity: u32) -> Option<(&PartSupplier, Decimal)> {
+    let offer = part.suppliers.iter().find(|s| !s.price_breaks.is_empty())?;
+    let price = offer
+        .unit_price(offer.order_quantity(quantity))
+        .or_else(|| {
+            let first = offer.price_breaks.iter().min_by_key(|b| b.quantity)?;
+            Some(first.price)
+        })?;
+    Some((offer, price))
+}
+
+/// Value of the stock of one part.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct StockValue {
+    pub part: String,
+    pub category: String,
+    pub quantity: u16,
+    /// `None` if the part has no price.
+    pub unit_price: Option<Decimal>,
+    pub currency: Option<String>,
+    pub value: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CategoryValue {
+    pub category: String,
+    /// By currency.
+    pub totals: Vec<Amount>,
+    /// Parts in stock without a price, not in the totals.
+    pub unpriced: Vec<String>,
+}
+
+/// Value of the stock on hand at the prices of the preferred suppliers.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Valuation {
+    pub parts: Vec<StockValue>,
+    /// Sorted by category name, parts count in their own category only.
+    pub categories: Vec<CategoryValue>,
+    pub totals: Vec<Amount>,
+}
+
+pub(crate) fn valuation(parts: &[Part]) -> Valuation {
+    let mut values = vec![];
+    let mut categories: Vec<CategoryValue> = vec![];
+    let mut totals = vec![];
+
+    for part in parts.iter().filter(|p| p.quantity > 0) {
+        let price = unit_price(part, part.quantity as u32);
+        let value = StockValue {
+            part: part.name.clone(),
+            category: part.category.clone(),
+            quantity: part.quantity,
+            unit_price: price.map(|(_, price)| price),
+            currency: price.and_then(|(offer, _)| offer.currency.clone()),
+            value: price.map(|(_, price)| price * Decimal::from(part.quantity)),
+        };
+
+        let index = match categories.iter().position(|c| c.category == part.category) {
+            Some(index) => index,
+            None => {
+                categories.push(CategoryValue {
+                    category: part.category.clone(),
+                    totals: vec![],
+                    unpriced: vec![],
+                });
+                categories.len() - 1
+            }
+        };
+        match value.value {
+            Some(amount) => {
+                add_amount(&mut categories[index].totals, amount, &value.currency);
+                add_amount(&mut totals, amount, &value.currency);
+            }
+            None => categories[index].unpriced.push(part.name.clone()),
+        }
+        values.push(value);
+    }
+
+    categories.sort_by(|a, b| a.category.cmp(&b.category));
+    Valuation {
+        parts: values,
+        categories,
+        totals,
+    }
+}
+
+/// Cost of one BOM line.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct BomCostLine {
+    pub part: String,
+    pub per_board: u16,
+    /// Quantity for all the boards, the prices are those of this quantity.
+    pub required: u32,
+    pub supplier: Option<String>,
+    /// `None` if the part has no price.
+    pub unit_price: Option<Decimal>,
+    pub currency: Option<String>,
+    /// Cost of the line on one board.
+    pub board_cost: Option<Decimal>,
+}
+
+/// Cost of a project when building `boards` boards, so the price breaks of
+/// the whole build apply to each board.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct BomCost {
+    pub project: String,
+    pub boards: u16,
+    pub lines: Vec<BomCostLine>,
+    /// By currency.
+    pub per_board: Vec<Amount>,
+    pub total: Vec<Amount>,
+    /// Parts without a price, not in the totals.
+    pub unpriced: Vec<String>,
+}
+
+pub(crate) fn bom_cost(project: &Project, parts: &[Part], boards: u16) -> BomCost {
+    let mut lines = vec![];
+    let mut per_board = vec![];
+    let mut total = vec![];
+    let mut unpriced = vec![];
+
+    for bom_item in project.bom.iter().filter(|i| i.quantity > 0) {
+        let required = bom_item.quantity as u32 * boards.max(1) as u32;
+        let part = parts.iter().find(|p| p.name == bom_item.part);
+        let price = part.and_then(|part| unit_price(part, required));
+
+        let line = BomCostLine {
+            part: bom_item.part.clone(),
+            per_board: bom_item.quantity,
+            required,
+            supplier: price.map(|(offer, _)| offer.supplier.clone()),
+            unit_price: price.map(|(_, price)| price),
+            currency: price.and_then(|(offer, _)| offer.currency.clone()),
+            board_cost: price.map(|(_, price)| price * Decimal::from(bom_item.quantity)),
+        };
+
+        match line.board_cost {
+            Some(cost) => {
+                add_amount(&mut per_board, cost, &line.currency);
+                add_amount(&mut total, cost * Decimal::from(boards), &line.currency);
+            }
+            None if bom_item.part != DELETED_ITEM => unpriced.push(bom_item.part.clone()),
+            None => {}
+        }
+        lines.push(line);
+    }
+
+    BomCost {
+        project: project.name.clone(),
+        boards,
+        lines,
+        per_board,
+        total,
+        unpriced,
+    }
+}
+
+fn write_csv<const N: usize>(
+    filename: &PathBuf,
+    header: [&str; N],
+    records: impl Iterator<Item = [String; N]>,
+) -> Result<(), EleboxError> {
+    let file = File::create(filename).map_err(DbError::from)?;
+    let mut writer = WriterBuilder::new().from_writer(file);
+
+    let invalid = |err: csv::Error| {
+        EleboxError::InvalidFile(filename.to_string_lossy().into_owned(), err.to_string())
+    };
+    writer.write_record(header).map_err(invalid)?;
+    for record in records {
+        writer.write_record(record).map_err(invalid)?;
+    }
+    writer.flush().map_err(DbError::from)?;
+    Ok(())
+}
+
+fn text(value: &Option<impl ToString>) -> String {
+    value.as_ref().map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// Write the value of each part as a CSV file.
+pub fn write_valuation(filename: &PathBuf, valuation: &Valuation) -> Result<(), EleboxError> {
+    let header = [
+        "Part",
+        "Category",
+        "Quantity",
+        "Unit Price",
+        "Currency",
+        "Value",
+    ];
+    let records = valuation.parts.iter().map(|v| {
+        [
+            v.part.clone(),
+            v.category.clone(),
+            v.quantity.to_string(),
+            text(&v.unit_price),
+            text(&v.currency),
+            text(&v.value),
+        ]
+    });
+    write_csv(filename, header, records)
+}
+
+/// Write the cost of each BOM line as a CSV file.
+pub fn write_bom_cost(filename: &PathBuf, cost: &BomCost) -> Result<(), EleboxError> {
+    let header = [
+        "Part",
+        "Per Board",
+        "Required",
+        "Supplier",
+        "Unit Price",
+        "Currency",
+        "Board Cost",
+    ];
+    let records = cost.lines.iter().map(|l| {
+        [
+            l.part.clone(),
+            l.per_board.to_string(),
+            l.required.to_string(),
+            text(&l.supplier),
+            text(&l.unit_price),
+            text(&l.currency),
+            text(&l.board_cost),
+        ]
+    });
+    write_csv(filename, header, records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BomItem, PriceBreak};
+
+    fn new_part(name: &str, category: &str, quantity: u16, prices: &[(u32, i64)]) -> Part {
+        let mut part = Part::new(name, category, quantity);
+        if !prices.is_empty() {
+            let mut offer = PartSupplier::new("DigiKey");
+            offer.currency = Some("USD".to_string());
+            offer.price_breaks = prices
+                .iter()
+                .map(|&(quantity, cents)| PriceBreak {
+                    quantity,
+                    price: Decimal::new(cents, 2),
+                })
+                .collect();
+            part.suppliers = vec![offer];
+        }
+        part
+    }
+
+    #[test]
+    fn test_valuation_by_category() {
+        // Arrange
+        let parts = vec![
+            new_part("10k", "Resistors", 200, &[(1, 10), (100, 2)]),
+            new_part("1k", "Resistors", 10, &[(1, 10), (100, 2)]),
+            new_part("100nF", "Capacitors", 50, &[]),
+            new_part("1uF", "Capacitors", 0, &[(1, 50)]),
+        ];
+
+        // Act
+        let valuation = valuation(&parts);
+
+        // Assert
+        assert_eq!(valuation.parts.len(), 3);
+        assert_eq!(valuation.categories[0].category, "Capacitors");
+        assert!(valuation.categories[0].totals.is_empty());
+        assert_eq!(valuation.categories[0].unpriced, vec!["100nF"]);
+        assert_eq!(
+            valuation.categories[1].totals[0].value,
+            Decimal::new(500, 2)
+        );
+        assert_eq!(valuation.totals[0].to_string(), "5.00 USD");
+    }
+
+    #[test]
+    fn test_bom_cost_with_price_breaks() {
+        // Arrange
+        let parts = vec![
+            new_part("10k", "Resistors", 0, &[(1, 10), (100, 2)]),
+            new_part("MCU", "ARM", 0, &[(1, 100)]),
+        ];
+        let project = Project::new(
+            "Clock",
+            None,
+            vec![
+                BomItem::new("10k", 4),
+                BomItem::new("MCU", 1),
+                BomItem::new("LED", 2),
+            ],
+        );
+
+        // Act
+        let one = bom_cost(&project, &parts, 1);
+        let fifty = bom_cost(&project, &parts, 50);
+
+        // Assert
+        assert_eq!(one.per_board[0].value, Decimal::new(140, 2));
+        assert_eq!(fifty.lines[0].required, 200);
+        assert_eq!(fifty.lines[0].unit_price, Some(Decimal::new(2, 2)));
+        assert_eq!(fifty.per_board[0].value, Decimal::new(108, 2));
+        assert_eq!(fifty.total[0].value, Decimal::new(5400, 2));
+        assert_eq!(fifty.unpriced, vec!["LED"]);
+    }
+}
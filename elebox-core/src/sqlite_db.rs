@@ -0,0 +1,860 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use rusqlite::{params_from_iter, types::Value, Connection, ErrorCode, OptionalExtension, Row};
+use uuid::Uuid;
+
+use crate::{comm::*, jamm_db::*, DbError};
+
+/// File extensions treated as SQLite databases.
+pub const SQLITE_EXTENSIONS: [&str; 3] = ["sqlite", "sqlite3", "db3"];
+
+/// Returns `true` if the path looks like a SQLite database file.
+pub fn is_sqlite_path(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|ext| SQLITE_EXTENSIONS.iter().any(|e| ext == *e))
+}
+
+/// Mapping between a database item and the columns of its SQLite table.
+///
+/// The table is named after `DatabaseItem::get_bucket()`, and always has an
+/// extra `id` primary key column which is not listed in `get_columns()`.
+pub trait SqliteItem: Sized {
+    /// Column names and SQL types, in the order used by `to_row()`.
+    fn get_columns() -> Vec<(&'static str, &'static str)>;
+    fn to_row(&self) -> Result<Vec<Value>, serde_json::Error>;
+    fn from_row(row: &Row) -> rusqlite::Result<Self>;
+
+    /// Columns renamed since the table was created, as `(old, new)`. Added
+    /// columns need no entry, but a default if they are `NOT NULL`.
+    fn renamed_columns() -> Vec<(&'static str, &'static str)> {
+        vec![]
+    }
+}
+
+fn text(s: &str) -> Value {
+    Value::Text(s.to_string())
+}
+
+fn to_json<T: serde::Serialize>(item: &T) -> Result<Value, serde_json::Error> {
+    serde_json::to_string(item).map(Value::Text)
+}
+
+fn from_json<T: for<'de> serde::Deserialize<'de>>(row: &Row, column: &str) -> rusqlite::Result<T> {
+    let idx = row.as_ref().column_index(column)?;
+    let json: String = row.get(idx)?;
+    serde_json::from_str(&json).map_err(|err| {
+        rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, Box::new(err))
+    })
+}
+
+impl SqliteItem for DbPart {
+    fn get_columns() -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("name", "TEXT NOT NULL UNIQUE"),
+            ("quantity", "INTEGER NOT NULL"),
+            ("category_id", "TEXT NOT NULL"),
+            ("package_id", "TEXT NOT NULL"),
+            ("package_detail", "TEXT NOT NULL"),
+            ("mfr_id", "TEXT NOT NULL"),
+            ("alias", "TEXT NOT NULL"),
+            ("description", "TEXT NOT NULL"),
+            ("location_id", "TEXT NOT NULL"),
+            ("mfr_no", "TEXT NOT NULL"),
+            ("datasheet_link", "TEXT NOT NULL"),
+            ("product_link", "TEXT NOT NULL"),
+            ("image_link", "TEXT NOT NULL"),
+            ("custom_fields", "TEXT NOT NULL"), // JSON array
+            ("suppliers", "TEXT NOT NULL"),     // JSON array
+            ("starred", "INTEGER NOT NULL"),
+            ("min_stock", "INTEGER"),
+            ("reorder_quantity", "INTEGER"),
+            ("stock", "TEXT NOT NULL DEFAULT '[]'"), // JSON array
+        ]
+    }
+
+    fn to_row(&self) -> Result<Vec<Value>, serde_json::Error> {
+        Ok(vec![
+            text(&self.name),
+            Value::Integer(self.quantity as i64),
+            text(&self.category_id),
+            text(&self.package_id),
+            text(&self.package_detail),
+            text(&self.mfr_id),
+            text(&self.alias),
+            text(&self.description),
+            text(&self.location_id),
+            text(&self.mfr_no),
+            text(&self.datasheet_link),
+            text(&self.product_link),
+            text(&self.image_link),
+            to_json(&self.custom_fields)?,
+            to_json(&self.suppliers)?,
+            Value::Integer(self.starred as i64),
+            self.min_stock
+                .map_or(Value::Null, |q| Value::Integer(q as i64)),
+            self.reorder_quantity
+                .map_or(Value::Null, |q| Value::Integer(q as i64)),
+            to_json(&self.stock)?,
+        ])
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            name: row.get("name")?,
+            quantity: row.get("quantity")?,
+            category_id: row.get("category_id")?,
+            package_id: row.get("package_id")?,
+            package_detail: row.get("package_detail")?,
+            mfr_id: row.get("mfr_id")?,
+            alias: row.get("alias")?,
+            description: row.get("description")?,
+            location_id: row.get("location_id")?,
+            mfr_no: row.get("mfr_no")?,
+            datasheet_link: row.get("datasheet_link")?,
+            product_link: row.get("product_link")?,
+            image_link: row.get("image_link")?,
+            custom_fields: from_json(row, "custom_fields")?,
+            suppliers: from_json(row, "suppliers")?,
+            starred: row.get("starred")?,
+            min_stock: row.get("min_stock")?,
+            reorder_quantity: row.get("reorder_quantity")?,
+            stock: from_json(row, "stock")?,
+        })
+    }
+
+    fn renamed_columns() -> Vec<(&'static str, &'static str)> {
+        vec![("location", "location_id")]
+    }
+}
+
+impl SqliteItem for DbLocation {
+    fn get_columns() -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("name", "TEXT NOT NULL UNIQUE"),
+            ("parent_id", "TEXT NOT NULL"),
+            ("alias", "TEXT NOT NULL"),
+        ]
+    }
+
+    fn to_row(&self) -> Result<Vec<Value>, serde_json::Error> {
+        Ok(vec![
+            text(&self.name),
+            text(&self.parent_id),
+            text(&self.alias),
+        ])
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            name: row.get("name")?,
+            parent_id: row.get("parent_id")?,
+            alias: row.get("alias")?,
+        })
+    }
+}
+
+impl SqliteItem for DbSupplier {
+    fn get_columns() -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("name", "TEXT NOT NULL UNIQUE"),
+            ("alias", "TEXT NOT NULL"),
+            ("url", "TEXT NOT NULL"),
+        ]
+    }
+
+    fn to_row(&self) -> Result<Vec<Value>, serde_json::Error> {
+        Ok(vec![text(&self.name), text(&self.alias), text(&self.url)])
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            name: row.get("name")?,
+            alias: row.get("alias")?,
+            url: row.get("url")?,
+        })
+    }
+}
+
+impl SqliteItem for DbFieldDef {
+    fn get_columns() -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("name", "TEXT NOT NULL UNIQUE"),
+            ("field_type", "TEXT NOT NULL"), // JSON
+            ("description", "TEXT NOT NULL"),
+        ]
+    }
+
+    fn to_row(&self) -> Result<Vec<Value>, serde_json::Error> {
+        Ok(vec![
+            text(&self.name),
+            to_json(&self.field_type)?,
+            text(&self.description),
+        ])
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            name: row.get("name")?,
+            field_type: from_json(row, "field_type")?,
+            description: row.get("description")?,
+        })
+    }
+}
+
+impl SqliteItem for DbCategory {
+    fn get_columns() -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("name", "TEXT NOT NULL UNIQUE"),
+            ("parent_id", "TEXT NOT NULL"),
+            ("alias", "TEXT NOT NULL"),
+            ("parameters", "TEXT NOT NULL DEFAULT '[]'"), // JSON array
+        ]
+    }
+
+    fn to_row(&self) -> Result<Vec<Value>, serde_json::Error> {
+        Ok(vec![
+            text(&self.name),
+            text(&self.parent_id),
+            text(&self.alias),
+            to_json(&self.parameters)?,
+        ])
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            name: row.get("name")?,
+            parent_id: row.get("parent_id")?,
+            alias: row.get("alias")?,
+            parameters: from_json(row, "parameters")?,
+        })
+    }
+}
+
+impl SqliteItem for DbPackage {
+    fn get_columns() -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("name", "TEXT NOT NULL UNIQUE"),
+            ("pkg_type", "TEXT NOT NULL"),
+            ("alias", "TEXT NOT NULL"),
+        ]
+    }
+
+    fn to_row(&self) -> Result<Vec<Value>, serde_json::Error> {
+        Ok(vec![
+            text(&self.name),
+            text(&self.pkg_type),
+            text(&self.alias),
+        ])
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            name: row.get("name")?,
+            pkg_type: row.get("pkg_type")?,
+            alias: row.get("alias")?,
+        })
+    }
+}
+
+impl SqliteItem for DbManufacturer {
+    fn get_columns() -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("name", "TEXT NOT NULL UNIQUE"),
+            ("alias", "TEXT NOT NULL"),
+            ("url", "TEXT NOT NULL"),
+        ]
+    }
+
+    fn to_row(&self) -> Result<Vec<Value>, serde_json::Error> {
+        Ok(vec![text(&self.name), text(&self.alias), text(&self.url)])
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            name: row.get("name")?,
+            alias: row.get("alias")?,
+            url: row.get("url")?,
+        })
+    }
+}
+
+impl SqliteItem for DbLedgerEntry {
+    fn get_columns() -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("part_id", "TEXT NOT NULL"),
+            ("timestamp", "INTEGER NOT NULL"),
+            ("delta", "INTEGER NOT NULL"),
+            ("reason", "TEXT NOT NULL"),
+            ("note", "TEXT NOT NULL"),
+            ("project", "TEXT NOT NULL"),
+        ]
+    }
+
+    fn to_row(&self) -> Result<Vec<Value>, serde_json::Error> {
+        Ok(vec![
+            text(&self.part_id),
+            Value::Integer(self.timestamp),
+            Value::Integer(self.delta as i64),
+            text(&self.reason),
+            text(&self.note),
+            text(&self.project),
+        ])
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            part_id: row.get("part_id")?,
+            timestamp: row.get("timestamp")?,
+            delta: row.get("delta")?,
+            reason: row.get("reason")?,
+            note: row.get("note")?,
+            project: row.get("project")?,
+        })
+    }
+}
+
+impl SqliteItem for DbProject {
+    fn get_columns() -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("name", "TEXT NOT NULL UNIQUE"),
+            ("description", "TEXT NOT NULL"),
+            ("bom", "TEXT NOT NULL"), // JSON array
+        ]
+    }
+
+    fn to_row(&self) -> Result<Vec<Value>, serde_json::Error> {
+        Ok(vec![
+            text(&self.name),
+            text(&self.description),
+            to_json(&self.bom)?,
+        ])
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            name: row.get("name")?,
+            description: row.get("description")?,
+            bom: from_json(row, "bom")?,
+        })
+    }
+}
+
+impl SqliteItem for DbOrder {
+    fn get_columns() -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("name", "TEXT NOT NULL UNIQUE"),
+            ("supplier_id", "TEXT NOT NULL"),
+            ("status", "TEXT NOT NULL"),
+            ("note", "TEXT NOT NULL"),
+            ("lines", "TEXT NOT NULL"), // JSON array
+        ]
+    }
+
+    fn to_row(&self) -> Result<Vec<Value>, serde_json::Error> {
+        Ok(vec![
+            text(&self.name),
+            text(&self.supplier_id),
+            text(&self.status),
+            text(&self.note),
+            to_json(&self.lines)?,
+        ])
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            name: row.get("name")?,
+            supplier_id: row.get("supplier_id")?,
+            status: row.get("status")?,
+            note: row.get("note")?,
+            lines: from_json(row, "lines")?,
+        })
+    }
+}
+
+/// SQLite backend, every item type is stored in its own table with one
+/// column per field, so the database can be queried by other tools.
+///
+/// Cloning is cheap and shares the same connection.
+#[derive(Clone)]
+pub struct SqliteDatabase {
+    conn: Arc<Mutex<Connection>>,
+    tx: Arc<TxLock>,
+}
+
+impl SqliteDatabase {
+    /// Open or create a SQLite database file, use `":memory:"` for a temporary one.
+    pub fn open(path: &str) -> Result<Self, DbError> {
+        let conn = Connection::open(path).map_err(|_| DbError::CannotOpenDb(path.to_string()))?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+            tx: Arc::new(TxLock::default()),
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Connection> {
+        // The connection is shared, so other threads wait for the
+        // transaction to end rather than joining it.
+        // A poisoned lock only means another thread panicked mid-call,
+        // SQLite itself keeps the file consistent.
+        self.tx
+            .outside(|| self.conn.lock())
+            .unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// End a level of the transaction, returns whether the outermost one committed.
+    fn end(&self, commit: bool) -> Result<Option<bool>, DbError> {
+        self.tx.end(commit, |commit| {
+            let sql = if commit { "COMMIT" } else { "ROLLBACK" };
+            self.lock().execute_batch(sql)?;
+            Ok(())
+        })
+    }
+}
+
+fn column_names<DI: SqliteItem>() -> Vec<&'static str> {
+    DI::get_columns().iter().map(|(name, _)| *name).collect()
+}
+
+impl<DI> Database<DI> for SqliteDatabase
+where
+    DI: SqliteItem + DatabaseItem + Send + Sync,
+{
+    fn init(&self) -> Result<(), DbError> {
+        let columns: Vec<String> = DI::get_columns()
+            .iter()
+            .map(|(name, sql_type)| format!("{name} {sql_type}"))
+            .collect();
+
+        let sql = format!(
+            "CREATE TABLE IF NOT EXISTS {} (id TEXT PRIMARY KEY, {})",
+            DI::get_bucket(),
+            columns.join(", ")
+        );
+        let conn = self.lock();
+        conn.execute(&sql, []).map_err(table_error::<DI>)?;
+
+        // Tables created by older releases
+        let table = DI::get_bucket();
+        let has_column = |name: &str| -> rusqlite::Result<bool> {
+            let sql = format!("SELECT 1 FROM pragma_table_info('{table}') WHERE name = ?1");
+            let exists = conn.query_row(&sql, [name], |_| Ok(())).optional()?;
+            Ok(exists.is_some())
+        };
+        for (old, new) in DI::renamed_columns() {
+            if has_column(old)? {
+                conn.execute(
+                    &format!("ALTER TABLE {table} RENAME COLUMN {old} TO {new}"),
+                    [],
+                )?;
+            }
+        }
+        for (name, sql_type) in DI::get_columns() {
+            if !has_column(name)? {
+                conn.execute(
+                    &format!("ALTER TABLE {table} ADD COLUMN {name} {sql_type}"),
+                    [],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn get_id(&self, name: &str) -> Result<String, DbError> {
+        if !DI::is_named() {
+            return Err(DbError::NotExists(DI::get_bucket()));
+        }
+
+        let sql = format!("SELECT id FROM {} WHERE name = ?1", DI::get_bucket());
+        let id: Option<String> = self
+            .lock()
+            .query_row(&sql, [name], |row| row.get(0))
+            .optional()
+            .map_err(table_error::<DI>)?;
+        id.ok_or_else(|| DbError::NotExists(DI::get_bucket()))
+    }
+
+    fn get(&self, id: &str) -> Result<DI, DbError> {
+        let sql = format!(
+            "SELECT {} FROM {} WHERE id = ?1",
+            column_names::<DI>().join(", "),
+            DI::get_bucket()
+        );
+        let item = self
+            .lock()
+            .query_row(&sql, [id], |row| DI::from_row(row))
+            .optional()
+            .map_err(table_error::<DI>)?;
+        item.ok_or_else(|| DbError::NotExists(DI::get_bucket()))
+    }
+
+    fn list(&self) -> Result<Vec<DI>, DbError> {
+        let items = self.list_with_id()?;
+        Ok(items.into_iter().map(|(_, item)| item).collect())
+    }
+
+    fn list_with_id(&self) -> Result<Vec<(Id, DI)>, DbError> {
+        let items = self.scan()?;
+        items
+            .into_iter()
+            .map(|(id, item)| item.map(|item| (id, item)))
+            .collect()
+    }
+
+    fn scan(&self) -> Result<Scanned<DI>, DbError> {
+        let sql = format!(
+            "SELECT id, {} FROM {} ORDER BY id",
+            column_names::<DI>().join(", "),
+            DI::get_bucket()
+        );
+
+        let conn = self.lock();
+        let mut stmt = conn.prepare(&sql).map_err(table_error::<DI>)?;
+        let rows = stmt
+            .query_map([], |row| {
+                let item = DI::from_row(row).map_err(table_error::<DI>);
+                Ok((row.get("id")?, item))
+            })
+            .map_err(table_error::<DI>)?;
+
+        let mut items: Scanned<DI> = Vec::new();
+        for row in rows {
+            items.push(row.map_err(table_error::<DI>)?);
+        }
+        Ok(items)
+    }
+
+    fn add(&self, item: &DI) -> Result<(), DbError> {
+        let id = Uuid::new_v4().to_string();
+        self.update(&id, item)
+    }
+
+    /// Insert or replace the item with the given ID, same as `put` of jammdb.
+    fn update(&self, ori_id: &str, new_item: &DI) -> Result<(), DbError> {
+        let columns = column_names::<DI>();
+        let placeholders: Vec<String> = (2..=columns.len() + 1).map(|i| format!("?{i}")).collect();
+        let assignments: Vec<String> = columns
+            .iter()
+            .map(|c| format!("{c} = excluded.{c}"))
+            .collect();
+
+        let sql = format!(
+            "INSERT INTO {} (id, {}) VALUES (?1, {}) ON CONFLICT(id) DO UPDATE SET {}",
+            DI::get_bucket(),
+            columns.join(", "),
+            placeholders.join(", "),
+            assignments.join(", ")
+        );
+
+        let mut values = vec![text(ori_id)];
+        let row = new_item
+            .to_row()
+            .map_err(|err| DbError::Serialization(DI::get_bucket(), err.to_string()))?;
+        values.extend(row);
+        self.lock()
+            .execute(&sql, params_from_iter(values))
+            .map_err(table_error::<DI>)?;
+        Ok(())
+    }
+
+    fn delete(&self, id: &str) -> Result<(), DbError> {
+        let sql = format!("DELETE FROM {} WHERE id = ?1", DI::get_bucket());
+        let count = self.lock().execute(&sql, [id]).map_err(table_error::<DI>)?;
+
+        match count {
+            0 => Err(DbError::NotExists(DI::get_bucket())),
+            _ => Ok(()),
+        }
+    }
+
+    fn check(&self) -> Result<(), DbError> {
+        let table: Option<String> = self
+            .lock()
+            .query_row(
+                "SELECT name FROM sqlite_master WHERE type = 'table' AND name = ?1",
+                [DI::get_bucket()],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        match table {
+            Some(_) => Ok(()),
+            None => Err(DbError::BucketMissing(DI::get_bucket())),
+        }
+    }
+    fn get_version(&self) -> Result<u32, DbError> {
+        let version = self
+            .lock()
+            .query_row(
+                "SELECT version FROM meta WHERE bucket = ?1",
+                [DI::get_bucket()],
+                |row| row.get(0),
+            )
+            .optional();
+
+        match version {
+            Ok(version) => Ok(version.unwrap_or(0)),
+            // Databases created before versioning have no meta table
+            Err(rusqlite::Error::SqliteFailure(_, Some(msg)))
+                if msg.starts_with("no such table") =>
+            {
+                Ok(0)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn set_version(&self, version: u32) -> Result<(), DbError> {
+        let conn = self.lock();
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS meta (bucket TEXT PRIMARY KEY, version INTEGER NOT NULL)",
+            [],
+        )?;
+        conn.execute(
+            "INSERT INTO meta (bucket, version) VALUES (?1, ?2) \
+             ON CONFLICT(bucket) DO UPDATE SET version = excluded.version",
+            rusqlite::params![DI::get_bucket(), version],
+        )?;
+        Ok(())
+    }
+
+    fn begin(&self) -> Result<(), DbError> {
+        if self.tx.begin() {
+            if let Err(err) = self.lock().execute_batch("BEGIN") {
+                let _ = self.tx.end(false, |_| Ok(()));
+                return Err(err.into());
+            }
+        }
+        Ok(())
+    }
+
+    fn commit(&self) -> Result<(), DbError> {
+        match self.end(true)? {
+            Some(false) => Err(DbError::Transaction(
+                "rolled back by an inner transaction".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn rollback(&self) -> Result<(), DbError> {
+        self.end(false).map(|_| ())
+    }
+}
+
+/// Convert errors of statements on the table of the item type.
+fn table_error<DI: DatabaseItem>(err: rusqlite::Error) -> DbError {
+    match err {
+        rusqlite::Error::FromSqlConversionFailure(..) | rusqlite::Error::InvalidColumnType(..) => {
+            DbError::Serialization(DI::get_bucket(), err.to_string())
+        }
+        rusqlite::Error::SqliteFailure(_, Some(ref msg)) if msg.starts_with("no such table") => {
+            DbError::BucketMissing(DI::get_bucket())
+        }
+        others => DbError::from(others),
+    }
+}
+
+impl From<rusqlite::Error> for DbError {
+    fn from(err: rusqlite::Error) -> DbError {
+        match err.sqlite_error_code() {
+            Some(ErrorCode::DatabaseBusy) | Some(ErrorCode::DatabaseLocked) => {
+                DbError::Locked(err.to_string())
+            }
+            Some(ErrorCode::DatabaseCorrupt) | Some(ErrorCode::NotADatabase) => {
+                DbError::Corrupted(err.to_string())
+            }
+            _ => DbError::AccessFailed(format!("sqlite {}", err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    fn new_db() -> SqliteDatabase {
+        let db = SqliteDatabase::open(":memory:").expect("Expected OK");
+        Database::<DbManufacturer>::init(&db).expect("Expected OK");
+        db
+    }
+
+    #[test]
+    fn test_add_and_get() {
+        // Arrange
+        let db = new_db();
+        let mfr = DbManufacturer {
+            name: "TestName".to_string(),
+            alias: "TestAlias".to_string(),
+            url: "https://test.com".to_string(),
+        };
+
+        // Act
+        db.add(&mfr).expect("Expected OK");
+        let id = Database::<DbManufacturer>::get_id(&db, "TestName").expect("Expected OK");
+        let item: DbManufacturer = db.get(&id).expect("Expected OK");
+
+        // Assert
+        assert_eq!(item.name, mfr.name);
+        assert_eq!(item.alias, mfr.alias);
+        assert_eq!(item.url, mfr.url);
+    }
+
+    #[test]
+    fn test_get_not_existing() {
+        // Arrange
+        let db = new_db();
+
+        // Act
+        let result = Database::<DbManufacturer>::get_id(&db, "TestName");
+
+        // Assert
+        assert!(matches!(result, Err(DbError::NotExists(_))));
+    }
+
+    #[test]
+    fn test_update_keeps_id() {
+        // Arrange
+        let db = new_db();
+        let mut mfr = DbManufacturer {
+            name: "TestName".to_string(),
+            alias: "".to_string(),
+            url: "".to_string(),
+        };
+        db.add(&mfr).expect("Expected OK");
+        let id = Database::<DbManufacturer>::get_id(&db, "TestName").expect("Expected OK");
+
+        // Act
+        mfr.name = "NewName".to_string();
+        db.update(&id, &mfr).expect("Expected OK");
+
+        // Assert
+        let items: Vec<(Id, DbManufacturer)> = db.list_with_id().expect("Expected OK");
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].0, id);
+        assert_eq!(items[0].1.name, "NewName");
+    }
+
+    #[test]
+    fn test_part_nested_fields() {
+        // Arrange
+        let db = SqliteDatabase::open(":memory:").expect("Expected OK");
+        Database::<DbPart>::init(&db).expect("Expected OK");
+        let part = DbPart {
+            name: "TestPart".to_string(),
+            quantity: 10,
+            category_id: "CatID".to_string(),
+            package_id: "".to_string(),
+            package_detail: "".to_string(),
+            mfr_id: "".to_string(),
+            alias: "".to_string(),
+            description: "".to_string(),
+            location_id: "".to_string(),
+            mfr_no: "".to_string(),
+            datasheet_link: "".to_string(),
+            product_link: "".to_string(),
+            image_link: "".to_string(),
+            custom_fields: vec![CustomField {
+                field_type: CustomFieldType::Link,
+                name: "Field".to_string(),
+                value: "https://test.com".to_string(),
+            }],
+            suppliers: vec![DbPartSupplier {
+                supplier_id: "SupID".to_string(),
+                link: "".to_string(),
+                price_breaks: vec![PriceBreak {
+                    quantity: 10,
+                    price: Decimal::new(15, 1),
+                }],
+                note: "".to_string(),
+                sku: "SKU-1".to_string(),
+                min_order: Some(10),
+                pack_size: None,
+                currency: "USD".to_string(),
+            }],
+            starred: true,
+            min_stock: None,
+            reorder_quantity: None,
+            stock: vec![DbStockLine {
+                location_id: "LocID".to_string(),
+                quantity: 10,
+            }],
+        };
+
+        // Act
+        db.add(&part).expect("Expected OK");
+        let items: Vec<DbPart> = db.list().expect("Expected OK");
+
+        // Assert
+        assert_eq!(items, vec![part]);
+    }
+
+    #[test]
+    fn test_init_upgrades_columns() {
+        // Arrange, the parts table before locations were records
+        let db = SqliteDatabase::open(":memory:").expect("Expected OK");
+        db.lock()
+            .execute_batch(
+                "CREATE TABLE parts (id TEXT PRIMARY KEY, name TEXT NOT NULL UNIQUE,
+                quantity INTEGER NOT NULL, category_id TEXT NOT NULL, package_id TEXT NOT NULL,
+                package_detail TEXT NOT NULL, mfr_id TEXT NOT NULL, alias TEXT NOT NULL,
+                description TEXT NOT NULL, location TEXT NOT NULL, mfr_no TEXT NOT NULL,
+                datasheet_link TEXT NOT NULL, product_link TEXT NOT NULL,
+                image_link TEXT NOT NULL, custom_fields TEXT NOT NULL,
+                suppliers TEXT NOT NULL, starred INTEGER NOT NULL);
+                INSERT INTO parts VALUES ('ID', 'TestPart', 1, '', '', '', '', '', '',
+                'Drawer A3', '', '', '', '', '[]',
+                '[{\"name\":\"Mouser\",\"link\":\"\",\"price\":0.8,\"note\":\"\"}]', 0);",
+            )
+            .unwrap();
+
+        // Act
+        let result = Database::<DbPart>::init(&db);
+        let again = Database::<DbPart>::init(&db);
+        let part: DbPart = db.get("ID").expect("Expected OK");
+
+        // Assert
+        assert!(result.is_ok());
+        assert!(again.is_ok());
+        assert_eq!(part.location_id, "Drawer A3");
+        assert!(part.stock.is_empty());
+        assert_eq!(part.suppliers[0].supplier_id, "Mouser");
+        assert_eq!(part.suppliers[0].price_breaks[0].price, Decimal::new(8, 1));
+    }
+
+    #[test]
+    fn test_is_sqlite_path() {
+        assert!(is_sqlite_path("elebox.sqlite"));
+        assert!(is_sqlite_path("dir/elebox.db3"));
+        assert!(!is_sqlite_path("elebox.db"));
+        assert!(!is_sqlite_path("elebox"));
+    }
+
+    #[test]
+    fn test_transaction_rollback() {
+        // Arrange
+        let db = new_db();
+        let mfr = DbManufacturer {
+            name: "TestName".to_string(),
+            alias: "".to_string(),
+            url: "".to_string(),
+        };
+
+        // Act, every clone joins the same transaction
+        Database::<DbManufacturer>::begin(&db).expect("Expected OK");
+        Database::<DbManufacturer>::begin(&db.clone()).expect("Expected OK");
+        db.add(&mfr).expect("Expected OK");
+        Database::<DbManufacturer>::commit(&db.clone()).expect("Expected OK");
+        let staged_result = Database::<DbManufacturer>::get_id(&db, "TestName");
+        Database::<DbManufacturer>::rollback(&db).expect("Expected OK");
+        let result = Database::<DbManufacturer>::get_id(&db, "TestName");
+
+        // Assert
+        assert!(staged_result.is_ok());
+        assert!(matches!(result, Err(DbError::NotExists(_))));
+    }
+}
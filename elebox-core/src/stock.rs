@@ -0,0 +1,223 @@
+use crate::{errors::EleboxError, jamm_db::*};
+use serde::{Deserialize, Serialize};
+
+/// Quantity of a part kept in one location, e.g. a reel in the storeroom
+/// and a cut strip on the bench.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct StockLine {
+    pub location: Option<String>,
+    pub quantity: u16,
+}
+
+impl StockLine {
+    pub fn new(location: Option<&str>, quantity: u16) -> Self {
+        Self {
+            location: location.map(|l| l.to_string()),
+            quantity,
+        }
+    }
+}
+
+impl DbPart {
+    /// Stock by location, parts stored by older releases have all their
+    /// stock in their location.
+    pub(crate) fn stock_lines(&self) -> Vec<DbStockLine> {
+        if self.stock.is_empty() && self.quantity > 0 {
+            return vec![DbStockLine {
+                location_id: self.location_id.clone(),
+                quantity: self.quantity,
+            }];
+        }
+        self.stock.clone()
+    }
+
+    /// Replace the stock lines, empty ones are dropped and lines of the same
+    /// location merged. The quantity is kept as the total, the stock is
+    /// unchanged if the total does not fit.
+    pub(crate) fn set_stock(&mut self, lines: Vec<DbStockLine>) -> Result<(), EleboxError> {
+        let overflow = || EleboxError::StockOverflow(self.name.clone());
+        let mut merged: Vec<DbStockLine> = vec![];
+        for line in lines.into_iter().filter(|l| l.quantity > 0) {
+            match merged
+                .iter_mut()
+                .find(|m| m.location_id == line.location_id)
+            {
+                Some(m) => {
+                    m.quantity = m.quantity.checked_add(line.quantity).ok_or_else(overflow)?
+                }
+                None => merged.push(line),
+            }
+        }
+
+        let quantity = merged
+            .iter()
+            .try_fold(0u16, |sum, l| sum.checked_add(l.quantity))
+            .ok_or_else(overflow)?;
+        self.quantity = quantity;
+        self.stock = merged;
+        Ok(())
+    }
+
+    pub(crate) fn put_stock(
+        &mut self,
+        location_id: &str,
+        quantity: u16,
+    ) -> Result<(), EleboxError> {
+        let mut lines = self.stock_lines();
+        lines.push(DbStockLine {
+            location_id: location_id.to_string(),
+            quantity,
+        });
+        self.set_stock(lines)
+    }
+
+    /// Take from the location, or if `None` from the part's location first
+    /// and then the others. Returns `InventoryShortage` and leaves the stock
+    /// unchanged if there is not enough.
+    pub(crate) fn take_stock(
+        &mut self,
+        location_id: Option<&str>,
+        quantity: u16,
+    ) -> Result<(), EleboxError> {
+        let mut lines = self.stock_lines();
+        match location_id {
+            Some(id) => lines.sort_by_key(|l| l.location_id != id),
+            None => lines.sort_by_key(|l| l.location_id != self.location_id),
+        }
+
+        let mut remaining = quantity;
+        for line in lines.iter_mut() {
+            if location_id.is_some_and(|id| line.location_id != id) {
+                break;
+            }
+            let taken = remaining.min(line.quantity);
+            line.quantity -= taken;
+            remaining -= taken;
+        }
+        if remaining > 0 {
+            return Err(EleboxError::InventoryShortage(self.name.clone()));
+        }
+
+        self.set_stock(lines)
+    }
+
+    /// Returns `true` if the part is in the location, or has stock there.
+    pub(crate) fn uses_location(&self, location_id: &str) -> bool {
+        self.location_id == location_id || self.stock.iter().any(|l| l.location_id == location_id)
+    }
+
+    /// Move everything in one location to another, e.g. `""` for none.
+    pub(crate) fn relocate(&mut self, from_id: &str, to_id: &str) -> Result<(), EleboxError> {
+        if self.location_id == from_id {
+            self.location_id = to_id.to_string();
+        }
+
+        let mut lines = self.stock_lines();
+        for line in lines.iter_mut().filter(|l| l.location_id == from_id) {
+            line.location_id = to_id.to_string();
+        }
+        self.set_stock(lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_part(quantity: u16, location_id: &str) -> DbPart {
+        DbPart {
+            name: "R1".to_string(),
+            quantity,
+            category_id: "".to_string(),
+            package_id: "".to_string(),
+            package_detail: "".to_string(),
+            mfr_id: "".to_string(),
+            alias: "".to_string(),
+            description: "".to_string(),
+            location_id: location_id.to_string(),
+            mfr_no: "".to_string(),
+            datasheet_link: "".to_string(),
+            product_link: "".to_string(),
+            image_link: "".to_string(),
+            custom_fields: vec![],
+            suppliers: vec![],
+            starred: false,
+            min_stock: None,
+            reorder_quantity: None,
+            stock: vec![],
+        }
+    }
+
+    #[test]
+    fn test_stock_lines_of_older_parts() {
+        // Arrange
+        let part = new_part(30, "ID_REEL");
+
+        // Act
+        let lines = part.stock_lines();
+
+        // Assert
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].location_id, "ID_REEL");
+        assert_eq!(lines[0].quantity, 30);
+    }
+
+    #[test]
+    fn test_take_from_own_location_first() {
+        // Arrange
+        let mut part = new_part(30, "ID_REEL");
+        part.put_stock("ID_BENCH", 5).unwrap();
+
+        // Act
+        let result = part.take_stock(None, 32);
+
+        // Assert
+        assert!(result.is_ok());
+        assert_eq!(part.quantity, 3);
+        assert_eq!(part.stock.len(), 1);
+        assert_eq!(part.stock[0].location_id, "ID_BENCH");
+    }
+
+    #[test]
+    fn test_take_from_location_shortage() {
+        // Arrange
+        let mut part = new_part(30, "ID_REEL");
+        part.put_stock("ID_BENCH", 5).unwrap();
+
+        // Act
+        let result = part.take_stock(Some("ID_BENCH"), 6);
+
+        // Assert
+        assert!(matches!(result, Err(EleboxError::InventoryShortage(_))));
+        assert_eq!(part.quantity, 35);
+    }
+
+    #[test]
+    fn test_relocate_merges_lines() {
+        // Arrange
+        let mut part = new_part(30, "ID_REEL");
+        part.put_stock("ID_BENCH", 5).unwrap();
+
+        // Act
+        part.relocate("ID_BENCH", "ID_REEL").expect("Expected OK");
+
+        // Assert
+        assert_eq!(part.quantity, 35);
+        assert_eq!(part.stock.len(), 1);
+        assert!(!part.uses_location("ID_BENCH"));
+    }
+
+    #[test]
+    fn test_put_stock_overflow() {
+        // Arrange
+        let mut part = new_part(30, "ID_REEL");
+
+        // Act
+        let result = part.put_stock("ID_BENCH", u16::MAX - 10);
+
+        // Assert
+        assert!(matches!(result, Err(EleboxError::StockOverflow(_))));
+        assert_eq!(part.quantity, 30);
+        assert!(part.stock.is_empty());
+    }
+}
@@ -0,0 +1,592 @@
+use crate::{comm::*, errors::*, jamm_db::*, Manager};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, fmt::Debug, path::PathBuf};
+
+/// A distributor or shop parts are bought from, e.g. DigiKey or Mouser.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Supplier {
+    pub name: String,
+    pub alias: Option<String>,
+    pub url: Option<String>,
+}
+
+impl Supplier {
+    pub fn new(name: &str, alias: Option<&str>, url: Option<&str>) -> Self {
+        Self {
+            name: name.to_string(),
+            alias: alias.map(|s| s.to_string()),
+            url: url.map(|s| s.to_string()),
+        }
+    }
+}
+
+/// Offer of a supplier for a part, with its order number and prices.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PartSupplier {
+    /// Supplier name, `name` in exports of older releases.
+    #[serde(alias = "name")]
+    pub supplier: String,
+    /// Order number of the part at the supplier.
+    #[serde(default)]
+    pub sku: Option<String>,
+    #[serde(default)]
+    pub link: Option<String>,
+    /// Minimum order quantity.
+    #[serde(default)]
+    pub min_order: Option<u32>,
+    /// The part is sold in multiples of it, e.g. a full reel.
+    #[serde(default)]
+    pub pack_size: Option<u32>,
+    /// ISO 4217 code of the prices, e.g. `USD`.
+    #[serde(default)]
+    pub currency: Option<String>,
+    /// A single `price` in exports of older releases.
+    #[serde(default, alias = "price", deserialize_with = "price_breaks")]
+    pub price_breaks: Vec<PriceBreak>,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+impl PartSupplier {
+    pub fn new(supplier: &str) -> Self {
+        Self {
+            supplier: supplier.to_string(),
+            sku: None,
+            link: None,
+            min_order: None,
+            pack_size: None,
+            currency: None,
+            price_breaks: vec![],
+            note: None,
+        }
+    }
+
+    /// Unit price when ordering `quantity`, `None` below the first break.
+    pub fn unit_price(&self, quantity: u32) -> Option<Decimal> {
+        self.price_breaks
+            .iter()
+            .filter(|b| b.quantity <= quantity)
+            .max_by_key(|b| b.quantity)
+            .map(|b| b.price)
+    }
+
+    /// Quantity to order for at least `quantity`, raised to the minimum
+    /// order and rounded up to whole packs.
+    pub fn order_quantity(&self, quantity: u32) -> u32 {
+        let quantity = quantity.max(self.min_order.unwrap_or(0));
+        match self.pack_size.filter(|&size| size > 1) {
+            Some(size) => quantity.div_ceil(size).saturating_mul(size),
+            None => quantity,
+        }
+    }
+}
+
+/// ID of the supplier, names are matched ignoring case so "DigiKey" and
+/// "Digikey" are the same supplier.
+pub(crate) fn find_supplier_id(db: &dyn Database<DbSupplier>, name: &str) -> Result<Id, DbError> {
+    if let Ok(id) = db.get_id(name) {
+        return Ok(id);
+    }
+
+    let name = name.trim();
+    db.list_with_id()?
+        .into_iter()
+        .find(|(_, sup)| sup.name.eq_ignore_ascii_case(name))
+        .map(|(id, _)| id)
+        .ok_or_else(|| DbError::NotExists(DbSupplier::get_bucket()))
+}
+
+/// ID of the supplier, it is added if it does not exist yet.
+pub(crate) fn find_or_add_supplier(
+    db: &dyn Database<DbSupplier>,
+    name: &str,
+) -> Result<Id, DbError> {
+    match find_supplier_id(db, name) {
+        Err(DbError::NotExists(_)) => {
+            db.add(&DbSupplier {
+                name: name.trim().to_string(),
+                alias: "".to_string(),
+                url: "".to_string(),
+            })?;
+            db.get_id(name.trim())
+        }
+        other => other,
+    }
+}
+
+/// Version 3 of the parts, older releases stored the supplier names in the
+/// offers instead of the IDs of supplier records. Turn the names into records.
+pub(crate) fn migrate_suppliers(mgr: &Manager) -> Result<(), EleboxError> {
+    let db = &*mgr.supplier_db;
+    // IDs of records are kept, anything else is the old name
+    let ids: BTreeSet<Id> = db.list_with_id()?.into_iter().map(|(id, _)| id).collect();
+    let is_name = |id: &Id| !id.trim().is_empty() && !ids.contains(id);
+
+    for (part_id, mut part) in mgr.part_db.list_with_id()? {
+        if !part.suppliers.iter().any(|s| is_name(&s.supplier_id)) {
+            continue;
+        }
+
+        for sup in part.suppliers.iter_mut() {
+            if is_name(&sup.supplier_id) {
+                sup.supplier_id = find_or_add_supplier(db, &sup.supplier_id)?;
+            }
+        }
+        mgr.part_db.update(&part_id, &part)?;
+    }
+    Ok(())
+}
+
+pub struct SupplierHandler<'a> {
+    pub(crate) db: &'a dyn Database<DbSupplier>,
+    pub(crate) part_db: &'a dyn Database<DbPart>,
+    pub(crate) order_db: &'a dyn Database<DbOrder>,
+}
+
+impl SupplierHandler<'_> {
+    fn to_db_item(&self, item: &Supplier) -> DbSupplier {
+        DbSupplier {
+            name: item.name.to_string(),
+            alias: item.alias.clone().unwrap_or_default(),
+            url: item.url.clone().unwrap_or_default(),
+        }
+    }
+
+    fn to_item(&self, db_item: &DbSupplier) -> Supplier {
+        Supplier::new(
+            &db_item.name,
+            Some(db_item.alias.as_str()).filter(|s| !s.is_empty()),
+            Some(db_item.url.as_str()).filter(|s| !s.is_empty()),
+        )
+    }
+
+    /// Returns `true` if another supplier has the name, ignoring case.
+    fn name_taken(&self, name: &str, except: Option<&str>) -> Result<bool, EleboxError> {
+        match find_supplier_id(self.db, name) {
+            Ok(id) => Ok(except != Some(id.as_str())),
+            Err(DbError::NotExists(_)) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Delete the supplier, the parts and orders that still use it are
+    /// handled by `policy`. `Cascade` removes the offers of the supplier from
+    /// the parts, the parts are kept, and deletes its received orders. Open
+    /// orders can only be reassigned.
+    pub fn delete_with(&self, name: &str, policy: &DeletePolicy) -> Result<(), EleboxError> {
+        let id = find_supplier_id(self.db, name)?;
+        let parts: Vec<(Id, DbPart)> = self
+            .part_db
+            .list_with_id()?
+            .into_iter()
+            .filter(|(_, part)| part.suppliers.iter().any(|s| s.supplier_id == id))
+            .collect();
+        let orders: Vec<(Id, DbOrder)> = self
+            .order_db
+            .list_with_id()?
+            .into_iter()
+            .filter(|(_, order)| order.supplier_id == id)
+            .collect();
+
+        let new_id = match policy {
+            DeletePolicy::Reassign(new_name) => Some(find_supplier_id(self.db, new_name)?),
+            _ => None,
+        };
+
+        // Reassigning to itself would keep the references
+        let restrict = *policy == DeletePolicy::Restrict || new_id.as_ref() == Some(&id);
+        let users: Vec<String> = if restrict {
+            let parts = parts.iter().map(|(_, part)| part.name.clone());
+            let orders = orders.iter().map(|(_, order)| order.name.clone());
+            parts.chain(orders).collect()
+        } else if new_id.is_none() {
+            orders
+                .iter()
+                .filter(|(_, order)| order.is_open())
+                .map(|(_, order)| order.name.clone())
+                .collect()
+        } else {
+            vec![]
+        };
+        if !users.is_empty() {
+            return Err(EleboxError::StillReferenced(
+                String::from(ITEM_SUPPLIER),
+                name.to_string(),
+                users,
+            ));
+        }
+
+        in_transaction(self.db, self.part_db, || {
+            in_transaction(self.order_db, self.part_db, || {
+                for (part_id, mut part) in parts {
+                    match &new_id {
+                        Some(new_id) => part
+                            .suppliers
+                            .iter_mut()
+                            .filter(|s| s.supplier_id == id)
+                            .for_each(|s| s.supplier_id = new_id.clone()),
+                        None => part.suppliers.retain(|s| s.supplier_id != id),
+                    }
+                    self.part_db.update(&part_id, &part)?;
+                }
+                for (order_id, mut order) in orders {
+                    match &new_id {
+                        Some(new_id) => {
+                            order.supplier_id = new_id.clone();
+                            self.order_db.update(&order_id, &order)?;
+                        }
+                        None => self.order_db.delete(&order_id)?,
+                    }
+                }
+                self.db.delete(&id)?;
+                Ok(())
+            })
+        })
+    }
+}
+
+impl Handler<Supplier> for SupplierHandler<'_> {
+    /// Delete the supplier, refused if any part or order still uses it.
+    fn delete(&self, name: &str) -> Result<(), EleboxError> {
+        self.delete_with(name, &DeletePolicy::Restrict)
+    }
+
+    fn add(&self, item: &Supplier) -> Result<(), EleboxError> {
+        if self.name_taken(&item.name, None)? {
+            return Err(EleboxError::AlreadyExists(
+                String::from(ITEM_SUPPLIER),
+                item.name.clone(),
+            ));
+        }
+
+        self.db.add(&self.to_db_item(item))?;
+        Ok(())
+    }
+
+    fn update(&self, ori_name: &str, new_item: &Supplier) -> Result<(), EleboxError> {
+        let ori_id = find_supplier_id(self.db, ori_name)?;
+
+        if self.name_taken(&new_item.name, Some(&ori_id))? {
+            return Err(EleboxError::AlreadyExists(
+                String::from(ITEM_SUPPLIER),
+                new_item.name.clone(),
+            ));
+        }
+
+        self.db.update(&ori_id, &self.to_db_item(new_item))?;
+        Ok(())
+    }
+
+    fn get(&self, name: &str) -> Result<Supplier, EleboxError> {
+        let id = find_supplier_id(self.db, name)?;
+        let db_item = self.db.get(&id)?;
+        Ok(self.to_item(&db_item))
+    }
+
+    fn list(&self) -> Result<Vec<Supplier>, EleboxError> {
+        let db_items = self.db.list()?;
+        Ok(db_items
+            .iter()
+            .map(|db_item| self.to_item(db_item))
+            .collect())
+    }
+}
+
+impl Transferable for SupplierHandler<'_> {
+    fn export(&self, filename: &PathBuf) -> Result<(), EleboxError> {
+        write_items(filename, self.list()?)
+    }
+
+    fn import(&self, filename: &PathBuf) -> Result<(), EleboxError> {
+        let items: Vec<Supplier> = read_items(filename)?;
+        for item in items {
+            if let Err(e) = self.add(&item) {
+                match e {
+                    EleboxError::AlreadyExists(_, _) => continue,
+                    others => return Err(others),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{migrate, Category, OrderLine, Part, PurchaseOrder};
+    use uuid::Uuid;
+
+    fn new_db_part() -> DbPart {
+        DbPart {
+            name: "10k".to_string(),
+            quantity: 0,
+            category_id: "".to_string(),
+            package_id: "".to_string(),
+            package_detail: "".to_string(),
+            mfr_id: "".to_string(),
+            alias: "".to_string(),
+            description: "".to_string(),
+            location_id: "".to_string(),
+            mfr_no: "".to_string(),
+            datasheet_link: "".to_string(),
+            product_link: "".to_string(),
+            image_link: "".to_string(),
+            custom_fields: vec![],
+            suppliers: vec![],
+            starred: false,
+            min_stock: None,
+            reorder_quantity: None,
+            stock: vec![],
+        }
+    }
+
+    fn new_manager() -> Manager {
+        let mgr = Manager::in_memory();
+        mgr.category()
+            .add(&Category::new("Resistors", None, None))
+            .unwrap();
+        mgr.supplier()
+            .add(&Supplier::new(
+                "DigiKey",
+                None,
+                Some("https://www.digikey.com"),
+            ))
+            .unwrap();
+        mgr.supplier()
+            .add(&Supplier::new("Mouser", None, None))
+            .unwrap();
+
+        let mut offer = PartSupplier::new("digikey");
+        offer.sku = Some("311-10.0KHRCT-ND".to_string());
+        let mut part = Part::new("10k", "Resistors", 100);
+        part.suppliers = vec![offer];
+        mgr.part().add(&part).unwrap();
+        mgr
+    }
+
+    fn new_offer() -> PartSupplier {
+        let mut offer = PartSupplier::new("DigiKey");
+        offer.min_order = Some(10);
+        offer.pack_size = Some(5);
+        offer.price_breaks = vec![
+            PriceBreak {
+                quantity: 10,
+                price: Decimal::new(10, 2),
+            },
+            PriceBreak {
+                quantity: 100,
+                price: Decimal::new(4, 2),
+            },
+        ];
+        offer
+    }
+
+    #[test]
+    fn test_unit_price() {
+        // Arrange
+        let offer = new_offer();
+
+        // Act & Assert
+        assert_eq!(offer.unit_price(1), None);
+        assert_eq!(offer.unit_price(10), Some(Decimal::new(10, 2)));
+        assert_eq!(offer.unit_price(99), Some(Decimal::new(10, 2)));
+        assert_eq!(offer.unit_price(1000), Some(Decimal::new(4, 2)));
+    }
+
+    #[test]
+    fn test_order_quantity() {
+        // Arrange
+        let offer = new_offer();
+
+        // Act & Assert
+        assert_eq!(offer.order_quantity(1), 10);
+        assert_eq!(offer.order_quantity(12), 15);
+        assert_eq!(offer.order_quantity(20), 20);
+    }
+
+    #[test]
+    fn test_add_same_name_ignoring_case() {
+        // Arrange
+        let mgr = new_manager();
+
+        // Act
+        let result = mgr.supplier().add(&Supplier::new("Digikey ", None, None));
+
+        // Assert
+        assert!(matches!(result, Err(EleboxError::AlreadyExists(_, _))));
+    }
+
+    #[test]
+    fn test_part_offer() {
+        // Arrange
+        let mgr = new_manager();
+        let mut part = Part::new("1k", "Resistors", 10);
+        part.suppliers = vec![PartSupplier::new("Farnell")];
+
+        // Act
+        let unknown = mgr.part().add(&part);
+        let ten_k = mgr.part().get("10k").expect("Expected OK");
+
+        // Assert
+        assert!(matches!(unknown, Err(EleboxError::NotExists(_, _))));
+        assert_eq!(ten_k.suppliers[0].supplier, "DigiKey");
+        assert_eq!(ten_k.suppliers[0].sku.as_deref(), Some("311-10.0KHRCT-ND"));
+    }
+
+    #[test]
+    fn test_delete_with_policy() {
+        // Arrange
+        let mgr = new_manager();
+
+        // Act
+        let restricted = mgr.supplier().delete("DigiKey");
+        mgr.supplier()
+            .delete_with("DigiKey", &DeletePolicy::Reassign("Mouser".to_string()))
+            .expect("Expected OK");
+        let reassigned = mgr.part().get("10k").expect("Expected OK");
+        mgr.supplier()
+            .delete_with("Mouser", &DeletePolicy::Cascade)
+            .expect("Expected OK");
+        let cascaded = mgr.part().get("10k").expect("Expected OK");
+
+        // Assert
+        assert!(matches!(
+            restricted,
+            Err(EleboxError::StillReferenced(_, _, _))
+        ));
+        assert_eq!(reassigned.suppliers[0].supplier, "Mouser");
+        assert_eq!(
+            reassigned.suppliers[0].sku.as_deref(),
+            Some("311-10.0KHRCT-ND")
+        );
+        assert!(cascaded.suppliers.is_empty());
+        assert!(mgr.supplier().list().expect("Expected OK").is_empty());
+    }
+
+    #[test]
+    fn test_delete_ordering_supplier() {
+        // Arrange
+        let mgr = new_manager();
+        let order =
+            |name: &str| PurchaseOrder::new(name, "DigiKey", vec![OrderLine::new("10k", 10)]);
+        mgr.order().add(&order("PO-1")).unwrap();
+        mgr.order().add(&order("PO-2")).unwrap();
+        mgr.order().send("PO-2").unwrap();
+        mgr.order().receive_all("PO-2", None).unwrap();
+
+        // Act
+        mgr.supplier()
+            .delete_with("Mouser", &DeletePolicy::Cascade)
+            .expect("Expected OK");
+        let restricted = mgr.supplier().delete("DigiKey");
+        let cascaded = mgr
+            .supplier()
+            .delete_with("DigiKey", &DeletePolicy::Cascade);
+        mgr.supplier()
+            .add(&Supplier::new("Farnell", None, None))
+            .unwrap();
+        mgr.supplier()
+            .delete_with("DigiKey", &DeletePolicy::Reassign("Farnell".to_string()))
+            .expect("Expected OK");
+
+        // Assert
+        assert!(matches!(
+            restricted,
+            Err(EleboxError::StillReferenced(_, _, ref users)) if users.contains(&"PO-2".to_string())
+        ));
+        assert!(matches!(
+            cascaded,
+            Err(EleboxError::StillReferenced(_, _, ref users)) if users == &["PO-1"]
+        ));
+        assert_eq!(mgr.order().get("PO-1").unwrap().supplier, "Farnell");
+        assert_eq!(mgr.order().get("PO-2").unwrap().supplier, "Farnell");
+    }
+
+    #[test]
+    fn test_export_import() {
+        // Arrange
+        let mgr = new_manager();
+        let other = Manager::in_memory();
+        let dir = std::env::temp_dir().join(format!("elebox_test_{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("Expected OK");
+        let filename = dir.join("suppliers.json");
+
+        // Act
+        mgr.supplier().export(&filename).expect("Expected OK");
+        other.supplier().import(&filename).expect("Expected OK");
+        let wrong_ext = other.supplier().import(&dir.join("suppliers.csv"));
+        let missing = other.supplier().import(&dir.join("missing.yaml"));
+
+        // Assert
+        let digikey = other.supplier().get("DigiKey").expect("Expected OK");
+        assert_eq!(digikey.url.as_deref(), Some("https://www.digikey.com"));
+        assert!(matches!(wrong_ext, Err(EleboxError::InvalidFile(_, _))));
+        assert!(matches!(missing, Err(EleboxError::InvalidFile(_, _))));
+        std::fs::remove_dir_all(&dir).expect("Expected OK");
+    }
+
+    #[test]
+    fn test_migrate_suppliers() {
+        // Arrange, offers of older releases by name and with a float price
+        let mgr = Manager::in_memory();
+        mgr.supplier()
+            .add(&Supplier::new("Mouser", None, None))
+            .unwrap();
+        let mouser = mgr.supplier_db.get_id("Mouser").unwrap();
+
+        let mut db_part = new_db_part();
+        db_part.suppliers = serde_json::from_str(
+            r#"[{"name": "mouser", "link": "", "price": 0.1, "note": "Cut tape"},
+                {"name": "LCSC", "link": "", "price": null, "note": ""}]"#,
+        )
+        .unwrap();
+        mgr.part_db.add(&db_part).unwrap();
+
+        let mut db_part = new_db_part();
+        db_part.name = "1k".to_string();
+        db_part.suppliers = vec![DbPartSupplier {
+            supplier_id: mouser.clone(),
+            ..mgr.part_db.list().unwrap()[0].suppliers[0].clone()
+        }];
+        mgr.part_db.add(&db_part).unwrap();
+
+        // Act
+        let count = migrate::<DbPart>(&mgr).expect("Expected OK");
+        let again = migrate::<DbPart>(&mgr).expect("Expected OK");
+        let offers = |name: &str| {
+            let id = mgr.part_db.get_id(name).unwrap();
+            mgr.part_db.get(&id).unwrap().suppliers
+        };
+
+        // Assert
+        assert!(count > 0);
+        assert_eq!(again, 0);
+        let migrated = offers("10k");
+        assert_eq!(migrated[0].supplier_id, mouser);
+        assert_eq!(migrated[0].price_breaks[0].quantity, 1);
+        assert_eq!(migrated[0].price_breaks[0].price, Decimal::new(1, 1));
+        let lcsc = mgr.supplier_db.get_id("LCSC").unwrap();
+        assert_eq!(migrated[1].supplier_id, lcsc);
+        assert!(migrated[1].price_breaks.is_empty());
+        assert_eq!(offers("1k")[0].supplier_id, mouser);
+        assert_eq!(mgr.supplier_db.list().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_decode_positional_offer() {
+        // Arrange, the positional offer of older releases
+        let value = rmp_serde::to_vec(&("Mouser", "", Some(0.8f32), "7' reel")).unwrap();
+
+        // Act
+        let offer: DbPartSupplier = rmp_serde::from_slice(&value).expect("Expected OK");
+
+        // Assert
+        assert_eq!(offer.supplier_id, "Mouser");
+        assert_eq!(offer.price_breaks[0].price, Decimal::new(8, 1));
+        assert_eq!(offer.note, "7' reel");
+        assert!(offer.sku.is_empty());
+    }
+}
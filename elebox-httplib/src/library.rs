@@ -0,0 +1,164 @@
+use elebox_core::{Category, Part};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Custom fields of a part that set the KiCad symbol and footprint, e.g.
+/// `Device:C` and `Capacitor_SMD:C_0402_1005Metric`.
+const SYMBOL_FIELD: &str = "Symbol";
+const FOOTPRINT_FIELD: &str = "Footprint";
+
+/// Items are identified by their name, encoded so it is a valid path segment.
+pub fn to_id(name: &str) -> String {
+    utf8_percent_encode(name, NON_ALPHANUMERIC).to_string()
+}
+
+pub fn from_id(id: &str) -> String {
+    percent_decode_str(id).decode_utf8_lossy().into_owned()
+}
+
+/// A category or part in the lists of the library.
+#[derive(Debug, Serialize)]
+pub struct LibraryItem {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LibraryField {
+    pub value: String,
+    pub visible: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LibraryPart {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "symbolIdStr")]
+    pub symbol_id: String,
+    pub exclude_from_bom: String,
+    pub exclude_from_board: String,
+    pub exclude_from_sim: String,
+    pub fields: BTreeMap<String, LibraryField>,
+}
+
+fn kicad_bool(value: bool) -> String {
+    String::from(if value { "True" } else { "False" })
+}
+
+pub fn category_item(category: &Category) -> LibraryItem {
+    LibraryItem {
+        id: to_id(&category.name),
+        name: category.name.clone(),
+        description: category.alias.clone().unwrap_or_default(),
+    }
+}
+
+pub fn part_item(part: &Part) -> LibraryItem {
+    LibraryItem {
+        id: to_id(&part.name),
+        name: part.name.clone(),
+        description: part.description.clone().unwrap_or_default(),
+    }
+}
+
+/// Map a part to the fields of a KiCad symbol, empty fields are left out.
+pub fn library_part(part: &Part) -> LibraryPart {
+    let custom = |name: &str| {
+        part.custom_fields
+            .iter()
+            .find(|field| field.name.eq_ignore_ascii_case(name))
+            .map(|field| field.value.clone())
+    };
+
+    let mut fields = BTreeMap::new();
+    let mut add = |name: &str, value: Option<String>, visible: bool| {
+        if let Some(value) = value.filter(|s| !s.is_empty()) {
+            let visible = kicad_bool(visible);
+            fields.insert(name.to_string(), LibraryField { value, visible });
+        }
+    };
+
+    add("value", Some(part.name.clone()), true);
+    add(
+        "footprint",
+        custom(FOOTPRINT_FIELD).or_else(|| part.package.clone()),
+        false,
+    );
+    add("datasheet", part.datasheet_link.clone(), false);
+    add("description", part.description.clone(), false);
+    add("keywords", part.alias.clone(), false);
+    add("Manufacturer", part.mfr.clone(), false);
+    add("MPN", part.mfr_no.clone(), false);
+    add("Package", part.package.clone(), false);
+    add("Location", part.location.clone(), false);
+    add("Stock", Some(part.quantity.to_string()), false);
+    for field in &part.custom_fields {
+        let name = field.name.as_str();
+        if !name.eq_ignore_ascii_case(SYMBOL_FIELD) && !name.eq_ignore_ascii_case(FOOTPRINT_FIELD) {
+            add(name, Some(field.value.clone()), false);
+        }
+    }
+
+    LibraryPart {
+        id: to_id(&part.name),
+        name: part.name.clone(),
+        symbol_id: custom(SYMBOL_FIELD).unwrap_or_default(),
+        exclude_from_bom: kicad_bool(false),
+        exclude_from_board: kicad_bool(false),
+        exclude_from_sim: kicad_bool(true),
+        fields,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use elebox_core::{CustomField, CustomFieldType};
+
+    #[test]
+    fn test_id_round_trip() {
+        // Arrange
+        let name = "100nF 0402/X7R";
+
+        // Act
+        let id = to_id(name);
+
+        // Assert
+        assert!(!id.contains('/'));
+        assert_eq!(from_id(&id), name);
+    }
+
+    #[test]
+    fn test_library_part_fields() {
+        // Arrange
+        let mut part = Part::new("100nF", "Capacitors", 50);
+        part.package = Some("0402".to_string());
+        part.mfr_no = Some("GRM155R71C104KA88D".to_string());
+        part.custom_fields = vec![
+            CustomField {
+                field_type: CustomFieldType::Normal,
+                name: "Symbol".to_string(),
+                value: "Device:C".to_string(),
+            },
+            CustomField {
+                field_type: CustomFieldType::Normal,
+                name: "Voltage".to_string(),
+                value: "16V".to_string(),
+            },
+        ];
+
+        // Act
+        let lib_part = library_part(&part);
+
+        // Assert
+        assert_eq!(lib_part.symbol_id, "Device:C");
+        assert_eq!(lib_part.fields["footprint"].value, "0402");
+        assert_eq!(lib_part.fields["MPN"].value, "GRM155R71C104KA88D");
+        assert_eq!(lib_part.fields["Voltage"].value, "16V");
+        assert_eq!(lib_part.fields["Stock"].value, "50");
+        assert!(!lib_part.fields.contains_key("Symbol"));
+        assert!(!lib_part.fields.contains_key("datasheet"));
+    }
+}
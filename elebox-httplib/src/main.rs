@@ -0,0 +1,149 @@
+use clap::Parser;
+use elebox_core::{EleboxError, Handler, Manager};
+use serde::Serialize;
+use serde_json::json;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+mod library;
+
+use library::*;
+
+/// Serve the inventory as a KiCad HTTP library, add it to KiCad with a
+/// `.kicad_httplib` file whose `root_url` points to this server.
+#[derive(Parser)]
+#[clap(author, version, about)]
+struct Cli {
+    /// Path to the database file
+    #[arg(default_value = "elebox.db")]
+    db_path: String,
+
+    /// Address to listen on
+    #[arg(short = 'a', long = "addr", default_value = "127.0.0.1:8080")]
+    addr: String,
+
+    /// Token that KiCad must send, as set in the `.kicad_httplib` file
+    #[arg(short = 't', long = "token")]
+    token: Option<String>,
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    // Checked once, the requests then read a database of the current schema
+    let checked = Manager::open(&cli.db_path)
+        .map_err(EleboxError::from)
+        .and_then(|mgr| mgr.check());
+    if let Err(err) = checked {
+        println!("Error: {err}");
+        return;
+    }
+
+    let server = match Server::http(&cli.addr) {
+        Ok(server) => server,
+        Err(err) => {
+            println!("Error: {err}");
+            return;
+        }
+    };
+    println!("Serving {} at http://{}/", cli.db_path, cli.addr);
+
+    for request in server.incoming_requests() {
+        let (status, body) = if !authorized(&request, cli.token.as_deref()) {
+            (401, json!({ "error": "invalid token" }).to_string())
+        } else if *request.method() != Method::Get {
+            (405, json!({ "error": "only GET is supported" }).to_string())
+        } else {
+            route(&cli.db_path, request.url())
+        };
+
+        let header = Header::from_bytes("Content-Type", "application/json").unwrap();
+        let response = Response::from_string(body)
+            .with_status_code(status)
+            .with_header(header);
+        if let Err(err) = request.respond(response) {
+            println!("Error: {err}");
+        }
+    }
+}
+
+fn authorized(request: &Request, token: Option<&str>) -> bool {
+    let Some(token) = token else {
+        return true;
+    };
+    let expected = format!("Token {token}");
+    request
+        .headers()
+        .iter()
+        .any(|header| header.field.equiv("Authorization") && header.value.as_str() == expected)
+}
+
+/// Respond with the status code and JSON body of the endpoint.
+fn route(db_path: &str, url: &str) -> (u16, String) {
+    let path = url.split('?').next().unwrap_or("");
+    let Some((_, endpoint)) = path.split_once("/v1") else {
+        return not_found();
+    };
+    let endpoint = endpoint.trim_start_matches('/');
+
+    if endpoint.is_empty() {
+        return (200, json!({ "categories": "", "parts": "" }).to_string());
+    }
+
+    // The database is opened for each request, so the CLI can use it in between
+    let manager = match Manager::open(db_path) {
+        Ok(mgr) => mgr,
+        Err(err) => return (500, json!({ "error": err.to_string() }).to_string()),
+    };
+
+    let result = if endpoint == "categories.json" {
+        categories(&manager)
+    } else if let Some(id) = strip_json(endpoint, "parts/category/") {
+        parts_in(&manager, &from_id(id))
+    } else if let Some(id) = strip_json(endpoint, "parts/") {
+        part(&manager, &from_id(id))
+    } else {
+        return not_found();
+    };
+
+    match result {
+        Ok(body) => (200, body),
+        Err(EleboxError::NotExists(_, _)) => not_found(),
+        Err(err) => (500, json!({ "error": err.to_string() }).to_string()),
+    }
+}
+
+fn not_found() -> (u16, String) {
+    (404, json!({ "error": "not found" }).to_string())
+}
+
+/// The ID in `<prefix><id>.json`.
+fn strip_json<'a>(endpoint: &'a str, prefix: &str) -> Option<&'a str> {
+    endpoint.strip_prefix(prefix)?.strip_suffix(".json")
+}
+
+fn to_json<T: Serialize>(value: &T) -> String {
+    // Only plain structs of strings are serialized, this cannot fail
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+fn categories(manager: &Manager) -> Result<String, EleboxError> {
+    let categories = manager.category().list()?;
+    let items: Vec<LibraryItem> = categories.iter().map(category_item).collect();
+    Ok(to_json(&items))
+}
+
+fn parts_in(manager: &Manager, category: &str) -> Result<String, EleboxError> {
+    manager.category().get(category)?;
+    let parts = manager.part().list()?;
+    let items: Vec<LibraryItem> = parts
+        .iter()
+        .filter(|part| part.category == category)
+        .map(part_item)
+        .collect();
+    Ok(to_json(&items))
+}
+
+fn part(manager: &Manager, name: &str) -> Result<String, EleboxError> {
+    let part = manager.part().get(name)?;
+    Ok(to_json(&library_part(&part)))
+}
@@ -6,9 +6,15 @@ mod config;
 use config::{save_config, Config};
 use dirs::{self};
 use elebox_core::{
-    Category, Handler, JammDatabase, Manager, Manufacturer, Package, Part, TreeNode,
+    read_kicad_mapping, sort_by_field, write_bom_cost, write_bulk_add, write_valuation, BomCost,
+    BomImportReport, BulkAddFormat, Category, DeletePolicy, FieldDef, FieldFilter, FsckReport,
+    Handler, JammDatabase, KicadMapping, LedgerEntry, Location, Manager, Manufacturer, Marking,
+    Package, ParamRange, ParamTemplate, Part, PartQuery, PassiveKind, Project, PurchaseOrder,
+    ReorderGroup, Shortage, SiValue, StockReason, Supplier, TreeNode, Valuation, ValueMatches,
+    ValueQuery,
 };
-use std::{path::PathBuf, sync::Mutex};
+use rust_decimal::Decimal;
+use std::{fs, path::PathBuf, sync::Mutex};
 use tauri::Manager as TauriManager;
 
 macro_rules! lock {
@@ -30,10 +36,88 @@ fn get_part(manager: tauri::State<EleboxManager>, name: &str) -> Option<Part> {
 }
 
 #[tauri::command(rename_all = "snake_case")]
-fn get_parts(manager: tauri::State<EleboxManager>) -> Vec<Part> {
+fn get_parts(manager: tauri::State<EleboxManager>) -> Result<Vec<Part>, String> {
     let mgr_lock = lock!(manager);
     let hdr = mgr_lock.part();
-    hdr.list().unwrap()
+    hdr.list().map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn find_parts(
+    manager: tauri::State<EleboxManager>,
+    category: Option<String>,
+    package: Option<String>,
+    conditions: Vec<String>,
+    fields: Option<Vec<String>>,
+    sort: Option<String>,
+    descending: Option<bool>,
+) -> Result<Vec<Part>, String> {
+    let parameters = conditions
+        .iter()
+        .map(|c| ParamRange::parse(c))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| err.to_string())?;
+    let fields = fields
+        .unwrap_or_default()
+        .iter()
+        .map(|c| FieldFilter::parse(c))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| err.to_string())?;
+    let query = PartQuery {
+        category,
+        package,
+        parameters,
+        fields,
+    };
+
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.part();
+    let mut parts = hdr.find(&query).map_err(|err| err.to_string())?;
+    if let Some(field) = sort {
+        sort_by_field(&mut parts, &field, descending.unwrap_or(false));
+    }
+    Ok(parts)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn decode_marking(
+    manager: tauri::State<EleboxManager>,
+    code: &str,
+    kind: Option<PassiveKind>,
+    package: Option<String>,
+) -> Result<(Marking, Vec<Part>), String> {
+    let marking = Marking::decode(code, kind.unwrap_or_default()).map_err(|err| err.to_string())?;
+
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.part();
+    let parts = hdr
+        .find_by_marking(&marking, package.as_deref())
+        .map_err(|err| err.to_string())?;
+    Ok((marking, parts))
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn nearest_values(
+    manager: tauri::State<EleboxManager>,
+    kind: Option<PassiveKind>,
+    target: &str,
+    tolerance: Option<Decimal>,
+    package: Option<String>,
+    quantity: Option<u16>,
+    max_parts: Option<usize>,
+    limit: Option<usize>,
+) -> Result<ValueMatches, String> {
+    let target = SiValue::parse(target).ok_or_else(|| format!("Invalid value {}", target))?;
+    let mut query = ValueQuery::new(kind.unwrap_or_default(), target);
+    query.tolerance = tolerance.unwrap_or(query.tolerance);
+    query.package = package;
+    query.quantity = quantity.unwrap_or(query.quantity);
+    query.max_parts = max_parts.unwrap_or(query.max_parts);
+    query.limit = limit.unwrap_or(query.limit);
+
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.part();
+    hdr.nearest_values(&query).map_err(|err| err.to_string())
 }
 
 #[tauri::command(rename_all = "snake_case")]
@@ -67,15 +151,96 @@ fn increment_part(
     manager: tauri::State<EleboxManager>,
     name: &str,
     increment: i16,
+    location: Option<&str>,
+    note: Option<&str>,
+    project: Option<&str>,
 ) -> Result<(), String> {
     let mgr_lock = lock!(manager);
     let hdr = mgr_lock.part();
-    if let Err(err) = hdr.update_part_quantity(name, increment) {
+    let reason = if increment < 0 {
+        StockReason::Use
+    } else {
+        StockReason::Restock
+    };
+    if let Err(err) = hdr.move_stock(name, increment, location, reason, note, project) {
         return Err(err.to_string());
     }
     Ok(())
 }
 
+#[tauri::command(rename_all = "snake_case")]
+fn transfer_part(
+    manager: tauri::State<EleboxManager>,
+    name: &str,
+    quantity: u16,
+    from: Option<&str>,
+    to: Option<&str>,
+) -> Result<(), String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.part();
+    hdr.transfer(name, quantity, from, to)
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn get_ledger(
+    manager: tauri::State<EleboxManager>,
+    part: Option<&str>,
+    from: Option<i64>,
+    to: Option<i64>,
+) -> Result<Vec<LedgerEntry>, String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.ledger();
+    let entries = match part {
+        Some(name) => hdr.list_part(name),
+        None => hdr.list(),
+    }
+    .map_err(|err| err.to_string())?;
+
+    let range = from.unwrap_or(i64::MIN)..to.unwrap_or(i64::MAX);
+    Ok(entries
+        .into_iter()
+        .filter(|entry| range.contains(&entry.timestamp))
+        .collect())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn get_reorder(manager: tauri::State<EleboxManager>) -> Result<Vec<ReorderGroup>, String> {
+    let mgr_lock = lock!(manager);
+    mgr_lock.reorder().map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn export_reorder(
+    manager: tauri::State<EleboxManager>,
+    path: &str,
+    supplier: Option<&str>,
+    format: BulkAddFormat,
+) -> Result<(), String> {
+    let mgr_lock = lock!(manager);
+    let items: Vec<_> = mgr_lock
+        .reorder()
+        .map_err(|err| err.to_string())?
+        .into_iter()
+        .filter(|group| supplier.is_none() || group.supplier.as_deref() == supplier)
+        .flat_map(|group| group.items)
+        .collect();
+    write_bulk_add(&PathBuf::from(path), &items, format).map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn get_valuation(manager: tauri::State<EleboxManager>) -> Result<Valuation, String> {
+    let mgr_lock = lock!(manager);
+    mgr_lock.valuation().map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn export_valuation(manager: tauri::State<EleboxManager>, path: &str) -> Result<(), String> {
+    let mgr_lock = lock!(manager);
+    let valuation = mgr_lock.valuation().map_err(|err| err.to_string())?;
+    write_valuation(&PathBuf::from(path), &valuation).map_err(|err| err.to_string())
+}
+
 #[tauri::command(rename_all = "snake_case")]
 fn get_category(manager: tauri::State<EleboxManager>, name: &str) -> Option<Category> {
     let mgr_lock = lock!(manager);
@@ -85,10 +250,10 @@ fn get_category(manager: tauri::State<EleboxManager>, name: &str) -> Option<Cate
 }
 
 #[tauri::command(rename_all = "snake_case")]
-fn get_categories(manager: tauri::State<EleboxManager>) -> Vec<Category> {
+fn get_categories(manager: tauri::State<EleboxManager>) -> Result<Vec<Category>, String> {
     let mgr_lock = lock!(manager);
     let hdr = mgr_lock.category();
-    hdr.list().unwrap()
+    hdr.list().map_err(|err| err.to_string())
 }
 
 #[tauri::command(rename_all = "snake_case")]
@@ -111,17 +276,94 @@ fn update_category(
 }
 
 #[tauri::command(rename_all = "snake_case")]
-fn del_category(manager: tauri::State<EleboxManager>, name: &str) -> Result<(), String> {
+fn del_category(
+    manager: tauri::State<EleboxManager>,
+    name: &str,
+    policy: Option<DeletePolicy>,
+) -> Result<(), String> {
     let mgr_lock = lock!(manager);
     let hdr = mgr_lock.category();
-    hdr.delete(name).map_err(|err| err.to_string())
+    hdr.delete_with(name, &policy.unwrap_or(DeletePolicy::Restrict))
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn get_category_template(
+    manager: tauri::State<EleboxManager>,
+    name: &str,
+) -> Result<Vec<ParamTemplate>, String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.category();
+    hdr.template(name).map_err(|err| err.to_string())
 }
 
 #[tauri::command(rename_all = "snake_case")]
-fn get_tree(manager: tauri::State<EleboxManager>) -> Vec<TreeNode> {
+fn get_tree(manager: tauri::State<EleboxManager>) -> Result<Vec<TreeNode>, String> {
     let mgr_lock = lock!(manager);
     let hdr = mgr_lock.category();
-    hdr.get_tree().unwrap()
+    hdr.get_tree().map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn get_location(manager: tauri::State<EleboxManager>, name: &str) -> Option<Location> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.location();
+    hdr.get(name).ok()
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn get_locations(manager: tauri::State<EleboxManager>) -> Result<Vec<Location>, String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.location();
+    hdr.list().map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn add_location(manager: tauri::State<EleboxManager>, item: Location) -> Result<(), String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.location();
+    hdr.add(&item).map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn update_location(
+    manager: tauri::State<EleboxManager>,
+    ori_name: &str,
+    new_item: Location,
+) -> Result<(), String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.location();
+    hdr.update(ori_name, &new_item)
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn del_location(
+    manager: tauri::State<EleboxManager>,
+    name: &str,
+    policy: Option<DeletePolicy>,
+) -> Result<(), String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.location();
+    hdr.delete_with(name, &policy.unwrap_or(DeletePolicy::Restrict))
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn get_location_tree(manager: tauri::State<EleboxManager>) -> Result<Vec<TreeNode>, String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.location();
+    hdr.get_tree().map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn get_location_parts(
+    manager: tauri::State<EleboxManager>,
+    name: &str,
+) -> Result<Vec<Part>, String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.location();
+    hdr.parts_in(name).map_err(|err| err.to_string())
 }
 
 #[tauri::command(rename_all = "snake_case")]
@@ -133,10 +375,10 @@ fn get_package(manager: tauri::State<EleboxManager>, name: &str) -> Option<Packa
 }
 
 #[tauri::command(rename_all = "snake_case")]
-fn get_packages(manager: tauri::State<EleboxManager>) -> Vec<Package> {
+fn get_packages(manager: tauri::State<EleboxManager>) -> Result<Vec<Package>, String> {
     let mgr_lock = lock!(manager);
     let hdr = mgr_lock.package();
-    hdr.list().unwrap()
+    hdr.list().map_err(|err| err.to_string())
 }
 
 #[tauri::command(rename_all = "snake_case")]
@@ -159,10 +401,15 @@ fn update_package(
 }
 
 #[tauri::command(rename_all = "snake_case")]
-fn del_package(manager: tauri::State<EleboxManager>, name: &str) -> Result<(), String> {
+fn del_package(
+    manager: tauri::State<EleboxManager>,
+    name: &str,
+    policy: Option<DeletePolicy>,
+) -> Result<(), String> {
     let mgr_lock = lock!(manager);
     let hdr = mgr_lock.package();
-    hdr.delete(name).map_err(|err| err.to_string())
+    hdr.delete_with(name, &policy.unwrap_or(DeletePolicy::Restrict))
+        .map_err(|err| err.to_string())
 }
 
 #[tauri::command(rename_all = "snake_case")]
@@ -174,10 +421,10 @@ fn get_mfr(manager: tauri::State<EleboxManager>, name: &str) -> Option<Manufactu
 }
 
 #[tauri::command(rename_all = "snake_case")]
-fn get_mfrs(manager: tauri::State<EleboxManager>) -> Vec<Manufacturer> {
+fn get_mfrs(manager: tauri::State<EleboxManager>) -> Result<Vec<Manufacturer>, String> {
     let mgr_lock = lock!(manager);
     let hdr = mgr_lock.manufacturer();
-    hdr.list().unwrap()
+    hdr.list().map_err(|err| err.to_string())
 }
 
 #[tauri::command(rename_all = "snake_case")]
@@ -200,16 +447,328 @@ fn update_mfr(
 }
 
 #[tauri::command(rename_all = "snake_case")]
-fn del_mfr(manager: tauri::State<EleboxManager>, name: &str) -> Result<(), String> {
+fn del_mfr(
+    manager: tauri::State<EleboxManager>,
+    name: &str,
+    policy: Option<DeletePolicy>,
+) -> Result<(), String> {
     let mgr_lock = lock!(manager);
     let hdr = mgr_lock.manufacturer();
+    hdr.delete_with(name, &policy.unwrap_or(DeletePolicy::Restrict))
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn get_supplier(manager: tauri::State<EleboxManager>, name: &str) -> Option<Supplier> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.supplier();
+    hdr.get(name).ok()
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn get_suppliers(manager: tauri::State<EleboxManager>) -> Result<Vec<Supplier>, String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.supplier();
+    hdr.list().map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn add_supplier(manager: tauri::State<EleboxManager>, item: Supplier) -> Result<(), String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.supplier();
+    hdr.add(&item).map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn update_supplier(
+    manager: tauri::State<EleboxManager>,
+    ori_name: &str,
+    new_item: Supplier,
+) -> Result<(), String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.supplier();
+    hdr.update(ori_name, &new_item)
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn del_supplier(
+    manager: tauri::State<EleboxManager>,
+    name: &str,
+    policy: Option<DeletePolicy>,
+) -> Result<(), String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.supplier();
+    hdr.delete_with(name, &policy.unwrap_or(DeletePolicy::Restrict))
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn get_field(manager: tauri::State<EleboxManager>, name: &str) -> Option<FieldDef> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.field();
+    hdr.get(name).ok()
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn get_fields(manager: tauri::State<EleboxManager>) -> Result<Vec<FieldDef>, String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.field();
+    hdr.list().map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn add_field(manager: tauri::State<EleboxManager>, item: FieldDef) -> Result<(), String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.field();
+    hdr.add(&item).map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn update_field(
+    manager: tauri::State<EleboxManager>,
+    ori_name: &str,
+    new_item: FieldDef,
+) -> Result<(), String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.field();
+    hdr.update(ori_name, &new_item)
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn del_field(manager: tauri::State<EleboxManager>, name: &str) -> Result<(), String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.field();
+    hdr.delete(name).map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn rename_field(
+    manager: tauri::State<EleboxManager>,
+    ori_name: &str,
+    new_name: &str,
+) -> Result<usize, String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.field();
+    hdr.rename(ori_name, new_name)
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn get_order(manager: tauri::State<EleboxManager>, name: &str) -> Option<PurchaseOrder> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.order();
+    hdr.get(name).ok()
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn get_orders(manager: tauri::State<EleboxManager>) -> Result<Vec<PurchaseOrder>, String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.order();
+    hdr.list().map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn get_open_orders(manager: tauri::State<EleboxManager>) -> Result<Vec<PurchaseOrder>, String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.order();
+    hdr.list_open().map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn add_order(manager: tauri::State<EleboxManager>, item: PurchaseOrder) -> Result<(), String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.order();
+    hdr.add(&item).map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn update_order(
+    manager: tauri::State<EleboxManager>,
+    ori_name: &str,
+    new_item: PurchaseOrder,
+) -> Result<(), String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.order();
+    hdr.update(ori_name, &new_item)
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn del_order(manager: tauri::State<EleboxManager>, name: &str) -> Result<(), String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.order();
+    hdr.delete(name).map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn send_order(manager: tauri::State<EleboxManager>, name: &str) -> Result<(), String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.order();
+    hdr.send(name).map_err(|err| err.to_string())
+}
+
+/// Receive `quantity` of `part`, or everything outstanding without a part.
+#[tauri::command(rename_all = "snake_case")]
+fn receive_order(
+    manager: tauri::State<EleboxManager>,
+    name: &str,
+    part: Option<&str>,
+    quantity: Option<u16>,
+    location: Option<&str>,
+) -> Result<(), String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.order();
+    match (part, quantity) {
+        (Some(part), Some(quantity)) => hdr.receive(name, part, quantity, location),
+        _ => hdr.receive_all(name, location),
+    }
+    .map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn get_project(manager: tauri::State<EleboxManager>, name: &str) -> Option<Project> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.project();
+    let project = hdr.get(name).ok()?;
+    Some(project)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn get_projects(manager: tauri::State<EleboxManager>) -> Result<Vec<Project>, String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.project();
+    hdr.list().map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn add_project(manager: tauri::State<EleboxManager>, item: Project) -> Result<(), String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.project();
+    hdr.add(&item).map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn update_project(
+    manager: tauri::State<EleboxManager>,
+    ori_name: &str,
+    new_item: Project,
+) -> Result<(), String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.project();
+    hdr.update(ori_name, &new_item)
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn del_project(manager: tauri::State<EleboxManager>, name: &str) -> Result<(), String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.project();
     hdr.delete(name).map_err(|err| err.to_string())
 }
 
+#[tauri::command(rename_all = "snake_case")]
+fn get_buildable(manager: tauri::State<EleboxManager>, name: &str) -> Result<u16, String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.project();
+    hdr.buildable(name).map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn get_shortages(
+    manager: tauri::State<EleboxManager>,
+    name: &str,
+    boards: u16,
+) -> Result<Vec<Shortage>, String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.project();
+    hdr.shortages(name, boards).map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn get_project_cost(
+    manager: tauri::State<EleboxManager>,
+    name: &str,
+    boards: u16,
+) -> Result<BomCost, String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.project();
+    hdr.cost(name, boards).map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn export_project_cost(
+    manager: tauri::State<EleboxManager>,
+    name: &str,
+    boards: u16,
+    path: &str,
+) -> Result<(), String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.project();
+    let cost = hdr.cost(name, boards).map_err(|err| err.to_string())?;
+    write_bom_cost(&PathBuf::from(path), &cost).map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn build_project(
+    manager: tauri::State<EleboxManager>,
+    name: &str,
+    boards: u16,
+) -> Result<(), String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.project();
+    hdr.build(name, boards).map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn import_kicad(
+    manager: tauri::State<EleboxManager>,
+    path: &str,
+    save_as: Option<&str>,
+) -> Result<BomImportReport, String> {
+    let mgr_lock = lock!(manager);
+    let hdr = mgr_lock.project();
+    let report = hdr
+        .import_kicad(&PathBuf::from(path))
+        .map_err(|err| err.to_string())?;
+
+    if let Some(name) = save_as {
+        hdr.add(&report.to_project(name, None))
+            .map_err(|err| err.to_string())?;
+    }
+    Ok(report)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn export_kicad_dbl(
+    manager: tauri::State<EleboxManager>,
+    path: &str,
+    mapping_path: Option<&str>,
+) -> Result<(), String> {
+    let mapping = match mapping_path {
+        Some(mapping_path) => {
+            read_kicad_mapping(&PathBuf::from(mapping_path)).map_err(|err| err.to_string())?
+        }
+        None => KicadMapping::new(),
+    };
+
+    let mgr_lock = lock!(manager);
+    mgr_lock
+        .export_kicad_dbl(&PathBuf::from(path), &mapping)
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn fsck_db(manager: tauri::State<EleboxManager>, repair: bool) -> Result<FsckReport, String> {
+    let mgr_lock = lock!(manager);
+    mgr_lock.fsck(repair).map_err(|err| err.to_string())
+}
+
 #[tauri::command(rename_all = "snake_case")]
 fn get_db_path(config: tauri::State<EleboxConfig>) -> String {
     let cfg = lock!(config);
-    cfg.database.clone().unwrap()
+    cfg.database.clone().unwrap_or_default()
 }
 
 #[tauri::command(rename_all = "snake_case")]
@@ -230,6 +789,7 @@ fn get_assets_path() -> Result<String, String> {
 
 #[tauri::command(rename_all = "snake_case")]
 fn set_db_path(
+    manager: tauri::State<EleboxManager>,
     config: tauri::State<EleboxConfig>,
     user_dir: tauri::State<EleboxUserDir>,
     new_path: &str,
@@ -239,11 +799,11 @@ fn set_db_path(
         return Err("File does not exist".to_string());
     }
 
-    if !check_db(&new_path) {
-        return Err("This file is not an database".to_string());
-    }
-
+    let mut mgr_lock = lock!(manager);
     let mut cfg = lock!(config);
+    switch_db(&mut mgr_lock, cfg.database.as_deref(), new_path, || {
+        open_db(new_path)
+    })?;
     cfg.database = Some(path.to_string_lossy().into_owned());
 
     let dir = lock!(user_dir);
@@ -253,17 +813,22 @@ fn set_db_path(
 
 #[tauri::command(rename_all = "snake_case")]
 fn create_db(
+    manager: tauri::State<EleboxManager>,
     config: tauri::State<EleboxConfig>,
     user_dir: tauri::State<EleboxUserDir>,
     new_path: &str,
     _empty: bool,
 ) -> Result<(), String> {
     let path = PathBuf::from(new_path);
+
+    let mut mgr_lock = lock!(manager);
     let mut cfg = lock!(config);
+    switch_db(&mut mgr_lock, cfg.database.as_deref(), new_path, || {
+        init_db(new_path);
+        open_db(new_path)
+    })?;
     cfg.database = Some(path.to_string_lossy().into_owned());
 
-    init_db(new_path);
-
     let dir = lock!(user_dir);
     let _ = save_config(&dir, &cfg);
 
@@ -316,11 +881,30 @@ fn import_file(
     // TODO
     let path = PathBuf::from(dir);
     let db_path = path.join("import_exebox.db");
-    let part_db = Box::new(JammDatabase::new(&db_path.to_string_lossy().into_owned()));
-    let pkg_db = Box::new(JammDatabase::new(&db_path.to_string_lossy().into_owned()));
-    let cat_db = Box::new(JammDatabase::new(&db_path.to_string_lossy().into_owned()));
-    let mfr_db = Box::new(JammDatabase::new(&db_path.to_string_lossy().into_owned()));
-    let _ = Manager::from(part_db, pkg_db, cat_db, mfr_db, &path);
+    let db = JammDatabase::new(&db_path.to_string_lossy().into_owned());
+    let part_db = Box::new(db.clone());
+    let pkg_db = Box::new(db.clone());
+    let cat_db = Box::new(db.clone());
+    let mfr_db = Box::new(db.clone());
+    let ledger_db = Box::new(db.clone());
+    let project_db = Box::new(db.clone());
+    let location_db = Box::new(db.clone());
+    let supplier_db = Box::new(db.clone());
+    let order_db = Box::new(db.clone());
+    let field_db = Box::new(db);
+    let _ = Manager::from(
+        part_db,
+        pkg_db,
+        cat_db,
+        mfr_db,
+        ledger_db,
+        project_db,
+        location_db,
+        supplier_db,
+        order_db,
+        field_db,
+        &path,
+    );
     Ok(())
 }
 
@@ -331,25 +915,30 @@ fn main() {
     let mut config = config::load_config(&user_dir).unwrap();
 
     if config.database.is_none() {
-        config.database = match get_default_database_path() {
-            Ok(path) => Some(path.to_string_lossy().into_owned()),
-            Err(err) => panic!("{}", err), // TODO
-        };
+        match get_default_database_path() {
+            Ok(path) => config.database = Some(path.to_string_lossy().into_owned()),
+            Err(err) => eprintln!("{}", err),
+        }
     }
 
-    let part_db = Box::new(JammDatabase::new(&config.database.clone().unwrap()));
-    let pkg_db = Box::new(JammDatabase::new(&config.database.clone().unwrap()));
-    let cat_db = Box::new(JammDatabase::new(&config.database.clone().unwrap()));
-    let mfr_db = Box::new(JammDatabase::new(&config.database.clone().unwrap()));
-    let manager = elebox_core::Manager::new(part_db, pkg_db, cat_db, mfr_db);
-    // init_db(&config.database.clone().unwrap());
-
     if config.language.is_none() {
         config.language = Some("en".to_string());
     }
 
     let _ = config::save_config(&user_dir, &config);
 
+    // Start without a database if it cannot be opened, so the user can pick
+    // another file. The path is kept in the saved config for the next start.
+    let manager = match config.database.as_deref().map(open_db) {
+        Some(Ok(mgr)) => mgr,
+        Some(Err(err)) => {
+            eprintln!("{}", err);
+            config.database = None;
+            Manager::in_memory()
+        }
+        None => Manager::in_memory(),
+    };
+
     tauri::Builder::default()
         .setup(|app| {
             #[cfg(debug_assertions)]
@@ -369,16 +958,33 @@ fn main() {
         .invoke_handler(tauri::generate_handler![
             get_part,
             get_parts,
+            find_parts,
+            decode_marking,
+            nearest_values,
             add_part,
             update_part,
             del_part,
             increment_part,
+            transfer_part,
+            get_ledger,
+            get_reorder,
+            export_reorder,
+            get_valuation,
+            export_valuation,
             get_category,
             get_categories,
             add_category,
             update_category,
             del_category,
+            get_category_template,
             get_tree,
+            get_location,
+            get_locations,
+            add_location,
+            update_location,
+            del_location,
+            get_location_tree,
+            get_location_parts,
             get_package,
             get_packages,
             add_package,
@@ -389,6 +995,38 @@ fn main() {
             add_mfr,
             update_mfr,
             del_mfr,
+            get_supplier,
+            get_suppliers,
+            add_supplier,
+            update_supplier,
+            del_supplier,
+            get_field,
+            get_fields,
+            add_field,
+            update_field,
+            del_field,
+            rename_field,
+            get_order,
+            get_orders,
+            get_open_orders,
+            add_order,
+            update_order,
+            del_order,
+            send_order,
+            receive_order,
+            get_project,
+            get_projects,
+            add_project,
+            update_project,
+            del_project,
+            get_buildable,
+            get_shortages,
+            get_project_cost,
+            export_project_cost,
+            build_project,
+            import_kicad,
+            export_kicad_dbl,
+            fsck_db,
             get_db_path,
             get_assets_path,
             get_default_db_path,
@@ -435,15 +1073,54 @@ fn init_db(path: &str) {
     elebox_core::create_default_db(path);
 }
 
-// TODO
-fn check_db(_path: &str) -> bool {
-    true
+/// Open the database and upgrade it if created by an older release, a new
+/// file is created on first write. Checked after the upgrade, as older
+/// releases lack the buckets added since.
+fn open_db(path: &str) -> Result<Manager, String> {
+    let exists = PathBuf::from(path).exists();
+    let manager = Manager::open(path).map_err(|err| err.to_string())?;
+    if !exists {
+        return Ok(manager);
+    }
+
+    manager.init().map_err(|err| err.to_string())?;
+    manager.check().map_err(|err| err.to_string())?;
+    Ok(manager)
+}
+
+/// Replace the current database with the one returned by `open`, the
+/// current one is kept if that fails.
+fn switch_db(
+    manager: &mut Manager,
+    cur_path: Option<&str>,
+    new_path: &str,
+    open: impl FnOnce() -> Result<Manager, String>,
+) -> Result<(), String> {
+    let same_file = cur_path.filter(|cur| is_same_file(cur, new_path));
+    let Some(cur_path) = same_file else {
+        *manager = open()?;
+        return Ok(());
+    };
+
+    // The handle of the current database has to be released first
+    *manager = Manager::in_memory();
+    match open() {
+        Ok(new_mgr) => {
+            *manager = new_mgr;
+            Ok(())
+        }
+        Err(err) => {
+            if let Ok(cur_mgr) = Manager::open(cur_path) {
+                *manager = cur_mgr;
+            }
+            Err(err)
+        }
+    }
+}
 
-    // return elebox_core::check_db(&path).is_ok();
-    // if path.is_none() {
-    //     let db_path = GET!(db);
-    //     return elebox_core::check_db(&db_path).is_ok();
-    // } else {
-    //     return elebox_core::check_db(&path.unwrap()).is_ok();
-    // }
+fn is_same_file(path: &str, other: &str) -> bool {
+    match (fs::canonicalize(path), fs::canonicalize(other)) {
+        (Ok(path), Ok(other)) => path == other,
+        _ => path == other,
+    }
 }
@@ -1,7 +1,19 @@
+import { CustomFieldType } from "@/types/part";
+
+/** A field the parts of a category are expected to have. */
+export interface ParamTemplate {
+  name: string;
+  field_type: CustomFieldType;
+  /** Unit of a `Parameter` field, e.g. `F`, empty for any. */
+  unit: string;
+}
+
 export interface Category {
   name: string;
   parent?: string;
   alias?: string;
+  /** Own fields of the template, without the inherited ones. */
+  parameters?: ParamTemplate[];
 }
 
 export interface TreeNode {
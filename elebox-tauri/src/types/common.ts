@@ -2,3 +2,6 @@ export interface Config {
   language?: string;
   database?: string;
 }
+
+/** What to do with the parts that still use a deleted item, `Restrict` if omitted. */
+export type DeletePolicy = "Restrict" | "Cascade" | { Reassign: string };
@@ -0,0 +1,8 @@
+import { CustomFieldType } from "@/types/part";
+
+/** A custom field shared by all parts. */
+export interface FieldDef {
+  name: string;
+  field_type: CustomFieldType;
+  description?: string;
+}
@@ -0,0 +1,11 @@
+export type StockReason = "Restock" | "Use" | "Adjustment" | "Import";
+
+export interface LedgerEntry {
+  part: string;
+  /** Unix time in seconds. */
+  timestamp: number;
+  delta: number;
+  reason: StockReason;
+  note?: string;
+  project?: string;
+}
@@ -0,0 +1,5 @@
+export interface Location {
+  name: string;
+  parent?: string;
+  alias?: string;
+}
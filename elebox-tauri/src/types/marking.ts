@@ -0,0 +1,9 @@
+import { PassiveKind, SiValue } from "@/types/passive";
+
+/** Value read off a component, e.g. `103` on a resistor as 10kΩ. */
+export interface Marking {
+  kind: PassiveKind;
+  value: SiValue;
+  /** e.g. `5%` for a gold band or `0.25pF` for a `C` suffix. */
+  tolerance?: SiValue;
+}
@@ -0,0 +1,17 @@
+export type OrderStatus = "Draft" | "Sent" | "Received";
+
+export interface OrderLine {
+  part: string;
+  quantity: number;
+  /** Quantity received so far. */
+  received: number;
+}
+
+export interface PurchaseOrder {
+  /** Order number. */
+  name: string;
+  supplier: string;
+  status: OrderStatus;
+  note?: string;
+  lines: OrderLine[];
+}
@@ -1,18 +1,34 @@
+import { PartSupplier } from "@/types/supplier";
+
+/**
+ * `Parameter` is a value with a unit like `100nF`, `Number` has an optional
+ * unit and `Enum` lists its choices in order.
+ */
+export type CustomFieldType =
+  | "Normal"
+  | "Link"
+  | "Parameter"
+  | "Integer"
+  | "Boolean"
+  | "Date"
+  | "Markdown"
+  | { Number: string | null }
+  | { Enum: string[] };
+
 export interface CustomField {
   name: string;
-  field_type: string; // TODO enum
+  field_type: CustomFieldType;
   value: string;
 }
 
-export interface Supplier {
-  name: string;
-  link: string;
-  price?: number;
-  note: string;
+export interface StockLine {
+  location?: string;
+  quantity: number;
 }
 
 export interface Part {
   name: string;
+  /** Total of `stock`. */
   quantity: number;
   category: string;
   package?: string;
@@ -26,6 +42,11 @@ export interface Part {
   product_link?: string;
   image_link?: string;
   custom_fields: CustomField[];
-  suppliers: Supplier[];
+  suppliers: PartSupplier[];
   starred: boolean;
+  stock?: StockLine[];
+  /** Reorder at or below this quantity. */
+  min_stock?: number;
+  /** Quantity to order, `min_stock` if not set. */
+  reorder_quantity?: number;
 }
@@ -0,0 +1,43 @@
+/** A number in the base unit, e.g. `0.0000001` F for `100nF`. */
+export interface SiValue {
+  /** Exact decimal, e.g. "10000". */
+  value: string;
+  /** e.g. `Ω`, `F` or `%`. */
+  unit: string;
+}
+
+/** Stored in the `Resistance`, `Capacitance` or `Inductance` parameter. */
+export type PassiveKind = "Resistor" | "Capacitor" | "Inductor";
+
+export interface StockedValue {
+  part: string;
+  value: SiValue;
+  quantity: number;
+}
+
+/**
+ * The first part is the single one of `SeriesParallel`, in series with the
+ * others in parallel, and of `ParallelSeries`.
+ */
+export type Connection =
+  | "Single"
+  | "Series"
+  | "Parallel"
+  | "SeriesParallel"
+  | "ParallelSeries";
+
+export interface Combination {
+  connection: Connection;
+  parts: StockedValue[];
+  /** Rounded to 4 significant digits. */
+  value: SiValue;
+  /** Deviation from the target in percent, e.g. "-0.73". */
+  deviation: string;
+}
+
+export interface ValueMatches {
+  /** Single parts, the nearest first. */
+  nearest: Combination[];
+  /** Two or more parts, the nearest first. */
+  combinations: Combination[];
+}
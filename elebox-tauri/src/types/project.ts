@@ -0,0 +1,31 @@
+export interface BomItem {
+  part: string;
+  /** Quantity per board. */
+  quantity: number;
+}
+
+export interface Project {
+  name: string;
+  description?: string;
+  bom: BomItem[];
+}
+
+export interface Shortage {
+  part: string;
+  required: number;
+  available: number;
+}
+
+export interface KicadLine {
+  references: string[];
+  value: string;
+  footprint: string;
+  mfr_no?: string;
+  quantity: number;
+}
+
+export type BomMatch = { Matched: string } | { Ambiguous: string[] } | "Missing";
+
+export interface BomImportReport {
+  lines: { line: KicadLine; status: BomMatch }[];
+}
@@ -0,0 +1,23 @@
+export type BulkAddFormat = "DigiKey" | "Mouser" | "Lcsc";
+
+export interface ReorderItem {
+  part: string;
+  mfr?: string;
+  mfr_no?: string;
+  quantity: number;
+  min_stock: number;
+  /** Outstanding on open orders. */
+  on_order: number;
+  order_quantity: number;
+  sku?: string;
+  link?: string;
+  /** Unit price at `order_quantity`, exact decimal. */
+  price?: string;
+  currency?: string;
+}
+
+export interface ReorderGroup {
+  /** Not set for parts without a supplier. */
+  supplier?: string;
+  items: ReorderItem[];
+}
@@ -0,0 +1,52 @@
+/** Prices are exact decimals. */
+export interface Amount {
+  value: string;
+  /** Not set for prices without a currency code. */
+  currency?: string;
+}
+
+export interface StockValue {
+  part: string;
+  category: string;
+  quantity: number;
+  /** Not set if the part has no price. */
+  unit_price?: string;
+  currency?: string;
+  value?: string;
+}
+
+export interface CategoryValue {
+  category: string;
+  /** By currency. */
+  totals: Amount[];
+  /** Parts in stock without a price, not in the totals. */
+  unpriced: string[];
+}
+
+export interface Valuation {
+  parts: StockValue[];
+  categories: CategoryValue[];
+  totals: Amount[];
+}
+
+export interface BomCostLine {
+  part: string;
+  per_board: number;
+  /** Quantity for all the boards, the prices are those of this quantity. */
+  required: number;
+  supplier?: string;
+  unit_price?: string;
+  currency?: string;
+  /** Cost of the line on one board. */
+  board_cost?: string;
+}
+
+export interface BomCost {
+  project: string;
+  boards: number;
+  lines: BomCostLine[];
+  per_board: Amount[];
+  total: Amount[];
+  /** Parts without a price, not in the totals. */
+  unpriced: string[];
+}
@@ -0,0 +1,23 @@
+export interface Supplier {
+  name: string;
+  alias?: string;
+  url?: string;
+}
+
+export interface PriceBreak {
+  quantity: number;
+  /** Exact decimal, e.g. "0.042". */
+  price: string;
+}
+
+export interface PartSupplier {
+  supplier: string;
+  sku?: string;
+  link?: string;
+  min_order?: number;
+  pack_size?: number;
+  /** ISO 4217 code, e.g. "USD". */
+  currency?: string;
+  price_breaks: PriceBreak[];
+  note?: string;
+}
@@ -1,6 +1,8 @@
 import { invoke } from "@tauri-apps/api/tauri";
+import { DeletePolicy } from "@/types/common";
 import {
   Category as CategoryInterface,
+  ParamTemplate,
   TreeNode as TreeNodeInterface,
 } from "@/types/category";
 
@@ -40,12 +42,17 @@ export namespace DbCategory {
     }
   }
 
-  export async function remove(name: string) {
-    await invoke("del_category", { name })
+  export async function remove(name: string, policy?: DeletePolicy) {
+    await invoke("del_category", { name, policy })
       .then((msg) => console.log(`Delete category, ${msg}`))
       .catch((err) => console.warn(`Delete category, ${err}`));
   }
 
+  /** Fields the parts are expected to have, including the inherited ones. */
+  export async function template(name: string): Promise<ParamTemplate[]> {
+    return invoke("get_category_template", { name });
+  }
+
   export async function getTree(): Promise<TreeNodeInterface[]> {
     return invoke("get_tree", {});
   }
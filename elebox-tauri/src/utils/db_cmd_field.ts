@@ -0,0 +1,52 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import { FieldDef as FieldDefInterface } from "@/types/field";
+
+export namespace DbField {
+  export type FieldDef = FieldDefInterface;
+
+  export async function get(name: string) {
+    return invoke("get_field", { name }).catch((err) =>
+      console.warn(`Get field, ${err}`)
+    );
+  }
+
+  export async function list(): Promise<FieldDefInterface[]> {
+    return invoke("get_fields", {});
+  }
+
+  export async function add(item: FieldDefInterface) {
+    try {
+      const msg = await invoke("add_field", { item });
+      console.log(`Add field, ${msg}`);
+      return msg;
+    } catch (err) {
+      console.warn(`Add field, ${err}`);
+      throw err;
+    }
+  }
+
+  export async function update(ori_name: string, new_item: FieldDefInterface) {
+    try {
+      const msg = await invoke("update_field", { ori_name, new_item });
+      console.log(`Update field, ${msg}`);
+      return msg;
+    } catch (err) {
+      console.warn(`Update field, ${err}`);
+      throw err;
+    }
+  }
+
+  export async function remove(name: string) {
+    await invoke("del_field", { name })
+      .then((msg) => console.log(`Delete field, ${msg}`))
+      .catch((err) => console.warn(`Delete field, ${err}`));
+  }
+
+  /** Rename the field in all parts, returns the number of changed parts. */
+  export async function rename(
+    ori_name: string,
+    new_name: string
+  ): Promise<number> {
+    return invoke("rename_field", { ori_name, new_name });
+  }
+}
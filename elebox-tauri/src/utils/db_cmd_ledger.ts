@@ -0,0 +1,15 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import { LedgerEntry as LedgerEntryInterface } from "@/types/ledger";
+
+export namespace DbLedger {
+  export type LedgerEntry = LedgerEntryInterface;
+
+  /** Stock movements, optionally of one part and from/to Unix seconds. */
+  export async function list(
+    part?: string,
+    from?: number,
+    to?: number
+  ): Promise<LedgerEntryInterface[]> {
+    return invoke("get_ledger", { part, from, to });
+  }
+}
@@ -0,0 +1,56 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import { DeletePolicy } from "@/types/common";
+import { TreeNode } from "@/types/category";
+import { Location as LocationInterface } from "@/types/location";
+import { Part } from "@/types/part";
+
+export namespace DbLocation {
+  export type Location = LocationInterface;
+
+  export async function get(name: string) {
+    return invoke("get_location", { name }).catch((err) =>
+      console.warn(`Get location, ${err}`)
+    );
+  }
+
+  export async function list(): Promise<LocationInterface[]> {
+    return invoke("get_locations", {});
+  }
+
+  export async function add(item: LocationInterface) {
+    try {
+      const msg = await invoke("add_location", { item });
+      console.log(`Add location, ${msg}`);
+      return msg;
+    } catch (err) {
+      console.warn(`Add location, ${err}`);
+      throw err;
+    }
+  }
+
+  export async function update(ori_name: string, new_item: LocationInterface) {
+    try {
+      const msg = await invoke("update_location", { ori_name, new_item });
+      console.log(`Update location, ${msg}`);
+      return msg;
+    } catch (err) {
+      console.warn(`Update location, ${err}`);
+      throw err;
+    }
+  }
+
+  export async function remove(name: string, policy?: DeletePolicy) {
+    await invoke("del_location", { name, policy })
+      .then((msg) => console.log(`Delete location, ${msg}`))
+      .catch((err) => console.warn(`Delete location, ${err}`));
+  }
+
+  export async function getTree(): Promise<TreeNode[]> {
+    return invoke("get_location_tree", {});
+  }
+
+  /** Parts in the location and the locations inside it. */
+  export async function listParts(name: string): Promise<Part[]> {
+    return invoke("get_location_parts", { name });
+  }
+}
@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api/tauri";
+import { DeletePolicy } from "@/types/common";
 import { Manufacturer as MfrInterface } from "@/types/manufacturer";
 
 export namespace DbManufacturer {
@@ -36,8 +37,8 @@ export namespace DbManufacturer {
     }
   }
 
-  export async function remove(name: string) {
-    await invoke("del_mfr", { name })
+  export async function remove(name: string, policy?: DeletePolicy) {
+    await invoke("del_mfr", { name, policy })
       .then((msg) => console.log(`Delete manufacturer, ${msg}`))
       .catch((err) => console.warn(`Delete manufacturer, ${err}`));
   }
@@ -0,0 +1,19 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import { Marking } from "@/types/marking";
+import { PassiveKind } from "@/types/passive";
+import { Part } from "@/types/part";
+
+export namespace DbMarking {
+  /**
+   * Decode a marking like `103`, `4R7`, `01C` or `brown black orange gold`,
+   * and the parts with its value. The package can be given by its size alone,
+   * e.g. `0805`.
+   */
+  export async function decode(
+    code: string,
+    kind: PassiveKind = "Resistor",
+    package_name?: string
+  ): Promise<[Marking, Part[]]> {
+    return invoke("decode_marking", { code, kind, package: package_name });
+  }
+}
@@ -0,0 +1,77 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import { PurchaseOrder as PurchaseOrderInterface } from "@/types/order";
+
+export namespace DbOrder {
+  export type PurchaseOrder = PurchaseOrderInterface;
+
+  export async function get(name: string) {
+    return invoke("get_order", { name }).catch((err) =>
+      console.warn(`Get order, ${err}`)
+    );
+  }
+
+  export async function list(): Promise<PurchaseOrderInterface[]> {
+    return invoke("get_orders", {});
+  }
+
+  /** Sent orders that are not fully received yet. */
+  export async function listOpen(): Promise<PurchaseOrderInterface[]> {
+    return invoke("get_open_orders", {});
+  }
+
+  export async function add(item: PurchaseOrderInterface) {
+    try {
+      const msg = await invoke("add_order", { item });
+      console.log(`Add order, ${msg}`);
+      return msg;
+    } catch (err) {
+      console.warn(`Add order, ${err}`);
+      throw err;
+    }
+  }
+
+  export async function update(
+    ori_name: string,
+    new_item: PurchaseOrderInterface
+  ) {
+    try {
+      const msg = await invoke("update_order", { ori_name, new_item });
+      console.log(`Update order, ${msg}`);
+      return msg;
+    } catch (err) {
+      console.warn(`Update order, ${err}`);
+      throw err;
+    }
+  }
+
+  export async function remove(name: string) {
+    await invoke("del_order", { name })
+      .then((msg) => console.log(`Delete order, ${msg}`))
+      .catch((err) => console.warn(`Delete order, ${err}`));
+  }
+
+  /** Mark a draft order as placed at the supplier. */
+  export async function send(name: string) {
+    try {
+      await invoke("send_order", { name });
+    } catch (err) {
+      console.warn(`Send order, ${err}`);
+      throw err;
+    }
+  }
+
+  /** Receive `quantity` of `part` into the stock, or everything outstanding. */
+  export async function receive(
+    name: string,
+    part?: string,
+    quantity?: number,
+    location?: string
+  ) {
+    try {
+      await invoke("receive_order", { name, part, quantity, location });
+    } catch (err) {
+      console.warn(`Receive order, ${err}`);
+      throw err;
+    }
+  }
+}
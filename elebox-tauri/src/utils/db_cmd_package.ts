@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api/tauri";
+import { DeletePolicy } from "@/types/common";
 import { Package as PackageInterface } from "@/types/package";
 
 export namespace DbPackage {
@@ -36,8 +37,8 @@ export namespace DbPackage {
     }
   }
 
-  export async function remove(name: string) {
-    await invoke("del_package", { name })
+  export async function remove(name: string, policy?: DeletePolicy) {
+    await invoke("del_package", { name, policy })
       .then((msg) => console.log(`Delete package, ${msg}`))
       .catch((err) => console.warn(`Delete package, ${err}`));
   }
@@ -14,6 +14,30 @@ export namespace DbPart {
     return invoke("get_parts", {});
   }
 
+  /**
+   * Parts in the category or its subcategories, in the package and within the
+   * parameter ranges, e.g. `Capacitance>=10uF` or `Resistance=1k..10k`,
+   * and meeting the field conditions, e.g. `Pins>=8` or `Notes~smd`. Sorted
+   * by the `sort` field if given.
+   */
+  export async function find(
+    category?: string,
+    package_name?: string,
+    conditions: string[] = [],
+    fields: string[] = [],
+    sort?: string,
+    descending = false
+  ): Promise<PartInterface[]> {
+    return invoke("find_parts", {
+      category,
+      package: package_name,
+      conditions,
+      fields,
+      sort,
+      descending,
+    });
+  }
+
   export async function add(item: PartInterface) {
     try {
       const msg = await invoke("add_part", { item });
@@ -42,9 +66,34 @@ export namespace DbPart {
       .catch((err) => console.warn(`Delete part, ${err}`));
   }
 
-  export async function modifyQty(name: string, increment: number) {
-    await invoke("increment_part", { name, increment })
+  export async function modifyQty(
+    name: string,
+    increment: number,
+    note?: string,
+    project?: string,
+    location?: string
+  ) {
+    await invoke("increment_part", { name, increment, location, note, project })
       .then((msg) => console.log(`Modify qty part, ${msg}`))
       .catch((err) => console.warn(`Modify qty part, ${err}`));
   }
+
+  /** Move stock between locations, the part's location if not given. */
+  export async function transfer(
+    name: string,
+    quantity: number,
+    from?: string,
+    to?: string
+  ) {
+    await invoke("transfer_part", { name, quantity, from, to })
+      .then((msg) => console.log(`Transfer part, ${msg}`))
+      .catch((err) => console.warn(`Transfer part, ${err}`));
+  }
+
+  /** Write a KiCad database library, symbols and footprints by package from the YAML mapping. */
+  export async function exportKicadDbl(path: string, mapping_path?: string) {
+    await invoke("export_kicad_dbl", { path, mapping_path })
+      .then(() => console.log(`Export KiCad library, ${path}`))
+      .catch((err) => console.warn(`Export KiCad library, ${err}`));
+  }
 }
@@ -0,0 +1,30 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import { PassiveKind, ValueMatches } from "@/types/passive";
+
+export namespace DbPassive {
+  /**
+   * The stocked values within `tolerance` percent of a target like `13.7k`,
+   * and the best series and parallel combinations of up to `max_parts` parts
+   * with enough stock for `quantity` circuits. The package can be given by
+   * its size alone, e.g. `0805`.
+   */
+  export async function nearest(
+    target: string,
+    kind: PassiveKind = "Resistor",
+    tolerance?: number,
+    package_name?: string,
+    quantity?: number,
+    max_parts?: number,
+    limit?: number
+  ): Promise<ValueMatches> {
+    return invoke("nearest_values", {
+      kind,
+      target,
+      tolerance,
+      package: package_name,
+      quantity,
+      max_parts,
+      limit,
+    });
+  }
+}
@@ -0,0 +1,95 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import {
+  BomImportReport,
+  Project as ProjectInterface,
+  Shortage as ShortageInterface,
+} from "@/types/project";
+import { BomCost } from "@/types/report";
+
+export namespace DbProject {
+  export type Project = ProjectInterface;
+  export type Shortage = ShortageInterface;
+
+  export async function get(name: string) {
+    return invoke("get_project", { name }).catch((err) =>
+      console.warn(`Get project, ${err}`)
+    );
+  }
+
+  export async function list(): Promise<ProjectInterface[]> {
+    return invoke("get_projects", {});
+  }
+
+  export async function add(item: ProjectInterface) {
+    try {
+      const msg = await invoke("add_project", { item });
+      console.log(`Add project, ${msg}`);
+      return msg;
+    } catch (err) {
+      console.warn(`Add project, ${err}`);
+      throw err;
+    }
+  }
+
+  export async function update(ori_name: string, new_item: ProjectInterface) {
+    try {
+      const msg = await invoke("update_project", { ori_name, new_item });
+      console.log(`Update project, ${msg}`);
+      return msg;
+    } catch (err) {
+      console.warn(`Update project, ${err}`);
+      throw err;
+    }
+  }
+
+  export async function remove(name: string) {
+    await invoke("del_project", { name })
+      .then((msg) => console.log(`Delete project, ${msg}`))
+      .catch((err) => console.warn(`Delete project, ${err}`));
+  }
+
+  /** How many boards can be built from the current stock. */
+  export async function buildable(name: string): Promise<number> {
+    return invoke("get_buildable", { name });
+  }
+
+  export async function shortages(
+    name: string,
+    boards: number
+  ): Promise<ShortageInterface[]> {
+    return invoke("get_shortages", { name, boards });
+  }
+
+  /** Cost per board and in total, at the price breaks of the whole build. */
+  export async function cost(name: string, boards: number): Promise<BomCost> {
+    return invoke("get_project_cost", { name, boards });
+  }
+
+  export async function exportCost(
+    name: string,
+    boards: number,
+    path: string
+  ) {
+    await invoke("export_project_cost", { name, boards, path })
+      .then(() => console.log(`Export project cost, ${path}`))
+      .catch((err) => console.warn(`Export project cost, ${err}`));
+  }
+
+  /** Take the parts of the boards from the stock, all or nothing. */
+  export async function build(name: string, boards: number) {
+    try {
+      await invoke("build_project", { name, boards });
+    } catch (err) {
+      console.warn(`Build project, ${err}`);
+      throw err;
+    }
+  }
+
+  /** Match a KiCad BOM CSV or XML netlist, saved as a project if `save_as` is given. */
+  export async function importKicad(
+    path: string,
+    save_as?: string
+  ): Promise<BomImportReport> {
+    return invoke("import_kicad", { path, save_as });
+  }
+}
@@ -0,0 +1,25 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import {
+  BulkAddFormat,
+  ReorderGroup as ReorderGroupInterface,
+} from "@/types/reorder";
+
+export namespace DbReorder {
+  export type ReorderGroup = ReorderGroupInterface;
+
+  /** Parts at or below their minimum stock, grouped by supplier. */
+  export async function list(): Promise<ReorderGroupInterface[]> {
+    return invoke("get_reorder");
+  }
+
+  /** Write a CSV file for the bulk add of a distributor. */
+  export async function exportCsv(
+    path: string,
+    format: BulkAddFormat,
+    supplier?: string
+  ) {
+    await invoke("export_reorder", { path, supplier, format })
+      .then(() => console.log(`Export reorder list, ${path}`))
+      .catch((err) => console.warn(`Export reorder list, ${err}`));
+  }
+}
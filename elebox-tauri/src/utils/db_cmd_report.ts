@@ -0,0 +1,18 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import { Valuation as ValuationInterface } from "@/types/report";
+
+export namespace DbReport {
+  export type Valuation = ValuationInterface;
+
+  /** Value of the stock on hand by category. */
+  export async function valuation(): Promise<ValuationInterface> {
+    return invoke("get_valuation");
+  }
+
+  /** Write the value of each part as a CSV file. */
+  export async function exportValuation(path: string) {
+    await invoke("export_valuation", { path })
+      .then(() => console.log(`Export valuation, ${path}`))
+      .catch((err) => console.warn(`Export valuation, ${err}`));
+  }
+}
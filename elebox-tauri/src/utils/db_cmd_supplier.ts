@@ -0,0 +1,45 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import { DeletePolicy } from "@/types/common";
+import { Supplier as SupplierInterface } from "@/types/supplier";
+
+export namespace DbSupplier {
+  export type Supplier = SupplierInterface;
+
+  export async function get(name: string) {
+    return invoke("get_supplier", { name }).catch((err) =>
+      console.warn(`Get supplier, ${err}`)
+    );
+  }
+
+  export async function list(): Promise<SupplierInterface[]> {
+    return invoke("get_suppliers", {});
+  }
+
+  export async function add(item: SupplierInterface) {
+    try {
+      const msg = await invoke("add_supplier", { item });
+      console.log(`Add supplier, ${msg}`);
+      return msg;
+    } catch (err) {
+      console.warn(`Add supplier, ${err}`);
+      throw err;
+    }
+  }
+
+  export async function update(ori_name: string, new_item: SupplierInterface) {
+    try {
+      const msg = await invoke("update_supplier", { ori_name, new_item });
+      console.log(`Update supplier, ${msg}`);
+      return msg;
+    } catch (err) {
+      console.warn(`Update supplier, ${err}`);
+      throw err;
+    }
+  }
+
+  export async function remove(name: string, policy?: DeletePolicy) {
+    await invoke("del_supplier", { name, policy })
+      .then((msg) => console.log(`Delete supplier, ${msg}`))
+      .catch((err) => console.warn(`Delete supplier, ${err}`));
+  }
+}